@@ -1,16 +1,24 @@
 //! A collection of [`Getter`](utils::Getter) call sites.
 
+use log::debug;
 use rules::ReturnsBool;
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
-use utils::{prelude::*, Getter, GetterError};
+use std::{
+    collections::{HashMap, HashSet},
+    ops::RangeInclusive,
+    sync::{Arc, Mutex, MutexGuard},
+};
+use utils::{
+    getter, paste::Concat, prelude::*, Candidate, Getter, GetterError, NonGetterReason, OptOut,
+    Scope,
+};
 
-#[derive(Debug)]
-struct GetterCallCollectionInner(HashMap<usize, Vec<Getter>>);
-
-impl Default for GetterCallCollectionInner {
-    fn default() -> Self {
-        GetterCallCollectionInner(HashMap::new())
-    }
+#[derive(Debug, Default)]
+struct GetterCallCollectionInner {
+    getter_calls: HashMap<usize, Vec<Getter>>,
+    candidates: Vec<Candidate>,
+    opt_out: OptOut,
+    /// Names of the getters generated by derive attributes.
+    derived: HashSet<String>,
 }
 
 /// A collection of [`Getter`](utils::Getter) call sites.
@@ -19,15 +27,17 @@ impl Default for GetterCallCollectionInner {
 /// eligibles to be renamed.
 #[derive(Debug, Default)]
 pub struct GetterCallCollection {
-    inner: Rc<RefCell<GetterCallCollectionInner>>,
+    inner: Arc<Mutex<GetterCallCollectionInner>>,
     offset: usize,
+    is_opted_out: bool,
 }
 
 impl GetterCollection for GetterCallCollection {
     fn clone(this: &Self) -> Self {
         GetterCallCollection {
-            inner: Rc::clone(&this.inner),
+            inner: Arc::clone(&this.inner),
             offset: this.offset,
+            is_opted_out: this.is_opted_out,
         }
     }
 
@@ -52,18 +62,106 @@ impl GetterCallCollection {
         Getter::try_new(name, returns_bool, line + self.offset)
     }
 
-    pub fn add(&self, getter: Getter) {
-        let mut inner = self.inner.borrow_mut();
-        let getter_calls_same_line = inner.0.entry(getter.line).or_insert_with(Vec::new);
+    /// Sets the opt-out markers of the file.
+    pub fn set_opt_out(&self, opt_out: OptOut) {
+        self.inner().opt_out = opt_out;
+    }
+
+    /// Opts out the getter calls added via this view, e.g. for the items
+    /// nested in an opted out item.
+    pub fn set_opted_out(&mut self, is_opted_out: bool) {
+        self.is_opted_out = is_opted_out;
+    }
+
+    /// Sets the names of the getters generated by derive attributes.
+    pub fn set_derived(&self, derived: HashSet<String>) {
+        self.inner().derived = derived;
+    }
+
+    /// Returns `true` if the getter `name` is generated by a derive attribute.
+    pub fn is_derived(&self, name: &str) -> bool {
+        self.inner().derived.contains(name)
+    }
+
+    /// Returns `true` if one of the `lines` is targeted by an opt-out marker.
+    pub fn is_marked(&self, lines: RangeInclusive<usize>) -> bool {
+        self.inner().opt_out.is_marked(lines)
+    }
+
+    /// Returns `true` if the getter call found at `line` is opted out.
+    pub fn is_opted_out(&self, line: usize) -> bool {
+        self.is_opted_out || self.is_marked(line..=line)
+    }
+
+    /// Adds the `getter` call found in `scope`.
+    pub fn add(&self, mut getter: Getter, scope: &Scope) {
+        getter.scope = scope.clone();
+        let mut inner = self.inner();
+        inner.candidates.push(Candidate::renamed(&getter, scope));
+        let getter_calls_same_line = inner.getter_calls.entry(getter.line).or_default();
 
         getter_calls_same_line.push(getter);
     }
 
+    /// Skips the `getter` call found in `scope` for the given `reason`.
+    pub fn skip(&self, getter: &Getter, scope: &Scope, reason: NonGetterReason) {
+        getter::skip(scope, &getter.name, &reason, getter.line);
+        self.inner()
+            .candidates
+            .push(Candidate::skipped(getter, scope, reason));
+    }
+
+    /// Adds the getter call which name is generated by the `concat`enation
+    /// found at `line` in `scope`.
+    ///
+    /// The call is skipped for `reason` if provided.
+    pub fn add_generated(
+        &self,
+        concat: &Concat,
+        line: usize,
+        scope: &Scope,
+        reason: Option<NonGetterReason>,
+    ) {
+        let line = line + self.offset;
+        let reason = if self.is_opted_out(line) {
+            Some(NonGetterReason::OptedOut)
+        } else {
+            reason
+        };
+
+        // The definition is not known: fall back to the calls heuristics
+        let candidate = Candidate::generated(concat, line, scope, ReturnsBool::Maybe, reason);
+        match candidate.skip.as_ref() {
+            Some(reason) => getter::skip(scope, &candidate.name, reason, line),
+            None => debug!("* {} @ {}: generated {}", scope, line, candidate.name),
+        }
+        self.inner().candidates.push(candidate);
+    }
+
+    /// Rejects the function call found in `scope` which can't be renamed.
+    pub fn reject(&self, err: GetterError, scope: &Scope) {
+        err.log(scope);
+        if !err.err.is_not_get_fn() {
+            self.inner()
+                .candidates
+                .push(Candidate::rejected(&err, scope));
+        }
+    }
+
     pub fn get(&self, line_idx: usize) -> Option<Vec<Getter>> {
-        self.inner.borrow_mut().0.remove(&(line_idx + 1)) // convert line idx to line_nb
+        self.inner().getter_calls.remove(&(line_idx + 1)) // convert line idx to line_nb
+    }
+
+    /// Takes the candidates which were added, skipped or rejected so far.
+    pub fn take_candidates(&self) -> Vec<Candidate> {
+        std::mem::take(&mut self.inner().candidates)
     }
 
     pub fn is_empty(&self) -> bool {
-        self.inner.borrow().0.is_empty()
+        self.inner().getter_calls.is_empty()
+    }
+
+    fn inner(&self) -> MutexGuard<'_, GetterCallCollectionInner> {
+        self.inner.lock().expect("getter collection poisoned")
     }
 }
@@ -0,0 +1,186 @@
+//! Getters generated by derive attributes.
+//!
+//! Supports the `with_prefix` option of the `getset` attributes, e.g.
+//! `#[getset(get = "pub", with_prefix)]`, and the `rename` option of the
+//! `derive-getters` attributes, e.g. `#[getter(rename = "get_name")]`.
+//!
+//! The derive attributes are not rewritten by `fix-getters-calls`, so the
+//! calls to the getters they generate with a `get_` prefix are kept unchanged.
+
+use std::collections::HashSet;
+use syn::{
+    ext::IdentExt,
+    visit::{self, Visit},
+};
+
+const GET_PREFIX: &str = "get_";
+const WITH_PREFIX: &str = "with_prefix";
+
+/// Adds the names of the getters generated with a `get_` prefix by the derive
+/// attributes of the `syntax_tree` to `names`.
+pub fn collect(syntax_tree: &syn::File, names: &mut HashSet<String>) {
+    DerivedGetterCollector { names }.visit_file(syntax_tree);
+}
+
+struct DerivedGetterCollector<'a> {
+    names: &'a mut HashSet<String>,
+}
+
+impl<'a, 'ast> Visit<'ast> for DerivedGetterCollector<'a> {
+    fn visit_item_struct(&mut self, node: &'ast syn::ItemStruct) {
+        if let syn::Fields::Named(fields) = &node.fields {
+            let struct_getset = GetsetAttrs::parse(&node.attrs);
+            for field in fields.named.iter() {
+                let ident = match field.ident.as_ref() {
+                    Some(ident) => ident,
+                    None => continue,
+                };
+
+                for attr in field.attrs.iter() {
+                    self.collect_renamed(attr);
+                }
+
+                let field_getset = GetsetAttrs::parse(&field.attrs);
+                let has_prefix = if field_getset.is_skipped {
+                    false
+                } else if field_getset.has_getter {
+                    field_getset.has_prefix
+                } else {
+                    struct_getset.has_getter && struct_getset.has_prefix
+                };
+                if has_prefix {
+                    self.names
+                        .insert(format!("{}{}", GET_PREFIX, ident.unraw()));
+                }
+            }
+        }
+
+        visit::visit_item_struct(self, node);
+    }
+}
+
+impl<'a> DerivedGetterCollector<'a> {
+    /// Adds the name of the getter of a `derive-getters` `rename` option in the `attr`, if any.
+    fn collect_renamed(&mut self, attr: &syn::Attribute) {
+        if !attr.path.is_ident("getter") {
+            return;
+        }
+
+        if let Ok(syn::Meta::List(list)) = attr.parse_meta() {
+            for nested in list.nested.iter() {
+                match nested {
+                    syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {
+                        path,
+                        lit: syn::Lit::Str(lit),
+                        ..
+                    })) if path.is_ident("rename") && lit.value().starts_with(GET_PREFIX) => {
+                        self.names.insert(lit.value());
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+}
+
+/// The `getset` getters options found in some attributes.
+#[derive(Debug, Default)]
+struct GetsetAttrs {
+    has_getter: bool,
+    is_skipped: bool,
+    has_prefix: bool,
+}
+
+impl GetsetAttrs {
+    fn parse(attrs: &[syn::Attribute]) -> Self {
+        let mut getset = GetsetAttrs::default();
+        for attr in attrs {
+            match attr.parse_meta() {
+                Ok(syn::Meta::List(list)) if list.path.is_ident("getset") => {
+                    for nested in list.nested.iter() {
+                        match nested {
+                            syn::NestedMeta::Meta(syn::Meta::Path(path))
+                                if path.is_ident(WITH_PREFIX) =>
+                            {
+                                getset.has_prefix = true;
+                            }
+                            syn::NestedMeta::Meta(syn::Meta::Path(path))
+                                if path.is_ident("skip") =>
+                            {
+                                getset.is_skipped = true;
+                            }
+                            syn::NestedMeta::Meta(syn::Meta::NameValue(name_value)) => {
+                                getset.have_name_value(name_value);
+                            }
+                            _ => (),
+                        }
+                    }
+                }
+                // Legacy form, e.g. `#[get = "pub with_prefix"]`
+                Ok(syn::Meta::NameValue(name_value)) => getset.have_name_value(&name_value),
+                _ => (),
+            }
+        }
+
+        getset
+    }
+
+    fn have_name_value(&mut self, name_value: &syn::MetaNameValue) {
+        if !name_value.path.is_ident("get") && !name_value.path.is_ident("get_copy") {
+            return;
+        }
+        self.has_getter = true;
+
+        // Option in the visibility, e.g. `get = "pub with_prefix"`
+        if let syn::Lit::Str(lit) = &name_value.lit {
+            if lit
+                .value()
+                .split_whitespace()
+                .any(|word| word == WITH_PREFIX)
+            {
+                self.has_prefix = true;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derived_names() {
+        let syntax_tree = syn::parse_file(
+            r#"#[derive(Getters)]
+#[getset(get = "pub", with_prefix)]
+struct Foo {
+    a: u32,
+    #[getset(skip)]
+    b: u32,
+    #[getset(get = "pub")]
+    c: u32,
+    #[getter(rename = "get_renamed")]
+    d: u32,
+}
+
+#[derive(Getters)]
+struct Bar {
+    #[get_copy = "pub with_prefix"]
+    r#type: u64,
+    #[getset(get = "pub")]
+    kept: u64,
+    #[getter(rename = "other")]
+    other: u64,
+}
+"#,
+        )
+        .unwrap();
+
+        let mut names = HashSet::new();
+        collect(&syntax_tree, &mut names);
+
+        let mut names: Vec<String> = names.into_iter().collect();
+        names.sort();
+        assert_eq!(names, vec!["get_a", "get_d", "get_renamed", "get_type"]);
+    }
+}
@@ -3,6 +3,8 @@
 pub mod collection;
 pub use collection::GetterCallCollection;
 
+pub mod derive;
+
 pub mod syntax_tree;
 pub use syntax_tree::StGetterCallCollector;
 
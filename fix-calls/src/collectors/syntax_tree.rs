@@ -2,13 +2,9 @@
 //! renamable [`Getter`](utils::Getter) calls.
 
 use rules::ReturnsBool;
-use std::{
-    cell::{Ref, RefCell},
-    path::Path,
-    rc::Rc,
-};
+use std::{ops::RangeInclusive, path::Path};
 use syn::visit::{self, Visit};
-use utils::{getter, prelude::*, DocCodeGetterCollector, NonGetterReason, Scope};
+use utils::{opt_out, prelude::*, DocCodeGetterCollector, NonGetterReason, Scope, ScopeItem};
 
 use crate::{GetterCallCollection, TsGetterCallCollector};
 
@@ -16,10 +12,11 @@ use crate::{GetterCallCollection, TsGetterCallCollector};
 /// renamable [`Getter`](utils::Getter) calls.
 #[derive(Debug)]
 pub struct StGetterCallCollector<'path> {
-    scope_stack: Vec<Rc<RefCell<Scope>>>,
+    scope: Scope,
     getter_collection: GetterCallCollection,
     path: &'path Path,
     identification_mode: IdentificationMode,
+    is_opted_out: bool,
     doc_code_collector: DocCodeGetterCollector<TsGetterCallCollector<'path>>,
 }
 
@@ -37,11 +34,12 @@ impl<'path> SyntaxTreeGetterCollector for StGetterCallCollector<'path> {
             doc_code_collector: DocCodeGetterCollector::<TsGetterCallCollector>::new(
                 path,
                 identification_mode,
-                &getter_collection,
+                getter_collection,
             ),
             path,
             identification_mode,
-            scope_stack: Vec::new(),
+            scope: Scope::from_file(path),
+            is_opted_out: false,
         };
         visitor.visit_file(syntax_tree);
     }
@@ -59,25 +57,37 @@ impl<'path> StGetterCallCollector<'path> {
         let getter = match res {
             Ok(getter) => getter,
             Err(err) => {
-                err.log(&self.scope());
+                self.getter_collection.reject(err, self.scope());
                 return;
             }
         };
 
+        if self.getter_collection.is_opted_out(getter.line) {
+            self.getter_collection.skip(&getter, self.scope(), OptedOut);
+            return;
+        }
+
+        if self.getter_collection.is_derived(&getter.name) {
+            self.getter_collection.skip(&getter, self.scope(), Derived);
+            return;
+        }
+
         if !getter.returns_bool().is_true() && self.identification_mode.is_conservative() {
             if method_call.turbofish.is_some() {
-                getter::skip(&self.scope(), &getter.name, &GenericTypeParam, getter.line);
+                self.getter_collection
+                    .skip(&getter, self.scope(), GenericTypeParam);
                 return;
             }
 
             if !method_call.args.is_empty() {
-                getter::skip(&self.scope(), &getter.name, &MultipleArgs, getter.line);
+                self.getter_collection
+                    .skip(&getter, self.scope(), MultipleArgs);
                 return;
             }
         }
 
-        getter.log(self.path, &self.scope());
-        self.getter_collection.add(getter);
+        getter.log(self.path, self.scope());
+        self.getter_collection.add(getter, self.scope());
     }
 
     fn process_fn_call(&mut self, fn_call: &syn::ExprCall) {
@@ -93,41 +103,93 @@ impl<'path> StGetterCallCollector<'path> {
                 let getter = match res {
                     Ok(getter) => getter,
                     Err(err) => {
-                        err.log(&self.scope());
+                        self.getter_collection.reject(err, self.scope());
                         return;
                     }
                 };
 
+                if self.getter_collection.is_opted_out(getter.line) {
+                    self.getter_collection.skip(&getter, self.scope(), OptedOut);
+                    return;
+                }
+
                 if !getter.returns_bool().is_true() && self.identification_mode.is_conservative() {
-                    getter::skip(&self.scope(), &getter.name, &NotAMethod, getter.line);
+                    self.getter_collection
+                        .skip(&getter, self.scope(), NotAMethod);
                     return;
                 }
 
-                getter.log(self.path, &self.scope());
-                self.getter_collection.add(getter);
+                getter.log(self.path, self.scope());
+                self.getter_collection.add(getter, self.scope());
             }
         }
     }
 
-    fn scope(&self) -> Ref<Scope> {
-        self.scope_stack.last().expect("empty scope stack").borrow()
+    /// Opts out the item with the given `attrs` if it is marked.
+    ///
+    /// `lines` are the lines from the first attribute of the item to its name.
+    /// Returns the previous opt-out state, to be restored when leaving the item.
+    fn enter_item(
+        &mut self,
+        attrs: &[syn::Attribute],
+        lines: Option<RangeInclusive<usize>>,
+    ) -> bool {
+        let was_opted_out = self.is_opted_out;
+        if opt_out::has_skip_attribute(attrs)
+            || lines.is_some_and(|lines| self.getter_collection.is_marked(lines))
+        {
+            self.is_opted_out = true;
+            self.getter_collection.set_opted_out(true);
+        }
+
+        was_opted_out
+    }
+
+    fn leave_item(&mut self, was_opted_out: bool) {
+        self.is_opted_out = was_opted_out;
+        self.getter_collection.set_opted_out(was_opted_out);
     }
 
-    fn push_scope(&mut self, scope: impl Into<Scope>) {
-        let scope = scope.into().into();
-        self.scope_stack.push(scope);
+    fn scope(&self) -> &Scope {
+        &self.scope
+    }
+
+    fn push_scope(&mut self, item: impl Into<ScopeItem>) {
+        self.scope.push(item);
     }
 
     fn pop_scope(&mut self) {
-        self.scope_stack.pop();
+        self.scope.pop();
     }
 }
 
 impl<'ast, 'path> Visit<'ast> for StGetterCallCollector<'path> {
     fn visit_item(&mut self, node: &'ast syn::Item) {
+        let was_opted_out = self.enter_item(opt_out::item_attrs(node), opt_out::item_lines(node));
+
         self.push_scope(node);
         visit::visit_item(self, node);
         self.pop_scope();
+
+        self.leave_item(was_opted_out);
+    }
+
+    fn visit_impl_item_method(&mut self, node: &'ast syn::ImplItemMethod) {
+        let lines = opt_out::attrs_lines(&node.attrs, node.sig.ident.span().start().line);
+        let was_opted_out = self.enter_item(&node.attrs, Some(lines));
+        self.push_scope(ScopeItem::Fn(node.sig.ident.to_string()));
+        visit::visit_impl_item_method(self, node);
+        self.pop_scope();
+        self.leave_item(was_opted_out);
+    }
+
+    fn visit_trait_item_method(&mut self, node: &'ast syn::TraitItemMethod) {
+        let lines = opt_out::attrs_lines(&node.attrs, node.sig.ident.span().start().line);
+        let was_opted_out = self.enter_item(&node.attrs, Some(lines));
+        self.push_scope(ScopeItem::Fn(node.sig.ident.to_string()));
+        visit::visit_trait_item_method(self, node);
+        self.pop_scope();
+        self.leave_item(was_opted_out);
     }
 
     fn visit_expr_method_call(&mut self, node: &'ast syn::ExprMethodCall) {
@@ -140,11 +202,23 @@ impl<'ast, 'path> Visit<'ast> for StGetterCallCollector<'path> {
         visit::visit_expr_call(self, node);
     }
 
+    fn visit_impl_item_const(&mut self, node: &'ast syn::ImplItemConst) {
+        self.push_scope(ScopeItem::Const(node.ident.to_string()));
+        visit::visit_impl_item_const(self, node);
+        self.pop_scope();
+    }
+
+    fn visit_trait_item_const(&mut self, node: &'ast syn::TraitItemConst) {
+        self.push_scope(ScopeItem::Const(node.ident.to_string()));
+        visit::visit_trait_item_const(self, node);
+        self.pop_scope();
+    }
+
     fn visit_macro(&mut self, node: &'ast syn::Macro) {
         self.push_scope(node);
         TsGetterCallCollector::collect(
             self.path,
-            &self.scope(),
+            self.scope(),
             &node.tokens,
             self.identification_mode,
             &self.getter_collection,
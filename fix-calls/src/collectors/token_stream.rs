@@ -7,7 +7,7 @@ use std::path::Path;
 use syn::buffer::{Cursor, TokenBuffer};
 
 use rules::ReturnsBool;
-use utils::{getter, prelude::*, Getter, NonGetterReason, Scope};
+use utils::{paste::Concat, prelude::*, Getter, NonGetterReason, Scope};
 
 use crate::GetterCallCollection;
 
@@ -109,18 +109,25 @@ impl<'scope> TsGetterCallCollector<'scope> {
                     }
                 }
                 TokenTree::Group(group) => {
-                    if let State::MaybeNamedFn(mut maybe) | State::ParamList(mut maybe) =
-                        self.state.take()
-                    {
-                        if let Delimiter::Parenthesis = group.delimiter() {
-                            if group.stream().is_empty() {
-                                // found `()` after a getter call
-                                self.process_maybe_getter(maybe);
-                            } else {
-                                maybe.has_multiple_args = true;
-                                self.process_maybe_getter(maybe);
+                    match self.state.take() {
+                        State::MaybeNamedFn(mut maybe) | State::ParamList(mut maybe) => {
+                            if let Delimiter::Parenthesis = group.delimiter() {
+                                if group.stream().is_empty() {
+                                    // found `()` after a getter call
+                                    self.process_maybe_getter(maybe);
+                                } else {
+                                    maybe.has_multiple_args = true;
+                                    self.process_maybe_getter(maybe);
+                                }
+                            }
+                        }
+                        State::Dot => {
+                            // `paste!`-style concatenation, e.g. `.[<get_ $field>]()`
+                            if let Some(concat) = Concat::parse(&group) {
+                                self.process_generated(&concat, group.span().start().line);
                             }
                         }
+                        _ => (),
                     }
 
                     if !group.stream().is_empty() {
@@ -137,46 +144,59 @@ impl<'scope> TsGetterCallCollector<'scope> {
         }
     }
 
+    fn process_generated(&self, concat: &Concat, line: usize) {
+        // The definition is not known, so the name can't be checked
+        let reason = if self.identification_mode.is_conservative() {
+            Some(NonGetterReason::Generated)
+        } else {
+            None
+        };
+
+        self.getter_collection
+            .add_generated(concat, line, self.scope, reason);
+    }
+
     fn process_maybe_getter(&mut self, maybe: MaybeGetter) {
         use NonGetterReason::*;
 
+        if self.getter_collection.is_opted_out(maybe.getter.line) {
+            self.getter_collection
+                .skip(&maybe.getter, self.scope, OptedOut);
+            return;
+        }
+
+        if self.getter_collection.is_derived(&maybe.getter.name) {
+            self.getter_collection
+                .skip(&maybe.getter, self.scope, Derived);
+            return;
+        }
+
         if !maybe.getter.returns_bool().is_true() && self.identification_mode.is_conservative() {
             // not a bool getter
             if maybe.has_no_args {
-                getter::skip(self.scope, &maybe.getter.name, &NoArgs, maybe.getter.line);
+                self.getter_collection
+                    .skip(&maybe.getter, self.scope, NoArgs);
                 return;
             }
             if !maybe.is_method {
-                getter::skip(
-                    self.scope,
-                    &maybe.getter.name,
-                    &NotAMethod,
-                    maybe.getter.line,
-                );
+                self.getter_collection
+                    .skip(&maybe.getter, self.scope, NotAMethod);
                 return;
             }
             if maybe.has_gen_params {
-                getter::skip(
-                    self.scope,
-                    &maybe.getter.name,
-                    &GenericTypeParam,
-                    maybe.getter.line,
-                );
+                self.getter_collection
+                    .skip(&maybe.getter, self.scope, GenericTypeParam);
                 return;
             }
             if maybe.has_multiple_args {
-                getter::skip(
-                    self.scope,
-                    &maybe.getter.name,
-                    &MultipleArgs,
-                    maybe.getter.line,
-                );
+                self.getter_collection
+                    .skip(&maybe.getter, self.scope, MultipleArgs);
                 return;
             }
         }
 
         maybe.getter.log(self.path, self.scope);
-        self.getter_collection.add(maybe.getter);
+        self.getter_collection.add(maybe.getter, self.scope);
     }
 
     fn try_new_maybe_named_fn(&mut self, ident: &syn::Ident, is_method: bool) -> State {
@@ -195,7 +215,7 @@ impl<'scope> TsGetterCallCollector<'scope> {
             }),
             Err(err) => {
                 if is_method {
-                    err.log(self.scope);
+                    self.getter_collection.reject(err, self.scope);
                 }
                 State::None
             }
@@ -212,8 +232,9 @@ struct MaybeGetter {
     has_no_args: bool,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 enum State {
+    #[default]
     None,
     Dot,
     MaybeNamedFn(MaybeGetter),
@@ -227,9 +248,3 @@ impl State {
         std::mem::replace(self, State::None)
     }
 }
-
-impl Default for State {
-    fn default() -> Self {
-        State::None
-    }
-}
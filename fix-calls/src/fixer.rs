@@ -1,103 +1,218 @@
 //! Rust source file level getter calls fixer.
 
-use std::borrow::Cow;
-use std::fs;
-use std::io::Write;
-use std::path::{Path, PathBuf};
+use log::debug;
+use std::{
+    collections::HashSet,
+    fs,
+    path::Path,
+    sync::{Arc, Mutex, MutexGuard},
+};
 
-use utils::{prelude::*, Error, ParseFileError};
+use utils::{
+    markdown, paste, prelude::*, Candidate, DocCodeGetterCollector, Edit, Error, OptOut,
+    ParseFileError, Report,
+};
 
-use crate::{GetterCallCollection, StGetterCallCollector};
+use crate::{derive, GetterCallCollection, StGetterCallCollector, TsGetterCallCollector};
 
 /// Rust source file level getter calls fixer.
+///
+/// The calls to the getters generated by derive attributes are kept unchanged,
+/// since the attributes are not rewritten. The attributes are collected from
+/// the files processed so far, or from all the files after a
+/// [scan](GetterCallFixer::scan).
 pub struct GetterCallFixer {
     identification_mode: IdentificationMode,
+    report: Option<Arc<Report>>,
+    /// Names of the getters generated by derive attributes.
+    derived: Mutex<HashSet<String>>,
 }
 
 impl GetterCallFixer {
     pub fn new(identification_mode: IdentificationMode) -> Self {
         GetterCallFixer {
             identification_mode,
+            report: None,
+            derived: Mutex::new(HashSet::new()),
         }
     }
+
+    /// Adds the renaming candidates of each processed file to the `report`.
+    pub fn with_report(mut self, report: Arc<Report>) -> Self {
+        self.report = Some(report);
+        self
+    }
+
+    /// Scans the Rust files from `path`, collecting the getters generated by
+    /// derive attributes.
+    ///
+    /// The files are selected according to the `settings`.
+    pub fn scan(&self, path: &Path, settings: &TraversalSettings) -> Result<(), Error> {
+        let settings = TraversalSettings {
+            action: Action::Scan,
+            ..*settings
+        };
+        self.traverse(path, &settings, &None)?;
+
+        debug!("{} derived getter name(s) found", self.derived().len());
+
+        Ok(())
+    }
+
+    fn derived(&self) -> MutexGuard<'_, HashSet<String>> {
+        self.derived.lock().expect("derived getters poisoned")
+    }
 }
 
-impl CrateTraverser for GetterCallFixer {
-    /// Fixes the file at the given path.
+impl GetterCallFixer {
+    /// Collects the getter call candidates in the `syntax_tree` parsed from `source_code`.
     ///
-    /// If `output_path` is specified, the result will be written there,
-    /// otherwise the input files are overwritten.
-    fn handle_rust_file(
-        &mut self,
+    /// Skipped candidates are included: use [`Candidate::is_skipped`] to filter them.
+    pub fn collect_candidates(
+        &self,
         path: &Path,
-        output_path: &Option<PathBuf>,
-    ) -> Result<(), Error> {
-        // Analyze Rust file
-        let source_code =
-            fs::read_to_string(path).map_err(|err| Error::ReadFile(path.to_owned(), err))?;
-        let syntax_tree = match syn::parse_file(&source_code) {
-            Ok(syntax_tree) => syntax_tree,
-            Err(error) => {
-                return Err(ParseFileError::new(error, path.to_owned(), source_code).into());
-            }
-        };
+        source_code: &str,
+        syntax_tree: &syn::File,
+    ) -> Vec<Candidate> {
+        derive::collect(syntax_tree, &mut self.derived());
 
         let getter_collection = GetterCallCollection::default();
+        getter_collection.set_opt_out(OptOut::new(source_code));
+        getter_collection.set_derived(self.derived().clone());
         StGetterCallCollector::collect(
             path,
-            &syntax_tree,
+            syntax_tree,
             self.identification_mode,
             &getter_collection,
         );
 
-        let output_path = match output_path {
-            Some(output_path) => output_path,
-            None => path,
+        getter_collection.take_candidates()
+    }
+
+    /// Collects the getter call candidates in the Rust code blocks of the
+    /// Markdown `source_code`.
+    ///
+    /// Skipped candidates are included: use [`Candidate::is_skipped`] to filter them.
+    pub fn collect_markdown_candidates(&self, path: &Path, source_code: &str) -> Vec<Candidate> {
+        let getter_collection = GetterCallCollection::default();
+        getter_collection.set_opt_out(OptOut::new(source_code));
+        getter_collection.set_derived(self.derived().clone());
+        DocCodeGetterCollector::<TsGetterCallCollector>::new(
+            path,
+            self.identification_mode,
+            &getter_collection,
+        )
+        .have_markdown(source_code);
+
+        getter_collection.take_candidates()
+    }
+}
+
+impl CrateTraverser for GetterCallFixer {
+    /// Collects the edits renaming the getter calls in `source_code`.
+    fn collect_edits(&self, path: &Path, source_code: &str) -> Result<Vec<Edit>, Error> {
+        let candidates = if markdown::is_markdown(path) {
+            self.collect_markdown_candidates(path, source_code)
+        } else {
+            // Analyze Rust code
+            let syntax_tree = match syn::parse_file(source_code) {
+                Ok(syntax_tree) => syntax_tree,
+                Err(error) => {
+                    return Err(ParseFileError::new(
+                        error,
+                        path.to_owned(),
+                        source_code.to_string(),
+                    )
+                    .into());
+                }
+            };
+
+            self.collect_candidates(path, source_code, &syntax_tree)
         };
 
-        if getter_collection.is_empty() {
-            // Nothing to do for this file
+        let mut edits = Vec::new();
+        rename_calls(
+            source_code,
+            candidates
+                .iter()
+                .filter(|candidate| !candidate.is_skipped()),
+            &mut edits,
+        );
+
+        if let Some(report) = self.report.as_ref() {
+            report.add(path, source_code, candidates);
+        }
+
+        Ok(edits)
+    }
+
+    /// Collects the getters generated by derive attributes in the Rust file at `path`.
+    fn scan_rust_file(&self, path: &Path) -> Result<(), Error> {
+        if markdown::is_markdown(path) {
             return Ok(());
         }
 
-        // Write result
-        let f =
-            fs::File::create(output_path).map_err(|err| Error::WriteFile(path.to_owned(), err))?;
-        let mut writer = std::io::BufWriter::new(f);
-
-        for (line_idx, line) in source_code.lines().enumerate() {
-            if let Some(getter_calls) = getter_collection.get(line_idx) {
-                let mut line = Cow::from(line);
-                for getter_call in getter_calls {
-                    line = Cow::from(line.replacen(
-                        &getter_call.name,
-                        getter_call.new_name.as_str(),
-                        1,
-                    ));
-                }
-                writer
-                    .write(line.as_bytes())
-                    .map_err(|err| Error::WriteFile(path.to_owned(), err))?;
-            } else {
-                // No changes for this line
-                writer
-                    .write(line.as_bytes())
-                    .map_err(|err| Error::WriteFile(path.to_owned(), err))?;
+        let source_code =
+            fs::read_to_string(path).map_err(|err| Error::ReadFile(path.to_owned(), err))?;
+
+        let syntax_tree = match syn::parse_file(&source_code) {
+            Ok(syntax_tree) => syntax_tree,
+            Err(error) => {
+                return Err(ParseFileError::new(error, path.to_owned(), source_code).into());
             }
+        };
+        derive::collect(&syntax_tree, &mut self.derived());
 
-            writer
-                .write(&[b'\n'])
-                .map_err(|err| Error::WriteFile(path.to_owned(), err))?;
+        Ok(())
+    }
+}
+
+/// Adds the edits renaming the getter `calls` in `source_code` to `edits`.
+///
+/// Each call renames the first occurrence of its name on its line which
+/// doesn't overlap one of the `edits`. Calls which can't be renamed are ignored.
+pub fn rename_calls<'a>(
+    source_code: &str,
+    calls: impl IntoIterator<Item = &'a Candidate>,
+    edits: &mut Vec<Edit>,
+) {
+    let mut calls: Vec<&Candidate> = calls.into_iter().collect();
+    if calls.is_empty() {
+        // Nothing to do for this code
+        return;
+    }
+    calls.sort_by_key(|call| call.line);
+
+    let mut calls = calls.into_iter().peekable();
+    let mut offset = 0;
+    for (line_idx, line) in source_code.split_inclusive('\n').enumerate() {
+        while let Some(call) = calls.next_if(|call| call.line <= line_idx + 1) {
+            let renaming = match call.renaming.as_ref() {
+                Some(renaming) if call.line == line_idx + 1 => renaming,
+                _ => continue,
+            };
+
+            // Rename the first occurrence not already renamed
+            let range = paste::match_ranges(line, &call.name)
+                .into_iter()
+                .map(|range| offset + range.start..offset + range.end)
+                .find(|range| !edits.iter().any(|edit| edit.overlaps(range)));
+
+            if let Some(range) = range {
+                edits.push(Edit::rename(range, renaming.new_name.as_str(), &call.scope));
+            }
         }
 
-        Ok(())
+        offset += line.len();
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::env;
+    use rules::dir_entry::{DefaultDirEntryFilter, Glob};
+    use std::{fs, path::PathBuf};
+    use utils::test_utils::{temp_dir, write};
 
     fn fix_baseline(id_mode: IdentificationMode) {
         let input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
@@ -110,12 +225,17 @@ mod tests {
             "all_get_functions"
         };
 
-        let output_path = env::temp_dir().join("fix-calls").join(file_id);
-        fs::create_dir_all(&output_path).unwrap();
+        let output_path = temp_dir(env!("CARGO_PKG_NAME"), file_id);
         let output_file = output_path.clone().join("baseline.rs");
 
-        let mut fixer = GetterCallFixer::new(id_mode);
-        fixer.traverse(&input_path, &Some(output_path)).unwrap();
+        let fixer = GetterCallFixer::new(id_mode);
+        fixer
+            .traverse(
+                &input_path,
+                &TraversalSettings::new(&DefaultDirEntryFilter),
+                &Some(output_path),
+            )
+            .unwrap();
 
         let output = fs::read_to_string(&output_file).unwrap();
 
@@ -145,4 +265,85 @@ mod tests {
     fn fix_baseline_all_get_functions() {
         fix_baseline(IdentificationMode::AllGetFunctions)
     }
+
+    #[test]
+    fn fix_stream() {
+        let input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("test_samples")
+            .join("input")
+            .join("baseline.rs");
+        let input = fs::read(&input_path).unwrap();
+
+        let fixer = GetterCallFixer::new(IdentificationMode::AllGetFunctions);
+        let mut output = Vec::new();
+        fixer
+            .fix_stream(&input_path, &mut input.as_slice(), &mut output)
+            .unwrap();
+
+        let expected_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("test_samples")
+            .join("expected")
+            .join("all_get_functions.rs");
+        let expected = fs::read_to_string(&expected_path).unwrap();
+
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+    }
+
+    #[test]
+    fn fix_markdown() {
+        let input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("test_samples")
+            .join("input");
+
+        let output_path = temp_dir(env!("CARGO_PKG_NAME"), "markdown");
+
+        let markdown = [Glob::new("*.md")];
+        let settings = TraversalSettings {
+            markdown: &markdown,
+            ..TraversalSettings::new(&DefaultDirEntryFilter)
+        };
+        let fixer = GetterCallFixer::new(IdentificationMode::AllGetFunctions);
+        fixer
+            .traverse(&input_path, &settings, &Some(output_path.clone()))
+            .unwrap();
+
+        let output = fs::read_to_string(output_path.join("README.md")).unwrap();
+
+        let expected_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("test_samples")
+            .join("expected")
+            .join("README.md");
+        let expected = fs::read_to_string(&expected_path).unwrap();
+
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn derived_getters() {
+        let root = temp_dir(env!("CARGO_PKG_NAME"), "derived_getters");
+        let call_path = root.join("src").join("a.rs");
+        write(
+            &call_path,
+            "fn f(foo: &Foo) {\n    foo.get_name();\n    foo.get_size();\n}\n",
+        );
+        write(
+            &root.join("src").join("lib.rs"),
+            "#[derive(Getters)]\n\
+             #[getset(get = \"pub\", with_prefix)]\n\
+             struct Foo {\n    \
+                 name: String,\n\
+             }\n",
+        );
+
+        let fixer = GetterCallFixer::new(IdentificationMode::AllGetFunctions);
+        fixer
+            .scan(&root, &TraversalSettings::new(&DefaultDirEntryFilter))
+            .unwrap();
+
+        let source_code = fs::read_to_string(&call_path).unwrap();
+        assert_eq!(
+            fixer.fix(&call_path, &source_code).unwrap().unwrap(),
+            "fn f(foo: &Foo) {\n    foo.get_name();\n    foo.size();\n}\n",
+        );
+    }
 }
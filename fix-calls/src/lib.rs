@@ -0,0 +1,9 @@
+//! Getter calls fixer.
+//!
+//! Removes the `get` prefix from the eligible getter call sites.
+
+mod fixer;
+pub use fixer::{rename_calls, GetterCallFixer};
+
+mod collectors;
+pub use collectors::*;
@@ -1,92 +1,35 @@
-mod fixer;
-use fixer::GetterCallFixer;
-
-mod collectors;
-pub use collectors::*;
-
-use log::{error, info};
-use std::{path::PathBuf, process};
-use utils::prelude::*;
+use fix_getters_calls::GetterCallFixer;
+use log::info;
+use std::{process, sync::Arc};
+use utils::cli::{common_args, init_logger, path_args, Options};
 
 fn main() {
     let m = clap::App::new(clap::crate_name!())
         .version(clap::crate_version!())
         .author(clap::crate_authors!())
         .about(clap::crate_description!())
-        .arg(
-            clap::Arg::with_name("conservative")
-                .short("c")
-                .long("conservative")
-                .help("Be conservative when selecting getter functions"),
-        )
-        .arg(
-            clap::Arg::with_name("quiet")
-                .short("q")
-                .long("quiet")
-                .help("Run silently"),
-        )
-        .arg(
-            clap::Arg::with_name("verbose")
-                .short("v")
-                .long("verbose")
-                .help("Show detailed logs"),
-        )
-        .arg(
-            clap::Arg::with_name("PATH")
-                .help("Crate or workspace root path (default: current directory)"),
-        )
-        .arg(clap::Arg::with_name("OUTPUT").help("Output to a different root path"))
+        .args(&common_args())
+        .args(&path_args())
         .get_matches();
 
-    stderrlog::new()
-        .verbosity(if m.is_present("verbose") {
-            5
-        } else if m.is_present("quiet") {
-            1
-        } else {
-            2
-        })
-        .init()
-        .unwrap();
+    init_logger(&m);
+    let options = Options::from_matches(&m);
 
-    let path: PathBuf = match m.value_of("PATH") {
-        Some(path) => path.into(),
-        None => PathBuf::from("."),
+    // Traverse the given crate tree following the rules defined by the filter
+    // and apply `fix` on elligible files.
+    let fixer = GetterCallFixer::new(options.identification_mode);
+    let fixer = match options.report.as_ref() {
+        Some(report) => fixer.with_report(Arc::clone(report)),
+        None => fixer,
     };
 
-    if !path.exists() {
-        error!(
-            "path not found {}",
-            path.to_str().expect("was a &str initially")
-        );
-        process::exit(1);
-    }
-
-    let output_path: Option<PathBuf> = if let Some(output) = m.value_of("OUTPUT") {
-        let output_path: PathBuf = output.to_string().into();
-        if !output_path.exists() {
-            error!(
-                "output path not found {}",
-                output_path.to_str().expect("was a &str initially")
-            );
+    // Standard streams are processed on the fly
+    if !options.use_stdio() {
+        info!("Collecting derived getters {:?}", options.path);
+        if let Err(error) = fixer.scan(&options.path, &options.settings()) {
+            options.log_error(&error);
             process::exit(1);
         }
-        Some(output_path)
-    } else {
-        None
-    };
-
-    // Traverse the given crate tree following the rules defined in crate `rules`
-    // and apply `fix` on elligible files.
-    let mut fixer = GetterCallFixer::new(if m.is_present("conservative") {
-        IdentificationMode::Conservative
-    } else {
-        IdentificationMode::AllGetFunctions
-    });
-    info!("Processing {:?}", path);
-    if let Err(error) = fixer.traverse(&path, &output_path) {
-        let _ = error!("{}", error);
-        process::exit(1);
     }
-    info!("Done {:?}", path);
+    options.run(fixer);
 }
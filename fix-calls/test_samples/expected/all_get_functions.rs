@@ -174,3 +174,63 @@ impl MyType {
         true
     }
 }
+
+fn opted_out() {
+    let my_struct = MyStruct::new();
+
+    // fix-getters: skip
+    let _ = my_struct.get_opted_out();
+    let _ = my_struct.get_opted_out_trailing(); // fix-getters: skip
+}
+
+/// ```no_run
+/// let _ = my_struct.no_run();
+/// ```
+///
+/// ```rust,should_panic
+/// # let my_struct = MyStruct::new();
+/// let _ = my_struct.should_panic();
+/// ```
+///
+/// ~~~edition2021
+/// let _ = my_struct.tilde();
+/// ~~~
+///
+/// ```text
+/// let _ = my_struct.get_text();
+/// ```
+fn doc_fences() {}
+
+/// ```
+/// # use my_crate::MyStruct;
+/// #[derive(Debug)]
+/// struct Wrapper(MyStruct);
+/// #
+/// # fn main() {
+/// # let my_struct = MyStruct::new();
+/// # let _ = my_struct.hidden();
+/// let _ = Wrapper(my_struct).0.shown();
+/// # }
+/// ```
+fn doc_hidden_lines() {}
+
+/**
+ * ```ignore
+ * let _ = my_struct.block();
+ * ```
+ */
+fn doc_block() {}
+
+#[doc = "```
+let _ = my_struct.literal();
+```"]
+fn doc_literal() {}
+
+macro_rules! call_generated_getters (
+    ($self: expr, $field:ident) => ({
+        paste::paste! {
+            let _ = $self.[<$field>]();
+            let _ = $self.[< get_$field _mut >]();
+        }
+    })
+);
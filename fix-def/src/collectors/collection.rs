@@ -1,17 +1,55 @@
 //! A collection of [`GetterDef`](crate::GetterDef)s.
 
+use log::debug;
+use proc_macro2::LineColumn;
 use rules::ReturnsBool;
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
-use utils::{prelude::*, GetterError};
+use std::{
+    collections::HashMap,
+    ops::RangeInclusive,
+    sync::{Arc, Mutex, MutexGuard},
+};
+use utils::{
+    getter, paste::Concat, prelude::*, Candidate, Getter, GetterError, NonGetterReason, OptOut,
+    Scope,
+};
 
 use crate::GetterDef;
 
-#[derive(Debug)]
-struct GetterDefCollectionInner(HashMap<usize, GetterDef>);
+#[derive(Debug, Default)]
+struct GetterDefCollectionInner {
+    getter_defs: HashMap<usize, GetterDef>,
+    /// Renamed getters generated by a concatenation, by line.
+    generated: HashMap<usize, Vec<Candidate>>,
+    /// Edits of the derive attributes generating getters.
+    attr_edits: Vec<AttrEdit>,
+    candidates: Vec<Candidate>,
+    opt_out: OptOut,
+}
 
-impl Default for GetterDefCollectionInner {
-    fn default() -> Self {
-        GetterDefCollectionInner(HashMap::new())
+/// An edit of a derive attribute, located by the positions of the parsed code.
+#[derive(Debug, PartialEq)]
+pub struct AttrEdit {
+    pub start: LineColumn,
+    pub end: LineColumn,
+    pub text: String,
+    /// The scope of the struct.
+    pub scope: Scope,
+    /// The original & the new names of the getters generated after the edit.
+    pub getters: Vec<(String, String)>,
+}
+
+impl AttrEdit {
+    /// Builds an [`AttrEdit`] replacing the code from `start` to `end` with `text`.
+    ///
+    /// The scope & the getters are defined once the getters are known.
+    pub fn new(start: LineColumn, end: LineColumn, text: impl Into<String>) -> Self {
+        AttrEdit {
+            start,
+            end,
+            text: text.into(),
+            scope: Scope::default(),
+            getters: Vec::new(),
+        }
     }
 }
 
@@ -21,17 +59,19 @@ impl Default for GetterDefCollectionInner {
 /// eligibles to be renamed.
 #[derive(Debug, Default)]
 pub struct GetterDefCollection {
-    inner: Rc<RefCell<GetterDefCollectionInner>>,
+    inner: Arc<Mutex<GetterDefCollectionInner>>,
     offset: usize,
     blocks_doc_alias: bool,
+    is_opted_out: bool,
 }
 
 impl GetterCollection for GetterDefCollection {
     fn clone(this: &Self) -> Self {
         GetterDefCollection {
-            inner: Rc::clone(&this.inner),
+            inner: Arc::clone(&this.inner),
             offset: this.offset,
             blocks_doc_alias: this.blocks_doc_alias,
+            is_opted_out: this.is_opted_out,
         }
     }
 
@@ -58,24 +98,144 @@ impl GetterDefCollection {
         GetterDef::try_new(name, returns_bool, line + self.offset)
     }
 
-    pub fn add(&self, getter_def: GetterDef) {
+    /// Sets the opt-out markers of the file.
+    pub fn set_opt_out(&self, opt_out: OptOut) {
+        self.inner().opt_out = opt_out;
+    }
+
+    /// Opts out the getters added via this view, e.g. for the items nested
+    /// in an opted out item.
+    pub fn set_opted_out(&mut self, is_opted_out: bool) {
+        self.is_opted_out = is_opted_out;
+    }
+
+    /// Returns `true` if one of the `lines` is targeted by an opt-out marker.
+    pub fn is_marked(&self, lines: RangeInclusive<usize>) -> bool {
+        self.inner().opt_out.is_marked(lines)
+    }
+
+    /// Returns `true` if the getter found at `line` is opted out.
+    pub fn is_opted_out(&self, line: usize) -> bool {
+        self.is_opted_out || self.is_marked(line..=line)
+    }
+
+    /// Adds the `getter_def` found in `scope`.
+    pub fn add(&self, mut getter_def: GetterDef, scope: &Scope) {
+        getter_def.set_scope(scope);
         let line_idx = getter_def.line();
-        if self
-            .inner
-            .borrow_mut()
-            .0
-            .insert(line_idx, getter_def)
-            .is_some()
-        {
+        let mut inner = self.inner();
+        inner
+            .candidates
+            .push(Candidate::renamed(getter_def.getter(), scope));
+        if inner.getter_defs.insert(line_idx, getter_def).is_some() {
             panic!("Found more than one getter definition @ {}", line_idx + 1);
         }
     }
 
+    /// Skips the `getter_def` found in `scope` for the given `reason`.
+    pub fn skip(&self, getter_def: &GetterDef, scope: &Scope, reason: NonGetterReason) {
+        getter::skip(scope, getter_def.name(), &reason, getter_def.line());
+        self.inner()
+            .candidates
+            .push(Candidate::skipped(getter_def.getter(), scope, reason));
+    }
+
+    /// Adds the getter definition which name is generated by the `concat`enation
+    /// found at `line` in `scope`, depending on whether it `returns_bool`.
+    ///
+    /// The definition is skipped for `reason` if provided.
+    pub fn add_generated(
+        &self,
+        concat: &Concat,
+        line: usize,
+        scope: &Scope,
+        returns_bool: ReturnsBool,
+        reason: Option<NonGetterReason>,
+    ) {
+        let line = line + self.offset;
+        let reason = if self.is_opted_out(line) {
+            Some(NonGetterReason::OptedOut)
+        } else {
+            reason
+        };
+
+        let candidate = Candidate::generated(concat, line, scope, returns_bool, reason);
+        let mut inner = self.inner();
+        match candidate.skip.as_ref() {
+            Some(reason) => getter::skip(scope, &candidate.name, reason, line),
+            None => {
+                debug!("* {} @ {}: generated {}", scope, line, candidate.name);
+                inner
+                    .generated
+                    .entry(line)
+                    .or_default()
+                    .push(candidate.clone());
+            }
+        }
+        inner.candidates.push(candidate);
+    }
+
+    /// Adds the `getter` generated by a derive attribute found in `scope`.
+    ///
+    /// The getter is skipped for `reason` if provided. Otherwise, it is renamed
+    /// by the [`AttrEdit`]s added with [`add_attr_edit`](Self::add_attr_edit).
+    pub fn add_derived(&self, getter: &Getter, scope: &Scope, reason: Option<NonGetterReason>) {
+        let candidate = match reason {
+            Some(reason) => {
+                getter::skip(scope, &getter.name, &reason, getter.line);
+                Candidate::skipped(getter, scope, reason)
+            }
+            None => {
+                debug!("* {} {} derived", scope, getter);
+                Candidate::renamed(getter, scope)
+            }
+        };
+        self.inner().candidates.push(candidate);
+    }
+
+    /// Adds an edit of a derive attribute generating getters.
+    pub fn add_attr_edit(&self, attr_edit: AttrEdit) {
+        self.inner().attr_edits.push(attr_edit);
+    }
+
+    /// Takes the edits of the derive attributes added so far.
+    pub fn take_attr_edits(&self) -> Vec<AttrEdit> {
+        std::mem::take(&mut self.inner().attr_edits)
+    }
+
+    /// Rejects the function found in `scope` which can't be renamed.
+    pub fn reject(&self, err: GetterError, scope: &Scope) {
+        err.log(scope);
+        if !err.err.is_not_get_fn() {
+            self.inner()
+                .candidates
+                .push(Candidate::rejected(&err, scope));
+        }
+    }
+
     pub fn get(&self, line_idx: usize) -> Option<GetterDef> {
-        self.inner.borrow_mut().0.remove(&(line_idx + 1)) // convert line idx to line_nb
+        self.inner().getter_defs.remove(&(line_idx + 1)) // convert line idx to line_nb
+    }
+
+    /// Takes the renamed getters generated by a concatenation at `line_idx`.
+    pub fn take_generated(&self, line_idx: usize) -> Vec<Candidate> {
+        self.inner()
+            .generated
+            .remove(&(line_idx + 1))
+            .unwrap_or_default()
+    }
+
+    /// Takes the candidates which were added, skipped or rejected so far.
+    pub fn take_candidates(&self) -> Vec<Candidate> {
+        std::mem::take(&mut self.inner().candidates)
     }
 
     pub fn is_empty(&self) -> bool {
-        self.inner.borrow().0.is_empty()
+        let inner = self.inner();
+        inner.getter_defs.is_empty() && inner.generated.is_empty() && inner.attr_edits.is_empty()
+    }
+
+    fn inner(&self) -> MutexGuard<'_, GetterDefCollectionInner> {
+        self.inner.lock().expect("getter collection poisoned")
     }
 }
@@ -0,0 +1,433 @@
+//! Getters generated by derive attributes.
+//!
+//! Supports the `with_prefix` option of the `getset` attributes, e.g.
+//! `#[getset(get = "pub", with_prefix)]`, and the `rename` option of the
+//! `derive-getters` attributes, e.g. `#[getter(rename = "get_name")]`.
+//!
+//! The attributes are rewritten when the generated getters can be renamed
+//! consistently with their call sites. Otherwise, the generated getters are
+//! recorded as skipped so that their call sites are kept unchanged.
+
+use proc_macro2::LineColumn;
+use rules::ReturnsBool;
+use syn::{ext::IdentExt, punctuated::Punctuated, Token};
+use utils::{Getter, NonGetterReason, Scope};
+
+use crate::{AttrEdit, GetterDefCollection};
+
+const WITH_PREFIX: &str = "with_prefix";
+
+/// Collects the getters generated by the derive attributes of the `struct_` found in `scope`.
+pub fn collect(struct_: &syn::ItemStruct, scope: &Scope, getter_collection: &GetterDefCollection) {
+    let fields = match &struct_.fields {
+        syn::Fields::Named(fields) => &fields.named,
+        _ => return,
+    };
+
+    let struct_getset = GetsetAttrs::parse(&struct_.attrs);
+    // Fields which getters are defined by the `struct` attributes
+    let mut struct_fields = Vec::new();
+
+    for field in fields {
+        let ident = match field.ident.as_ref() {
+            Some(ident) => ident,
+            None => continue,
+        };
+        let line = ident.span().start().line;
+
+        for attr in field.attrs.iter() {
+            collect_renamed(attr, scope, getter_collection);
+        }
+
+        let field_getset = GetsetAttrs::parse(&field.attrs);
+        if field_getset.is_skipped {
+            continue;
+        }
+
+        if field_getset.has_getter {
+            if let Some(with_prefix) = field_getset.with_prefix {
+                drop_prefix(&[(ident, line)], with_prefix, scope, getter_collection);
+            }
+        } else if struct_getset.has_getter {
+            struct_fields.push((ident, line));
+        }
+    }
+
+    if let Some(with_prefix) = struct_getset.with_prefix {
+        drop_prefix(&struct_fields, with_prefix, scope, getter_collection);
+    }
+}
+
+/// Drops the `with_prefix` option of a `getset` attribute if all the `fields`
+/// getters it applies to can be renamed after their fields.
+fn drop_prefix(
+    fields: &[(&syn::Ident, usize)],
+    mut with_prefix: AttrEdit,
+    scope: &Scope,
+    getter_collection: &GetterDefCollection,
+) {
+    let mut getters = Vec::with_capacity(fields.len());
+    let mut can_drop = true;
+    for (ident, line) in fields {
+        let field = ident.unraw().to_string();
+        match Getter::try_new(format!("get_{}", field), ReturnsBool::False, *line) {
+            Ok(getter) => {
+                if getter.new_name.as_str() != field || getter_collection.is_opted_out(*line) {
+                    can_drop = false;
+                }
+                getters.push(getter);
+            }
+            Err(err) => {
+                can_drop = false;
+                getter_collection.reject(err, scope);
+            }
+        }
+    }
+
+    if can_drop {
+        with_prefix.scope = scope.clone();
+        with_prefix.getters = getters
+            .iter()
+            .map(|getter| (getter.name.clone(), getter.new_name.as_str().to_string()))
+            .collect();
+        getter_collection.add_attr_edit(with_prefix);
+    }
+
+    for getter in getters {
+        let reason = if can_drop {
+            None
+        } else if getter_collection.is_opted_out(getter.line) {
+            Some(NonGetterReason::OptedOut)
+        } else {
+            Some(NonGetterReason::Derived)
+        };
+        getter_collection.add_derived(&getter, scope, reason);
+    }
+}
+
+/// Renames the getter of a `derive-getters` `rename` option in the `attr`, if any.
+fn collect_renamed(attr: &syn::Attribute, scope: &Scope, getter_collection: &GetterDefCollection) {
+    if !attr.path.is_ident("getter") {
+        return;
+    }
+
+    let list = match attr.parse_meta() {
+        Ok(syn::Meta::List(list)) => list,
+        _ => return,
+    };
+
+    for nested in list.nested.iter() {
+        let lit = match nested {
+            syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {
+                path,
+                lit: syn::Lit::Str(lit),
+                ..
+            })) if path.is_ident("rename") => lit,
+            _ => continue,
+        };
+
+        let line = lit.span().start().line;
+        let getter = match Getter::try_new(lit.value(), ReturnsBool::False, line) {
+            Ok(getter) => getter,
+            Err(err) => {
+                getter_collection.reject(err, scope);
+                continue;
+            }
+        };
+
+        if getter_collection.is_opted_out(line) {
+            getter_collection.add_derived(&getter, scope, Some(NonGetterReason::OptedOut));
+            continue;
+        }
+
+        getter_collection.add_attr_edit(AttrEdit {
+            scope: scope.clone(),
+            getters: vec![(getter.name.clone(), getter.new_name.as_str().to_string())],
+            ..AttrEdit::new(
+                lit.span().start(),
+                lit.span().end(),
+                format!("\"{}\"", getter.new_name.as_str()),
+            )
+        });
+        getter_collection.add_derived(&getter, scope, None);
+    }
+}
+
+/// The `getset` getters options found in some attributes.
+#[derive(Debug, Default)]
+struct GetsetAttrs {
+    has_getter: bool,
+    is_skipped: bool,
+    /// The edit dropping the `with_prefix` option, if found.
+    with_prefix: Option<AttrEdit>,
+}
+
+impl GetsetAttrs {
+    fn parse(attrs: &[syn::Attribute]) -> Self {
+        let mut getset = GetsetAttrs::default();
+        for attr in attrs {
+            match attr.parse_meta() {
+                Ok(syn::Meta::List(list)) if list.path.is_ident("getset") => {
+                    for (idx, nested) in list.nested.iter().enumerate() {
+                        match nested {
+                            syn::NestedMeta::Meta(syn::Meta::Path(path))
+                                if path.is_ident(WITH_PREFIX) =>
+                            {
+                                getset.with_prefix = Some(drop_nested(&list.nested, idx));
+                            }
+                            syn::NestedMeta::Meta(syn::Meta::Path(path))
+                                if path.is_ident("skip") =>
+                            {
+                                getset.is_skipped = true;
+                            }
+                            syn::NestedMeta::Meta(syn::Meta::NameValue(name_value)) => {
+                                getset.have_name_value(name_value);
+                            }
+                            _ => (),
+                        }
+                    }
+                }
+                // Legacy form, e.g. `#[get = "pub with_prefix"]`
+                Ok(syn::Meta::NameValue(name_value)) => getset.have_name_value(&name_value),
+                _ => (),
+            }
+        }
+
+        getset
+    }
+
+    fn have_name_value(&mut self, name_value: &syn::MetaNameValue) {
+        if !name_value.path.is_ident("get") && !name_value.path.is_ident("get_copy") {
+            return;
+        }
+        self.has_getter = true;
+
+        // Option in the visibility, e.g. `get = "pub with_prefix"`
+        if let syn::Lit::Str(lit) = &name_value.lit {
+            let value = lit.value();
+            let words: Vec<&str> = value.split_whitespace().collect();
+            if words.contains(&WITH_PREFIX) {
+                let words: Vec<&str> = words
+                    .into_iter()
+                    .filter(|word| *word != WITH_PREFIX)
+                    .collect();
+                self.with_prefix = Some(AttrEdit::new(
+                    lit.span().start(),
+                    lit.span().end(),
+                    format!("\"{}\"", words.join(" ")),
+                ));
+            }
+        }
+    }
+}
+
+/// Returns the edit dropping the `idx`-th `nested` item along with its separator.
+fn drop_nested(nested: &Punctuated<syn::NestedMeta, Token![,]>, idx: usize) -> AttrEdit {
+    let (start, end) = nested_span(&nested[idx]);
+    let pairs: Vec<_> = nested.pairs().collect();
+
+    let (start, end) = if idx > 0 {
+        // Drop the preceding separator, e.g. `get = "pub", with_prefix`
+        let comma = pairs[idx - 1].punct().expect("separator before item");
+        (comma.spans[0].start(), end)
+    } else if idx + 1 < pairs.len() {
+        // Drop up to the following item, e.g. `with_prefix, get = "pub"`
+        (start, nested_span(&nested[idx + 1]).0)
+    } else {
+        (start, end)
+    };
+
+    AttrEdit::new(start, end, "")
+}
+
+fn nested_span(nested: &syn::NestedMeta) -> (LineColumn, LineColumn) {
+    match nested {
+        syn::NestedMeta::Meta(meta) => {
+            let path = meta.path();
+            let first = path.segments.first().expect("empty path").ident.span();
+            let last = match meta {
+                syn::Meta::NameValue(name_value) => name_value.lit.span(),
+                _ => path.segments.last().expect("empty path").ident.span(),
+            };
+            (first.start(), last.end())
+        }
+        syn::NestedMeta::Lit(lit) => (lit.span().start(), lit.span().end()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use utils::{report::SkipReason, Candidate, OptOut};
+
+    fn collect_str(code: &str) -> (Vec<AttrEdit>, Vec<Candidate>) {
+        let struct_: syn::ItemStruct = syn::parse_str(code).unwrap();
+        let getter_collection = GetterDefCollection::default();
+        getter_collection.set_opt_out(OptOut::new(code));
+        collect(&struct_, &Scope::crate_root(), &getter_collection);
+
+        (
+            getter_collection.take_attr_edits(),
+            getter_collection.take_candidates(),
+        )
+    }
+
+    /// Builds the expected [`AttrEdit`], generating the getters with the original `names`.
+    fn attr_edit(
+        start: (usize, usize),
+        end: (usize, usize),
+        text: &str,
+        names: &[&str],
+    ) -> AttrEdit {
+        AttrEdit {
+            scope: Scope::crate_root(),
+            getters: names
+                .iter()
+                .map(|name| (name.to_string(), name["get_".len()..].to_string()))
+                .collect(),
+            ..AttrEdit::new(
+                LineColumn {
+                    line: start.0,
+                    column: start.1,
+                },
+                LineColumn {
+                    line: end.0,
+                    column: end.1,
+                },
+                text,
+            )
+        }
+    }
+
+    fn skip_reasons(candidates: &[Candidate]) -> Vec<(&str, Option<SkipReason>)> {
+        candidates
+            .iter()
+            .map(|candidate| (candidate.name.as_str(), candidate.skip))
+            .collect()
+    }
+
+    #[test]
+    fn drop_nested_separator() {
+        // First item: up to the following item
+        let (edits, candidates) =
+            collect_str(r#"#[getset(with_prefix, get = "pub")] struct Foo { a: u32 }"#);
+        assert_eq!(edits, vec![attr_edit((1, 9), (1, 22), "", &["get_a"])]);
+        assert_eq!(skip_reasons(&candidates), vec![("get_a", None)]);
+
+        // Middle item: from the preceding separator
+        let (edits, _) =
+            collect_str(r#"#[getset(get = "pub", with_prefix, set)] struct Foo { a: u32 }"#);
+        assert_eq!(edits, vec![attr_edit((1, 20), (1, 33), "", &["get_a"])]);
+
+        // Last item: from the preceding separator
+        let (edits, _) =
+            collect_str(r#"#[getset(get = "pub", with_prefix)] struct Foo { a: u32 }"#);
+        assert_eq!(edits, vec![attr_edit((1, 20), (1, 33), "", &["get_a"])]);
+
+        // Only item
+        let (edits, _) =
+            collect_str(r#"#[getset(get = "pub")] #[getset(with_prefix)] struct Foo { a: u32 }"#);
+        assert_eq!(edits, vec![attr_edit((1, 32), (1, 43), "", &["get_a"])]);
+    }
+
+    #[test]
+    fn legacy_form() {
+        let (edits, candidates) =
+            collect_str(r#"#[get = "pub with_prefix"] struct Foo { a: u32, b: u32 }"#);
+        assert_eq!(
+            edits,
+            vec![attr_edit((1, 8), (1, 25), r#""pub""#, &["get_a", "get_b"])]
+        );
+        assert_eq!(
+            skip_reasons(&candidates),
+            vec![("get_a", None), ("get_b", None)],
+        );
+
+        let (edits, _) =
+            collect_str(r#"struct Foo { #[get_copy = "with_prefix pub(crate)"] a: u32 }"#);
+        assert_eq!(
+            edits,
+            vec![attr_edit((1, 26), (1, 50), r#""pub(crate)""#, &["get_a"])]
+        );
+    }
+
+    #[test]
+    fn struct_and_field_levels() {
+        let (edits, candidates) = collect_str(
+            r#"#[getset(get = "pub", with_prefix)]
+struct Foo {
+    a: u32,
+    #[getset(get = "pub(crate)", with_prefix)]
+    b: u32,
+}"#,
+        );
+        assert_eq!(
+            edits,
+            vec![
+                attr_edit((4, 31), (4, 44), "", &["get_b"]),
+                attr_edit((1, 20), (1, 33), "", &["get_a"]),
+            ],
+        );
+        assert_eq!(
+            skip_reasons(&candidates),
+            vec![("get_b", None), ("get_a", None)],
+        );
+
+        // The struct level option is kept if one of its getters is kept
+        let (edits, candidates) = collect_str(
+            r#"#[getset(get = "pub", with_prefix)]
+struct Foo {
+    a: u32,
+    b: u32, // fix-getters: skip
+    #[getset(get = "pub", with_prefix)]
+    c: u32,
+}"#,
+        );
+        assert_eq!(edits, vec![attr_edit((5, 24), (5, 37), "", &["get_c"])]);
+        assert_eq!(
+            skip_reasons(&candidates),
+            vec![
+                ("get_c", None),
+                (
+                    "get_a",
+                    Some(SkipReason::NonGetter(NonGetterReason::Derived))
+                ),
+                (
+                    "get_b",
+                    Some(SkipReason::NonGetter(NonGetterReason::OptedOut))
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn skip() {
+        let (edits, candidates) = collect_str(
+            r#"#[getset(get = "pub", with_prefix)]
+struct Foo {
+    a: u32,
+    #[getset(skip)]
+    b: u32,
+}"#,
+        );
+        assert_eq!(edits, vec![attr_edit((1, 20), (1, 33), "", &["get_a"])]);
+        assert_eq!(skip_reasons(&candidates), vec![("get_a", None)]);
+    }
+
+    #[test]
+    fn getter_rename() {
+        let (edits, candidates) = collect_str(
+            r#"struct Foo {
+    #[getter(rename = "get_name")]
+    a: String,
+    #[getter(rename = "other")]
+    b: u32,
+}"#,
+        );
+        assert_eq!(
+            edits,
+            vec![attr_edit((2, 22), (2, 32), r#""name""#, &["get_name"])]
+        );
+        assert_eq!(skip_reasons(&candidates), vec![("get_name", None)]);
+    }
+}
@@ -1,7 +1,9 @@
 //! [`Getter`](utils::Getter) definitions collection implementations.
 
 pub mod collection;
-pub use collection::GetterDefCollection;
+pub use collection::{AttrEdit, GetterDefCollection};
+
+pub mod derive;
 
 pub mod syntax_tree;
 pub use syntax_tree::StGetterDefCollector;
@@ -1,14 +1,11 @@
 //! A [`SyntaxTreeGetterCollector`](utils::SyntaxTreeGetterCollector) collecting
 //! renamable [`Getter`](utils::Getter) definitions as [`GetterDef`](crate::GetterDef).
 
-use std::{
-    cell::{Ref, RefCell},
-    path::Path,
-    rc::Rc,
-};
+use std::{ops::RangeInclusive, path::Path};
 use syn::visit::{self, Visit};
-use utils::{getter, prelude::*, DocCodeGetterCollector, NonGetterReason, Scope};
+use utils::{opt_out, prelude::*, DocCodeGetterCollector, NonGetterReason, Scope, ScopeItem};
 
+use super::derive;
 use crate::{GetterDefCollection, TsGetterDefCollector};
 
 /// A [`SyntaxTreeGetterCollector`](utils::SyntaxTreeGetterCollector) collecting
@@ -16,9 +13,10 @@ use crate::{GetterDefCollection, TsGetterDefCollector};
 #[derive(Debug)]
 pub struct StGetterDefCollector<'path> {
     getter_collection: GetterDefCollection,
-    scope_stack: Vec<Rc<RefCell<Scope>>>,
+    scope: Scope,
     path: &'path Path,
     identification_mode: IdentificationMode,
+    is_opted_out: bool,
     doc_code_collector: DocCodeGetterCollector<TsGetterDefCollector<'path>>,
 }
 
@@ -36,11 +34,12 @@ impl<'path> SyntaxTreeGetterCollector for StGetterDefCollector<'path> {
             doc_code_collector: DocCodeGetterCollector::<TsGetterDefCollector>::new(
                 path,
                 identification_mode,
-                &getter_collection,
+                getter_collection,
             ),
             path,
             identification_mode,
-            scope_stack: Vec::new(),
+            scope: Scope::from_file(path),
+            is_opted_out: false,
         };
         visitor.visit_file(syntax_tree);
     }
@@ -49,7 +48,7 @@ impl<'path> SyntaxTreeGetterCollector for StGetterDefCollector<'path> {
 impl<'path> StGetterDefCollector<'path> {
     fn process(&mut self, sig: &syn::Signature) {
         use NonGetterReason::*;
-        use Scope::*;
+        use ScopeItem::*;
 
         let returns_bool = Self::returns_bool(sig);
         let line = sig.ident.span().start().line;
@@ -60,65 +59,65 @@ impl<'path> StGetterDefCollector<'path> {
         let mut getter = match res {
             Ok(getter) => getter,
             Err(err) => {
-                err.log(&self.scope());
+                self.getter_collection.reject(err, self.scope());
                 return;
             }
         };
 
-        let needs_doc_alias = match *self.scope() {
-            StructImpl(_) | Trait(_) | Macro(_) => true,
+        let needs_doc_alias = match self.scope().item() {
+            StructImpl { .. } | Trait(_) | Macro(_) => true,
             TraitImpl { .. } | Attribute(_) => false,
             _ => {
                 if !returns_bool && self.identification_mode.is_conservative() {
-                    getter::skip(&self.scope(), &sig.ident.to_string(), &NotAMethod, line);
+                    self.getter_collection
+                        .skip(&getter, self.scope(), NotAMethod);
                     return;
                 }
-                true
+                // Functions nested in a body can't be reached from the outside
+                !self.scope().is_in_body()
             }
         };
         getter.set_needs_doc_alias(needs_doc_alias);
 
+        if self.getter_collection.is_opted_out(getter.line()) {
+            self.getter_collection.skip(&getter, self.scope(), OptedOut);
+            return;
+        }
+
         if !returns_bool && self.identification_mode.is_conservative() {
             for param in &sig.generics.params {
                 match param {
                     syn::GenericParam::Lifetime(_) => (),
                     _ => {
-                        getter::skip(
-                            &self.scope(),
-                            getter.name(),
-                            &GenericTypeParam,
-                            getter.line(),
-                        );
+                        self.getter_collection
+                            .skip(&getter, self.scope(), GenericTypeParam);
                         return;
                     }
                 }
             }
 
             if sig.inputs.len() > 1 {
-                getter::skip(&self.scope(), getter.name(), &MultipleArgs, getter.line());
+                self.getter_collection
+                    .skip(&getter, self.scope(), MultipleArgs);
                 return;
             }
 
             match sig.inputs.first() {
                 Some(syn::FnArg::Receiver { .. }) => (),
                 Some(_) => {
-                    getter::skip(
-                        &self.scope(),
-                        getter.name(),
-                        &NonSelfUniqueArg,
-                        getter.line(),
-                    );
+                    self.getter_collection
+                        .skip(&getter, self.scope(), NonSelfUniqueArg);
                     return;
                 }
                 None => {
-                    getter::skip(&self.scope(), getter.name(), &NoArgs, getter.line());
+                    self.getter_collection.skip(&getter, self.scope(), NoArgs);
                     return;
                 }
             }
         }
 
-        getter.log(&self.path, &self.scope());
-        self.getter_collection.add(getter);
+        getter.log(self.path, self.scope());
+        self.getter_collection.add(getter, self.scope());
     }
 
     fn returns_bool(sig: &syn::Signature) -> bool {
@@ -138,25 +137,53 @@ impl<'path> StGetterDefCollector<'path> {
         false
     }
 
-    fn scope(&self) -> Ref<Scope> {
-        self.scope_stack.last().expect("empty scope stack").borrow()
+    /// Opts out the item with the given `attrs` if it is marked.
+    ///
+    /// `lines` are the lines from the first attribute of the item to its name.
+    /// Returns the previous opt-out state, to be restored when leaving the item.
+    fn enter_item(
+        &mut self,
+        attrs: &[syn::Attribute],
+        lines: Option<RangeInclusive<usize>>,
+    ) -> bool {
+        let was_opted_out = self.is_opted_out;
+        if opt_out::has_skip_attribute(attrs)
+            || lines.is_some_and(|lines| self.getter_collection.is_marked(lines))
+        {
+            self.is_opted_out = true;
+            self.getter_collection.set_opted_out(true);
+        }
+
+        was_opted_out
+    }
+
+    fn leave_item(&mut self, was_opted_out: bool) {
+        self.is_opted_out = was_opted_out;
+        self.getter_collection.set_opted_out(was_opted_out);
     }
 
-    fn push_scope(&mut self, scope: impl Into<Scope>) {
-        let scope = scope.into().into();
-        self.scope_stack.push(scope);
+    fn scope(&self) -> &Scope {
+        &self.scope
+    }
+
+    fn push_scope(&mut self, item: impl Into<ScopeItem>) {
+        self.scope.push(item);
     }
 
     fn pop_scope(&mut self) {
-        self.scope_stack.pop();
+        self.scope.pop();
     }
 }
 
 impl<'ast, 'path> Visit<'ast> for StGetterDefCollector<'path> {
     fn visit_item(&mut self, node: &'ast syn::Item) {
+        let was_opted_out = self.enter_item(opt_out::item_attrs(node), opt_out::item_lines(node));
+
         self.push_scope(node);
         visit::visit_item(self, node);
         self.pop_scope();
+
+        self.leave_item(was_opted_out);
     }
 
     fn visit_item_fn(&mut self, node: &'ast syn::ItemFn) {
@@ -164,21 +191,52 @@ impl<'ast, 'path> Visit<'ast> for StGetterDefCollector<'path> {
         visit::visit_item_fn(self, node);
     }
 
+    fn visit_item_struct(&mut self, node: &'ast syn::ItemStruct) {
+        derive::collect(node, self.scope(), &self.getter_collection);
+        visit::visit_item_struct(self, node);
+    }
+
     fn visit_impl_item_method(&mut self, node: &'ast syn::ImplItemMethod) {
+        let lines = opt_out::attrs_lines(&node.attrs, node.sig.ident.span().start().line);
+        let was_opted_out = self.enter_item(&node.attrs, Some(lines));
+
         self.process(&node.sig);
+        self.push_scope(ScopeItem::Fn(node.sig.ident.to_string()));
         visit::visit_impl_item_method(self, node);
+        self.pop_scope();
+
+        self.leave_item(was_opted_out);
     }
 
     fn visit_trait_item_method(&mut self, node: &'ast syn::TraitItemMethod) {
+        let lines = opt_out::attrs_lines(&node.attrs, node.sig.ident.span().start().line);
+        let was_opted_out = self.enter_item(&node.attrs, Some(lines));
+
         self.process(&node.sig);
+        self.push_scope(ScopeItem::Fn(node.sig.ident.to_string()));
         visit::visit_trait_item_method(self, node);
+        self.pop_scope();
+
+        self.leave_item(was_opted_out);
+    }
+
+    fn visit_impl_item_const(&mut self, node: &'ast syn::ImplItemConst) {
+        self.push_scope(ScopeItem::Const(node.ident.to_string()));
+        visit::visit_impl_item_const(self, node);
+        self.pop_scope();
+    }
+
+    fn visit_trait_item_const(&mut self, node: &'ast syn::TraitItemConst) {
+        self.push_scope(ScopeItem::Const(node.ident.to_string()));
+        visit::visit_trait_item_const(self, node);
+        self.pop_scope();
     }
 
     fn visit_macro(&mut self, node: &'ast syn::Macro) {
         self.push_scope(node);
         TsGetterDefCollector::collect(
             self.path,
-            &self.scope(),
+            self.scope(),
             &node.tokens,
             self.identification_mode,
             &self.getter_collection,
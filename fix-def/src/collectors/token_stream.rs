@@ -7,7 +7,7 @@ use std::path::Path;
 use syn::buffer::{Cursor, TokenBuffer};
 
 use rules::ReturnsBool;
-use utils::{getter, prelude::*, NonGetterReason, Scope};
+use utils::{paste::Concat, prelude::*, NonGetterReason, Scope};
 
 use crate::{GetterDef, GetterDefCollection};
 
@@ -144,7 +144,7 @@ impl<'scope> TsGetterDefCollector<'scope> {
                                 Ok(getter) => {
                                     self.state = State::new_named_fn(getter);
                                 }
-                                Err(err) => err.log(self.scope),
+                                Err(err) => self.getter_collection.reject(err, self.scope),
                             }
                         }
                         State::NamedFn(maybe) => {
@@ -211,6 +211,16 @@ impl<'scope> TsGetterDefCollector<'scope> {
                                 self.state = State::ArgList(maybe);
                             }
                         }
+                        State::Fn => {
+                            // `paste!`-style concatenation, e.g. `fn [<get_ $field>]`
+                            if let Some(concat) = Concat::parse(&group) {
+                                self.process_generated(
+                                    &concat,
+                                    group.span().start().line,
+                                    generated_returns_bool(next),
+                                );
+                            }
+                        }
                         other => trace!("ts {:?} {:?}", group, other),
                     }
 
@@ -226,51 +236,128 @@ impl<'scope> TsGetterDefCollector<'scope> {
         }
     }
 
+    /// Processes the getter generated by the `concat`enation at `line`.
+    ///
+    /// `returns_bool` is `None` if the return type couldn't be determined.
+    fn process_generated(&self, concat: &Concat, line: usize, returns_bool: Option<ReturnsBool>) {
+        // The generated name can't be checked against the signature
+        let reason = if self.identification_mode.is_conservative() {
+            Some(NonGetterReason::Generated)
+        } else if returns_bool.is_none() {
+            Some(NonGetterReason::UnknownReturnType)
+        } else {
+            None
+        };
+
+        self.getter_collection.add_generated(
+            concat,
+            line,
+            self.scope,
+            returns_bool.unwrap_or(ReturnsBool::Maybe),
+            reason,
+        );
+    }
+
     fn process_maybe_getter(&mut self, maybe: MaybeGetter) {
         use NonGetterReason::*;
 
+        if self.getter_collection.is_opted_out(maybe.getter.line()) {
+            self.getter_collection
+                .skip(&maybe.getter, self.scope, OptedOut);
+            return;
+        }
+
         if !maybe.getter.returns_bool().is_true() && self.identification_mode.is_conservative() {
             // not a bool getter
             if maybe.has_no_args {
-                getter::skip(
-                    self.scope,
-                    maybe.getter.name(),
-                    &NoArgs,
-                    maybe.getter.line(),
-                );
+                self.getter_collection
+                    .skip(&maybe.getter, self.scope, NoArgs);
                 return;
             }
             if !maybe.is_method {
-                getter::skip(
-                    self.scope,
-                    maybe.getter.name(),
-                    &NotAMethod,
-                    maybe.getter.line(),
-                );
+                self.getter_collection
+                    .skip(&maybe.getter, self.scope, NotAMethod);
                 return;
             }
             if maybe.has_gen_params {
-                getter::skip(
-                    self.scope,
-                    maybe.getter.name(),
-                    &GenericTypeParam,
-                    maybe.getter.line(),
-                );
+                self.getter_collection
+                    .skip(&maybe.getter, self.scope, GenericTypeParam);
                 return;
             }
             if maybe.has_multiple_args {
-                getter::skip(
-                    self.scope,
-                    maybe.getter.name(),
-                    &MultipleArgs,
-                    maybe.getter.line(),
-                );
+                self.getter_collection
+                    .skip(&maybe.getter, self.scope, MultipleArgs);
                 return;
             }
         }
 
         maybe.getter.log(self.path, self.scope);
-        self.getter_collection.add(maybe.getter);
+        self.getter_collection.add(maybe.getter, self.scope);
+    }
+}
+
+/// Determines whether the function which name is generated by a concatenation
+/// returns a `bool`, from the signature following the name at cursor `rest`.
+///
+/// Returns `None` if the return type can't be determined, e.g. if it's a macro
+/// variable such as `$type_`.
+fn generated_returns_bool(mut rest: Cursor) -> Option<ReturnsBool> {
+    // Generic parameters
+    if let Some((TokenTree::Punct(punct), next)) = rest.token_tree() {
+        if punct.as_char() != '<' {
+            return None;
+        }
+
+        let mut depth = 1;
+        rest = next;
+        while depth > 0 {
+            let (tt, next) = rest.token_tree()?;
+            if let TokenTree::Punct(punct) = tt {
+                match punct.as_char() {
+                    '<' => depth += 1,
+                    '>' => depth -= 1,
+                    _ => (),
+                }
+            }
+            rest = next;
+        }
+    }
+
+    // Arguments
+    match rest.token_tree()? {
+        (TokenTree::Group(group), next) if group.delimiter() == Delimiter::Parenthesis => {
+            rest = next;
+        }
+        _ => return None,
+    }
+
+    // Return type
+    match rest.token_tree() {
+        Some((TokenTree::Punct(punct), next)) if punct.as_char() == '-' => rest = next,
+        // No return type
+        _ => return Some(ReturnsBool::False),
+    }
+    match rest.token_tree()? {
+        (TokenTree::Punct(punct), next) if punct.as_char() == '>' => rest = next,
+        _ => return None,
+    }
+
+    let mut ret = Vec::new();
+    while let Some((tt, next)) = rest.token_tree() {
+        match &tt {
+            TokenTree::Group(group) if group.delimiter() == Delimiter::Brace => break,
+            TokenTree::Punct(punct) if punct.as_char() == ';' => break,
+            TokenTree::Ident(ident) if ident == "where" => break,
+            TokenTree::Punct(punct) if punct.as_char() == '$' => return None,
+            _ => ret.push(tt),
+        }
+        rest = next;
+    }
+
+    match ret.as_slice() {
+        [TokenTree::Ident(ident)] if ident == "bool" => Some(ReturnsBool::True),
+        [] => None,
+        _ => Some(ReturnsBool::False),
     }
 }
 
@@ -283,8 +370,9 @@ struct MaybeGetter {
     has_no_args: bool,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 enum State {
+    #[default]
     None,
     Fn,
     NamedFn(MaybeGetter),
@@ -335,9 +423,3 @@ impl State {
         }
     }
 }
-
-impl Default for State {
-    fn default() -> Self {
-        State::None
-    }
-}
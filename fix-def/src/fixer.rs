@@ -1,17 +1,19 @@
 //! Rust source file level getter definitions fixer.
 
-use std::fs;
-use std::io::Write;
-use std::path::{Path, PathBuf};
+use std::{path::Path, sync::Arc};
 
-use utils::{prelude::*, Error, ParseFileError};
+use utils::{
+    edit, markdown, paste, prelude::*, Candidate, DocCodeGetterCollector, Edit, Error, OptOut,
+    ParseFileError, Report,
+};
 
-use crate::{DocAliasMode, GetterDefCollection, StGetterDefCollector};
+use crate::{DocAliasMode, GetterDefCollection, StGetterDefCollector, TsGetterDefCollector};
 
 /// Rust source file level getter definitions fixer.
 pub struct GetterDefFixer {
     identification_mode: IdentificationMode,
     doc_alias_alias: DocAliasMode,
+    report: Option<Arc<Report>>,
 }
 
 impl GetterDefFixer {
@@ -19,88 +21,171 @@ impl GetterDefFixer {
         GetterDefFixer {
             identification_mode,
             doc_alias_alias,
+            report: None,
         }
     }
+
+    /// Adds the renaming candidates of each processed file to the `report`.
+    pub fn with_report(mut self, report: Arc<Report>) -> Self {
+        self.report = Some(report);
+        self
+    }
 }
 
-impl CrateTraverser for GetterDefFixer {
-    /// Fixes the Rust file at the given path.
+impl GetterDefFixer {
+    /// Collects the edits renaming the getter definitions in the `syntax_tree`
+    /// parsed from `source_code`.
     ///
-    /// If `output_path` is specified, the result will be written there,
-    /// otherwise the input files are overwritten.
-    fn handle_rust_file(
-        &mut self,
+    /// Also returns the renaming candidates, including the skipped functions.
+    pub fn collect_syntax_tree_edits(
+        &self,
         path: &Path,
-        output_path: &Option<PathBuf>,
-    ) -> Result<(), Error> {
-        // Analyze Rust file
-        let source_code =
-            fs::read_to_string(path).map_err(|err| Error::ReadFile(path.to_owned(), err))?;
-        let syntax_tree = match syn::parse_file(&source_code) {
-            Ok(syntax_tree) => syntax_tree,
-            Err(error) => {
-                return Err(ParseFileError::new(error, path.to_owned(), source_code).into());
-            }
-        };
-
+        source_code: &str,
+        syntax_tree: &syn::File,
+    ) -> (Vec<Edit>, Vec<Candidate>) {
         let getter_collection = GetterDefCollection::default();
+        getter_collection.set_opt_out(OptOut::new(source_code));
         StGetterDefCollector::collect(
-            &path,
-            &syntax_tree,
+            path,
+            syntax_tree,
             self.identification_mode,
             &getter_collection,
         );
 
-        let output_path = match output_path {
-            Some(output_path) => output_path,
-            None => path,
-        };
+        self.collect_edits_(source_code, &getter_collection)
+    }
+
+    /// Collects the edits renaming the getter definitions in the Rust code
+    /// blocks of the Markdown `source_code`.
+    ///
+    /// Also returns the renaming candidates, including the skipped functions.
+    pub fn collect_markdown_edits(
+        &self,
+        path: &Path,
+        source_code: &str,
+    ) -> (Vec<Edit>, Vec<Candidate>) {
+        let getter_collection = GetterDefCollection::default();
+        getter_collection.set_opt_out(OptOut::new(source_code));
+        DocCodeGetterCollector::<TsGetterDefCollector>::new(
+            path,
+            self.identification_mode,
+            &getter_collection,
+        )
+        .have_markdown(source_code);
+
+        self.collect_edits_(source_code, &getter_collection)
+    }
+
+    fn collect_edits_(
+        &self,
+        source_code: &str,
+        getter_collection: &GetterDefCollection,
+    ) -> (Vec<Edit>, Vec<Candidate>) {
+        let candidates = getter_collection.take_candidates();
 
+        let mut edits = Vec::new();
         if getter_collection.is_empty() {
-            // Nothing to do for this file
-            return Ok(());
+            // Nothing to do for this code
+            return (edits, candidates);
         }
 
-        // Write result
-        let f =
-            fs::File::create(output_path).map_err(|err| Error::WriteFile(path.to_owned(), err))?;
-        let mut writer = std::io::BufWriter::new(f);
+        // Rewrite the derive attributes generating getters
+        for attr_edit in getter_collection.take_attr_edits() {
+            let start = edit::offset(source_code, attr_edit.start.line, attr_edit.start.column);
+            let end = edit::offset(source_code, attr_edit.end.line, attr_edit.end.column);
+            if let (Some(start), Some(end)) = (start, end) {
+                edits.push(Edit::derive_attribute(
+                    start..end,
+                    attr_edit.text,
+                    &attr_edit.scope,
+                    attr_edit.getters,
+                ));
+            }
+        }
 
-        for (line_idx, line) in source_code.lines().enumerate() {
+        let mut offset = 0;
+        for (line_idx, line) in source_code.split_inclusive('\n').enumerate() {
             if let Some(getter_def) = getter_collection.get(line_idx) {
                 if self.doc_alias_alias.must_generate() && getter_def.needs_doc_alias() {
-                    writer
-                        .write_fmt(format_args!("#[doc(alias = \"{}\")] ", getter_def.name()))
-                        .map_err(|err| Error::WriteFile(path.to_owned(), err))?;
+                    edits.push(Edit::doc_alias(
+                        offset,
+                        format!("#[doc(alias = \"{}\")] ", getter_def.name()),
+                    ));
                 }
 
                 // Rename getter
-                let origin = format!("fn {}", getter_def.name());
-                let target = format!("fn {}", getter_def.new_name().as_str());
-
-                writer
-                    .write(line.replacen(&origin, &target, 1).as_bytes())
-                    .map_err(|err| Error::WriteFile(path.to_owned(), err))?;
-            } else {
-                // No changes for this line
-                writer
-                    .write(line.as_bytes())
-                    .map_err(|err| Error::WriteFile(path.to_owned(), err))?;
+                if let Some(pos) = line.find(&format!("fn {}", getter_def.name())) {
+                    let start = offset + pos + "fn ".len();
+                    edits.push(Edit::rename(
+                        start..start + getter_def.name().len(),
+                        getter_def.new_name().as_str(),
+                        getter_def.scope(),
+                    ));
+                }
+            }
+
+            // Rename the getters generated by a concatenation
+            for generated in getter_collection.take_generated(line_idx) {
+                let renaming = match generated.renaming.as_ref() {
+                    Some(renaming) => renaming,
+                    None => continue,
+                };
+
+                let range = paste::match_ranges(line, &generated.name)
+                    .into_iter()
+                    .map(|range| offset + range.start..offset + range.end)
+                    .find(|range| !edits.iter().any(|edit| edit.overlaps(range)));
+                if let Some(range) = range {
+                    edits.push(Edit::rename(
+                        range,
+                        renaming.new_name.as_str(),
+                        &generated.scope,
+                    ));
+                }
             }
 
-            writer
-                .write(&[b'\n'])
-                .map_err(|err| Error::WriteFile(path.to_owned(), err))?;
+            offset += line.len();
+        }
+
+        (edits, candidates)
+    }
+}
+
+impl CrateTraverser for GetterDefFixer {
+    /// Collects the edits renaming the getter definitions in `source_code`.
+    fn collect_edits(&self, path: &Path, source_code: &str) -> Result<Vec<Edit>, Error> {
+        let (edits, candidates) = if markdown::is_markdown(path) {
+            self.collect_markdown_edits(path, source_code)
+        } else {
+            // Analyze Rust code
+            let syntax_tree = match syn::parse_file(source_code) {
+                Ok(syntax_tree) => syntax_tree,
+                Err(error) => {
+                    return Err(ParseFileError::new(
+                        error,
+                        path.to_owned(),
+                        source_code.to_string(),
+                    )
+                    .into());
+                }
+            };
+
+            self.collect_syntax_tree_edits(path, source_code, &syntax_tree)
+        };
+        if let Some(report) = self.report.as_ref() {
+            report.add(path, source_code, candidates);
         }
 
-        Ok(())
+        Ok(edits)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::env;
+    use rules::dir_entry::{DefaultDirEntryFilter, Glob};
+    use std::{fs, path::PathBuf};
+    use utils::test_utils::temp_dir;
 
     fn fix_baseline(id_mode: IdentificationMode) {
         let input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
@@ -113,12 +198,17 @@ mod tests {
             "all_get_functions"
         };
 
-        let output_path = env::temp_dir().join("fix-def").join(file_id);
-        fs::create_dir_all(&output_path).unwrap();
+        let output_path = temp_dir(env!("CARGO_PKG_NAME"), file_id);
         let output_file = output_path.clone().join("baseline.rs");
 
-        let mut fixer = GetterDefFixer::new(id_mode, DocAliasMode::Generate);
-        fixer.traverse(&input_path, &Some(output_path)).unwrap();
+        let fixer = GetterDefFixer::new(id_mode, DocAliasMode::Generate);
+        fixer
+            .traverse(
+                &input_path,
+                &TraversalSettings::new(&DefaultDirEntryFilter),
+                &Some(output_path),
+            )
+            .unwrap();
 
         let output = fs::read_to_string(&output_file).unwrap();
 
@@ -148,4 +238,58 @@ mod tests {
     fn fix_baseline_all_get_functions() {
         fix_baseline(IdentificationMode::AllGetFunctions)
     }
+
+    #[test]
+    fn fix_stream() {
+        let input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("test_samples")
+            .join("input")
+            .join("baseline.rs");
+        let input = fs::read(&input_path).unwrap();
+
+        let fixer =
+            GetterDefFixer::new(IdentificationMode::AllGetFunctions, DocAliasMode::Generate);
+        let mut output = Vec::new();
+        fixer
+            .fix_stream(&input_path, &mut input.as_slice(), &mut output)
+            .unwrap();
+
+        let expected_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("test_samples")
+            .join("expected")
+            .join("all_get_functions.rs");
+        let expected = fs::read_to_string(&expected_path).unwrap();
+
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+    }
+
+    #[test]
+    fn fix_markdown() {
+        let input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("test_samples")
+            .join("input");
+
+        let output_path = temp_dir(env!("CARGO_PKG_NAME"), "markdown");
+
+        let markdown = [Glob::new("*.md")];
+        let settings = TraversalSettings {
+            markdown: &markdown,
+            ..TraversalSettings::new(&DefaultDirEntryFilter)
+        };
+        let fixer =
+            GetterDefFixer::new(IdentificationMode::AllGetFunctions, DocAliasMode::Generate);
+        fixer
+            .traverse(&input_path, &settings, &Some(output_path.clone()))
+            .unwrap();
+
+        let output = fs::read_to_string(output_path.join("README.md")).unwrap();
+
+        let expected_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("test_samples")
+            .join("expected")
+            .join("README.md");
+        let expected = fs::read_to_string(&expected_path).unwrap();
+
+        assert_eq!(output, expected);
+    }
 }
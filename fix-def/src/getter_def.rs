@@ -5,7 +5,7 @@ use std::{
     fmt::{self, Display},
     path::Path,
 };
-use utils::{Getter, GetterError};
+use utils::{Getter, GetterError, Scope};
 
 /// A [`Getter`](utils::Getter) definition.
 #[derive(Debug)]
@@ -26,6 +26,10 @@ impl GetterDef {
         })
     }
 
+    pub fn getter(&self) -> &Getter {
+        &self.getter
+    }
+
     pub fn name(&self) -> &str {
         &self.getter.name
     }
@@ -46,6 +50,14 @@ impl GetterDef {
         self.getter.line
     }
 
+    pub fn scope(&self) -> &Scope {
+        &self.getter.scope
+    }
+
+    pub fn set_scope(&mut self, scope: &Scope) {
+        self.getter.scope = scope.clone();
+    }
+
     pub fn needs_doc_alias(&self) -> bool {
         self.needs_doc_alias
     }
@@ -0,0 +1,15 @@
+//! Getter definitions fixer.
+//!
+//! Removes the `get` prefix from the eligible getter definitions.
+
+mod doc_alias_mode;
+pub use doc_alias_mode::DocAliasMode;
+
+mod fixer;
+pub use fixer::GetterDefFixer;
+
+mod getter_def;
+pub use getter_def::GetterDef;
+
+mod collectors;
+pub use collectors::*;
@@ -26,6 +26,17 @@
 //!     }
 //! );
 //! ```
+//!
+//! ```
+//! # struct Hidden(u64);
+//! # impl Hidden {
+//! #     fn get_hidden(&self) -> u64 {
+//! #         self.0
+//! #     }
+//! # }
+//! #[derive(Debug)]
+//! struct Shown(u64);
+//! ```
 
 /// This is a test
 #[doc(alias = "get_foo")]
@@ -264,3 +275,123 @@ impl<'a> MyTrait for &'a (u64, bool) {
         self.get_trait_no_impl().into()
     }
 }
+
+// fix-getters: skip
+// Mirrors the C API.
+impl MyType {
+    fn get_opted_out_impl(&self) -> u64 {
+        42u64
+    }
+}
+
+impl MyType {
+    #[cfg_attr(any(), fix_getters::skip)]
+    fn get_opted_out_attr(&self) -> u64 {
+        42u64
+    }
+
+    fn get_opted_out_line(&self) -> u64 { // fix-getters: skip
+        42u64
+    }
+}
+
+enum MyEnum {
+    A,
+}
+
+impl MyEnum {
+    fn get_variant_name(&self) -> &str {
+        fn get_nested_name(value: &MyEnum) -> &'static str {
+            "A"
+        }
+
+        get_nested_name(self)
+    }
+}
+
+const _: () = {
+    impl MyEnum {
+        fn get_in_const_block(&self) -> u64 {
+            42u64
+        }
+    }
+};
+
+impl<T> MyTrait for Vec<T>
+where
+    T: MyTrait + Clone,
+{
+    fn get_trait_no_impl(&self) -> u64 {
+        42u64
+    }
+
+    fn get_type(&self) -> u64 {
+        42u64
+    }
+
+    fn get_trait_impl_param<U: From<u64>>(&self) -> U {
+        self.get_trait_no_impl().into()
+    }
+}
+
+macro_rules! generated_getters(
+    ($($field:ident: $type_:ty),*) => {
+        paste::paste! {
+            impl MyType {
+                $(
+                    fn [<get_ $field>](&self) -> $type_ {
+                        self.$field
+                    }
+
+                    fn [<get_ $field _mut>](&mut self) -> &mut $type_ {
+                        &mut self.$field
+                    }
+                )*
+            }
+        }
+    };
+);
+
+macro_rules! generated_typed_getters(
+    ($($flag:ident),*; $($counter:ident),*) => {
+        paste::paste! {
+            impl MyType {
+                $(
+                    fn [<get_ $flag>](&self) -> bool {
+                        self.$flag
+                    }
+                )*
+                $(
+                    fn [<get_ $counter>](&self) -> u64 {
+                        self.$counter
+                    }
+                )*
+            }
+        }
+    };
+);
+
+#[derive(Getters, CopyGetters)]
+#[getset(get = "pub", with_prefix)]
+struct WithPrefix {
+    name: String,
+    #[getset(get_copy = "pub", with_prefix)]
+    count: u64,
+    #[get_copy = "pub with_prefix"]
+    legacy: u64,
+    #[getset(skip)]
+    skipped: u64,
+}
+
+#[derive(Getters)]
+#[getset(with_prefix, get = "pub")]
+struct WithPrefixKept {
+    name: String,
+    r#type: u64,
+}
+
+#[derive(Getters)]
+struct Renamed {
+    #[getter(rename = "get_value")]
+    value: u64,
+}
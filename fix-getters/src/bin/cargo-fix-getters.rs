@@ -0,0 +1,164 @@
+//! `cargo fix-getters` subcommand.
+
+use clap::{App, AppSettings, Arg, SubCommand};
+use def::DocAliasMode;
+use fix_getters::{GetterFixer, Workspace};
+use log::error;
+use std::{
+    fmt::Display,
+    io::{self, IsTerminal},
+    path::Path,
+    process,
+    sync::Arc,
+    time::Instant,
+};
+use utils::{
+    cli::{common_args, init_logger, mentions_arg, no_doc_aliases_arg, Options},
+    prelude::*,
+};
+
+fn main() {
+    let m = App::new("cargo")
+        .bin_name("cargo")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(
+            SubCommand::with_name("fix-getters")
+                .version(clap::crate_version!())
+                .author(clap::crate_authors!())
+                .about("Fix getters definition & call sites in the packages of a Cargo workspace")
+                .arg(
+                    Arg::with_name("manifest-path")
+                        .long("manifest-path")
+                        .value_name("PATH")
+                        .help("Path to Cargo.toml"),
+                )
+                .arg(
+                    Arg::with_name("package")
+                        .short("p")
+                        .long("package")
+                        .value_name("SPEC")
+                        .multiple(true)
+                        .number_of_values(1)
+                        .help("Package to process, e.g. `foo` or `foo@1.2.0` (can be repeated)"),
+                )
+                .arg(
+                    Arg::with_name("workspace")
+                        .long("workspace")
+                        .conflicts_with("package")
+                        .help("Process all packages in the workspace"),
+                )
+                .arg(
+                    Arg::with_name("all-targets")
+                        .long("all-targets")
+                        .help("Also process the tests, examples & benches"),
+                )
+                .arg(no_doc_aliases_arg())
+                .arg(mentions_arg())
+                .args(&common_args()),
+        )
+        .get_matches();
+
+    let m = match m.subcommand_matches("fix-getters") {
+        Some(m) => m,
+        None => unreachable!("subcommand required"),
+    };
+
+    init_logger(m);
+    let is_quiet = m.is_present("quiet");
+
+    let workspace = match Workspace::locate(m.value_of("manifest-path").map(Path::new)) {
+        Ok(workspace) => workspace,
+        Err(error) => {
+            error!("{}", error);
+            process::exit(1);
+        }
+    };
+    let names: Vec<&str> = m.values_of("package").into_iter().flatten().collect();
+    let packages = match workspace.select(&names, m.is_present("workspace")) {
+        Ok(packages) => packages,
+        Err(error) => {
+            error!("{}", error);
+            process::exit(1);
+        }
+    };
+    let filters: Vec<_> = packages
+        .iter()
+        .map(|package| workspace.package_filter(package, m.is_present("all-targets")))
+        .collect();
+
+    let options = Options::from_matches(m)
+        .with_root(workspace.root.clone())
+        .with_decisions_by_name();
+
+    let mut fixer = GetterFixer::new(
+        options.identification_mode,
+        if m.is_present("no-doc-aliases") {
+            DocAliasMode::Discard
+        } else {
+            DocAliasMode::Generate
+        },
+    );
+    if m.is_present("mentions") {
+        fixer = fixer.with_mentions();
+    }
+    let fixer = match options.report.as_ref() {
+        Some(report) => fixer.with_report(Arc::clone(report)),
+        None => fixer,
+    };
+
+    let start = Instant::now();
+
+    // Collect the definitions from all the selected packages first,
+    // so that calls to a getter defined in another package are renamed.
+    for (package, filter) in packages.iter().zip(filters.iter()) {
+        if !is_quiet {
+            status("Scanning", package);
+        }
+        if let Err(error) = fixer.scan(&package.path, &options.settings_with(filter)) {
+            options.log_error(&error);
+            process::exit(1);
+        }
+    }
+
+    let verb = if options.is_check() {
+        "Checking"
+    } else if options.is_dry_run() {
+        "Diffing"
+    } else {
+        "Fixing"
+    };
+
+    let fixer = options.review(fixer);
+    for (package, filter) in packages.iter().zip(filters.iter()) {
+        if !is_quiet {
+            status(verb, package);
+        }
+        if let Err(error) = fixer.traverse(&package.path, &options.settings_with(filter), &None) {
+            options.log_error(&error);
+            options.save_journal();
+            process::exit(1);
+        }
+    }
+
+    if !is_quiet {
+        status(
+            "Finished",
+            format!(
+                "{} package(s) in {:.2}s",
+                packages.len(),
+                start.elapsed().as_secs_f64()
+            ),
+        );
+    }
+
+    options.finish(&fixer);
+}
+
+/// Prints a status line to stderr the way `cargo` does.
+fn status(verb: &str, message: impl Display) {
+    if io::stderr().is_terminal() {
+        eprintln!("\x1b[1;32m{:>12}\x1b[0m {}", verb, message);
+    } else {
+        eprintln!("{:>12} {}", verb, message);
+    }
+}
@@ -0,0 +1,65 @@
+//! Getter definitions found across the workspace.
+
+use rules::ReturnsBool;
+use std::{
+    collections::HashMap,
+    sync::{Mutex, MutexGuard},
+};
+use utils::Candidate;
+
+/// What is known about the definitions of the getters with a given name.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Definition {
+    /// The definitions are renamed and agree on the returned type.
+    Renamed(ReturnsBool),
+    /// The definitions are kept unchanged.
+    Kept,
+    /// The definitions disagree: the calls can't be resolved.
+    Ambiguous,
+}
+
+impl Definition {
+    fn merge(self, other: Definition) -> Definition {
+        if self == other {
+            self
+        } else {
+            Definition::Ambiguous
+        }
+    }
+}
+
+/// Getter definitions found across the workspace by name.
+#[derive(Debug, Default)]
+pub struct Definitions(Mutex<HashMap<String, Definition>>);
+
+impl Definitions {
+    /// Adds the getter definition `candidate`.
+    pub fn add(&self, candidate: &Candidate) {
+        let definition = match (&candidate.renaming, candidate.is_skipped()) {
+            (Some(renaming), false) => Definition::Renamed(renaming.returns_bool),
+            _ => Definition::Kept,
+        };
+
+        self.inner()
+            .entry(candidate.name.clone())
+            .and_modify(|known| *known = known.merge(definition))
+            .or_insert(definition);
+    }
+
+    /// Returns what is known about the definitions of the getters named `name`.
+    pub fn get(&self, name: &str) -> Option<Definition> {
+        self.inner().get(name).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner().is_empty()
+    }
+
+    fn inner(&self) -> MutexGuard<'_, HashMap<String, Definition>> {
+        self.0.lock().expect("definitions poisoned")
+    }
+}
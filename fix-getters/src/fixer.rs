@@ -0,0 +1,479 @@
+//! Rust source file level getter definitions & calls fixer.
+
+use std::{
+    collections::HashMap,
+    fs,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use calls::{rename_calls, GetterCallFixer};
+use def::{DocAliasMode, GetterDefFixer};
+use log::{debug, info};
+use rules::ReturnsBool;
+use utils::{
+    edit, markdown, mention, paste, prelude::*, Candidate, Edit, Error, Getter, NonGetterReason,
+    OptOut, ParseFileError, Report, Scope, ScopeItem,
+};
+
+use crate::{Definition, Definitions};
+
+/// Rust source file level getter definitions & calls fixer.
+///
+/// The getter definitions are collected from all the Rust files during a
+/// [scan](GetterFixer::scan) of the workspace. The calls are then renamed
+/// according to the definitions: a call to a getter which is renamed uses the
+/// new name of the definition and a call to a getter which is kept unchanged
+/// is not renamed. Calls to unknown getters fall back to the calls heuristics.
+///
+/// Optionally, the getters [mentioned](mention) in the comments & the
+/// documentation prose are renamed when their definitions are renamed.
+pub struct GetterFixer {
+    def_fixer: GetterDefFixer,
+    call_fixer: GetterCallFixer,
+    definitions: Definitions,
+    scanned: Mutex<HashMap<PathBuf, Scanned>>,
+    report: Option<Arc<Report>>,
+    must_rename_mentions: bool,
+}
+
+/// The result of scanning a Rust file.
+#[derive(Debug)]
+struct Scanned {
+    def_edits: Vec<Edit>,
+    def_candidates: Vec<Candidate>,
+    call_candidates: Vec<Candidate>,
+    /// Lines of the items opted out of the renaming.
+    opted_out_lines: Vec<RangeInclusive<usize>>,
+}
+
+impl GetterFixer {
+    pub fn new(identification_mode: IdentificationMode, doc_alias_mode: DocAliasMode) -> Self {
+        GetterFixer {
+            def_fixer: GetterDefFixer::new(identification_mode, doc_alias_mode),
+            call_fixer: GetterCallFixer::new(identification_mode),
+            definitions: Definitions::default(),
+            scanned: Mutex::new(HashMap::new()),
+            report: None,
+            must_rename_mentions: false,
+        }
+    }
+
+    /// Also renames the getters mentioned in the comments & documentation prose.
+    ///
+    /// Only the mentions of getters which definitions are renamed are renamed.
+    /// The other mentions are logged & reported as skipped.
+    pub fn with_mentions(mut self) -> Self {
+        self.must_rename_mentions = true;
+        self
+    }
+
+    /// Adds the renaming candidates of each processed file to the `report`.
+    pub fn with_report(mut self, report: Arc<Report>) -> Self {
+        self.report = Some(report);
+        self
+    }
+
+    /// Scans the Rust files from `path`, collecting the getter definitions.
+    ///
+    /// The files are selected according to the `settings`. Each file is parsed
+    /// once: the result is kept for the subsequent traversal.
+    pub fn scan(&self, path: &Path, settings: &TraversalSettings) -> Result<(), Error> {
+        let settings = TraversalSettings {
+            action: Action::Scan,
+            ..*settings
+        };
+        self.traverse(path, &settings, &None)?;
+
+        debug!("{} getter definition name(s) found", self.definitions.len());
+
+        Ok(())
+    }
+
+    /// Returns the getter definitions collected so far.
+    pub fn definitions(&self) -> &Definitions {
+        &self.definitions
+    }
+
+    fn scan_source(&self, path: &Path, source_code: &str) -> Result<Scanned, Error> {
+        if markdown::is_markdown(path) {
+            let (def_edits, def_candidates) =
+                self.def_fixer.collect_markdown_edits(path, source_code);
+            for candidate in def_candidates.iter() {
+                self.definitions.add(candidate);
+            }
+
+            return Ok(Scanned {
+                def_edits,
+                def_candidates,
+                call_candidates: self
+                    .call_fixer
+                    .collect_markdown_candidates(path, source_code),
+                opted_out_lines: Vec::new(),
+            });
+        }
+
+        // Analyze Rust code
+        let syntax_tree = match syn::parse_file(source_code) {
+            Ok(syntax_tree) => syntax_tree,
+            Err(error) => {
+                return Err(
+                    ParseFileError::new(error, path.to_owned(), source_code.to_string()).into(),
+                );
+            }
+        };
+
+        let (def_edits, def_candidates) =
+            self.def_fixer
+                .collect_syntax_tree_edits(path, source_code, &syntax_tree);
+        for candidate in def_candidates.iter() {
+            self.definitions.add(candidate);
+        }
+
+        Ok(Scanned {
+            def_edits,
+            def_candidates,
+            call_candidates: self
+                .call_fixer
+                .collect_candidates(path, source_code, &syntax_tree),
+            opted_out_lines: OptOut::new(source_code).opted_out_lines(&syntax_tree),
+        })
+    }
+
+    /// Resolves the getter `call` using the known definitions.
+    fn resolve(&self, call: Candidate) -> Candidate {
+        if call.is_opted_out() {
+            return call;
+        }
+
+        let definition = match self.definitions.get(&call.name) {
+            Some(definition) => definition,
+            None => return call,
+        };
+
+        // Name generated by a concatenation, e.g. `[<get_ $field>]`
+        if let Some(concat) = paste::Concat::from_name(&call.name) {
+            return match definition {
+                Definition::Renamed(returns_bool) => {
+                    Candidate::generated(&concat, call.line, &call.scope, returns_bool, None)
+                }
+                Definition::Kept if !call.is_skipped() => Candidate::generated(
+                    &concat,
+                    call.line,
+                    &call.scope,
+                    ReturnsBool::Maybe,
+                    Some(NonGetterReason::DefinitionKept),
+                ),
+                Definition::Kept | Definition::Ambiguous => call,
+            };
+        }
+
+        match definition {
+            Definition::Renamed(returns_bool) => {
+                match Getter::try_new(call.name.clone(), returns_bool, call.line) {
+                    Ok(getter) => Candidate::renamed(&getter, &call.scope),
+                    Err(_) => call,
+                }
+            }
+            Definition::Kept if !call.is_skipped() => {
+                match Getter::try_new(call.name.clone(), ReturnsBool::Maybe, call.line) {
+                    Ok(getter) => {
+                        Candidate::skipped(&getter, &call.scope, NonGetterReason::DefinitionKept)
+                    }
+                    Err(_) => call,
+                }
+            }
+            Definition::Kept | Definition::Ambiguous => call,
+        }
+    }
+
+    /// Adds the edits renaming the getters mentioned in the comments &
+    /// documentation prose of `source_code` to `edits`.
+    ///
+    /// The mentions located in the `opted_out_lines` are left unchanged.
+    ///
+    /// Returns the candidates for the mentions, including the skipped ones.
+    fn rename_mentions(
+        &self,
+        path: &Path,
+        source_code: &str,
+        opted_out_lines: &[RangeInclusive<usize>],
+        edits: &mut Vec<Edit>,
+    ) -> Vec<Candidate> {
+        let mentions = if markdown::is_markdown(path) {
+            mention::find_in_markdown(source_code)
+        } else {
+            mention::find(source_code)
+        };
+
+        let scope = Scope::from_file(path).join(ScopeItem::Comment);
+        let mut candidates = Vec::new();
+        for mention in mentions {
+            if edits.iter().any(|edit| edit.overlaps(&mention.range)) {
+                continue;
+            }
+
+            let (line, column) = edit::position(source_code, mention.range.start);
+            let (returns_bool, reason) = match self.definitions.get(&mention.name) {
+                _ if opted_out_lines.iter().any(|lines| lines.contains(&line)) => {
+                    (ReturnsBool::Maybe, Some(NonGetterReason::OptedOut))
+                }
+                Some(Definition::Renamed(returns_bool)) => (returns_bool, None),
+                Some(Definition::Kept) => {
+                    (ReturnsBool::Maybe, Some(NonGetterReason::DefinitionKept))
+                }
+                Some(Definition::Ambiguous) | None => (
+                    ReturnsBool::Maybe,
+                    Some(NonGetterReason::NoRenamedDefinition),
+                ),
+            };
+
+            let getter = match Getter::try_new(mention.name, returns_bool, line) {
+                Ok(getter) => getter,
+                Err(_) => continue,
+            };
+
+            match reason {
+                None => {
+                    edits.push(Edit::rename(
+                        mention.range,
+                        getter.new_name.as_str(),
+                        &scope,
+                    ));
+                    candidates.push(Candidate::renamed(&getter, &scope));
+                }
+                Some(reason) => {
+                    info!(
+                        "{}:{}:{}: mention of {}() left unchanged: {}",
+                        path.display(),
+                        line,
+                        column,
+                        getter.name,
+                        reason,
+                    );
+                    candidates.push(Candidate::skipped(&getter, &scope, reason));
+                }
+            }
+        }
+
+        candidates
+    }
+}
+
+impl CrateTraverser for GetterFixer {
+    /// Collects the edits renaming the getter definitions & calls in `source_code`.
+    ///
+    /// If the file wasn't scanned, it is scanned now and the definitions found
+    /// in this file are taken into account.
+    fn collect_edits(&self, path: &Path, source_code: &str) -> Result<Vec<Edit>, Error> {
+        let scanned = self.scanned.lock().expect("scanned poisoned").remove(path);
+        let scanned = match scanned {
+            Some(scanned) => scanned,
+            None => self.scan_source(path, source_code)?,
+        };
+
+        let call_candidates: Vec<Candidate> = scanned
+            .call_candidates
+            .into_iter()
+            .map(|call| self.resolve(call))
+            .collect();
+
+        let mut edits = scanned.def_edits;
+        rename_calls(
+            source_code,
+            call_candidates
+                .iter()
+                .filter(|candidate| !candidate.is_skipped()),
+            &mut edits,
+        );
+        let mention_candidates = if self.must_rename_mentions {
+            self.rename_mentions(path, source_code, &scanned.opted_out_lines, &mut edits)
+        } else {
+            Vec::new()
+        };
+        edits.sort_by_key(|edit| edit.range.start);
+
+        if let Some(report) = self.report.as_ref() {
+            let mut candidates = scanned.def_candidates;
+            candidates.extend(call_candidates);
+            candidates.extend(mention_candidates);
+            report.add(path, source_code, candidates);
+        }
+
+        Ok(edits)
+    }
+
+    /// Scans the Rust file at `path` and keeps the result for the traversal.
+    fn scan_rust_file(&self, path: &Path) -> Result<(), Error> {
+        let source_code =
+            fs::read_to_string(path).map_err(|err| Error::ReadFile(path.to_owned(), err))?;
+
+        let scanned = self.scan_source(path, &source_code)?;
+        self.scanned
+            .lock()
+            .expect("scanned poisoned")
+            .insert(path.to_owned(), scanned);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rules::dir_entry::DefaultDirEntryFilter;
+    use utils::test_utils::{temp_dir, write};
+
+    #[test]
+    fn resolve_calls() {
+        let root = temp_dir(env!("CARGO_PKG_NAME"), "resolve_calls");
+        write(
+            &root.join("src").join("lib.rs"),
+            "struct Foo;\n\
+             impl Foo {\n    \
+                 fn get_flag(&self) -> bool { true }\n    \
+                 fn get_generic<T: Default>(&self) -> T { T::default() }\n\
+             }\n",
+        );
+        let main_path = root.join("src").join("main.rs");
+        write(
+            &main_path,
+            "fn main() {\n    \
+                 let _ = foo.get_flag();\n    \
+                 let _: u32 = foo.get_generic();\n    \
+                 let _ = bar.get_other();\n\
+             }\n",
+        );
+
+        let fixer = GetterFixer::new(IdentificationMode::Conservative, DocAliasMode::Discard);
+        fixer
+            .scan(&root, &TraversalSettings::new(&DefaultDirEntryFilter))
+            .unwrap();
+        assert_eq!(
+            fixer.definitions().get("get_flag"),
+            Some(Definition::Renamed(ReturnsBool::True)),
+        );
+        assert_eq!(
+            fixer.definitions().get("get_generic"),
+            Some(Definition::Kept),
+        );
+
+        let source_code = fs::read_to_string(&main_path).unwrap();
+        assert_eq!(
+            fixer.fix(&main_path, &source_code).unwrap().unwrap(),
+            "fn main() {\n    \
+                 let _ = foo.is_flag();\n    \
+                 let _: u32 = foo.get_generic();\n    \
+                 let _ = bar.other();\n\
+             }\n",
+        );
+    }
+
+    #[test]
+    fn resolve_generated_calls() {
+        let root = temp_dir(env!("CARGO_PKG_NAME"), "resolve_generated_calls");
+        let lib_path = root.join("src").join("lib.rs");
+        write(
+            &lib_path,
+            "macro_rules! getters(\n    \
+                 ($flag:ident, $field:ident: $type_:ty) => {\n        \
+                     paste::paste! {\n            \
+                         impl Foo {\n                \
+                             fn [<get_ $flag>](&self) -> bool { self.$flag }\n                \
+                             fn [<get_ $field>](&self) -> $type_ { self.$field }\n                \
+                             fn both(&self) -> bool { self.[<get_ $flag>]() && self.[<get_ $field>]() }\n            \
+                         }\n        \
+                     }\n    \
+                 };\n\
+             );\n",
+        );
+
+        let fixer = GetterFixer::new(IdentificationMode::AllGetFunctions, DocAliasMode::Discard);
+        fixer
+            .scan(&root, &TraversalSettings::new(&DefaultDirEntryFilter))
+            .unwrap();
+        assert_eq!(
+            fixer.definitions().get("[<get_ $flag>]"),
+            Some(Definition::Renamed(ReturnsBool::True)),
+        );
+        assert_eq!(
+            fixer.definitions().get("[<get_ $field>]"),
+            Some(Definition::Kept),
+        );
+
+        let source_code = fs::read_to_string(&lib_path).unwrap();
+        assert_eq!(
+            fixer.fix(&lib_path, &source_code).unwrap().unwrap(),
+            "macro_rules! getters(\n    \
+                 ($flag:ident, $field:ident: $type_:ty) => {\n        \
+                     paste::paste! {\n            \
+                         impl Foo {\n                \
+                             fn [<is_ $flag>](&self) -> bool { self.$flag }\n                \
+                             fn [<get_ $field>](&self) -> $type_ { self.$field }\n                \
+                             fn both(&self) -> bool { self.[<is_ $flag>]() && self.[<get_ $field>]() }\n            \
+                         }\n        \
+                     }\n    \
+                 };\n\
+             );\n",
+        );
+    }
+
+    #[test]
+    fn rename_mentions() {
+        let root = temp_dir(env!("CARGO_PKG_NAME"), "rename_mentions");
+        let lib_path = root.join("src").join("lib.rs");
+        write(
+            &lib_path,
+            "//! See [`Foo::get_flag`] & `get_generic`.\n\
+             struct Foo;\n\
+             impl Foo {\n    \
+                 /// Same as get_flag() but also get_other().\n    \
+                 fn get_flag(&self) -> bool { true }\n    \
+                 fn get_generic<T: Default>(&self) -> T { T::default() } // get_flag()\n\
+             }\n\
+             impl Foo {\n    \
+                 /// Calls get_flag().\n    \
+                 #[cfg_attr(any(), fix_getters::skip)]\n    \
+                 fn get_raw_flag(&self) -> bool { self.get_flag() }\n\
+             }\n\
+             // fix-getters: skip\n\
+             // Mirrors get_flag().\n\
+             /// Same as get_flag().\n\
+             fn get_c_flag(foo: &Foo) -> bool {\n    \
+                 // get_flag()\n    \
+                 foo.get_flag()\n\
+             }\n",
+        );
+
+        let fixer = GetterFixer::new(IdentificationMode::Conservative, DocAliasMode::Discard)
+            .with_mentions();
+        fixer
+            .scan(&root, &TraversalSettings::new(&DefaultDirEntryFilter))
+            .unwrap();
+
+        let source_code = fs::read_to_string(&lib_path).unwrap();
+        assert_eq!(
+            fixer.fix(&lib_path, &source_code).unwrap().unwrap(),
+            "//! See [`Foo::is_flag`] & `get_generic`.\n\
+             struct Foo;\n\
+             impl Foo {\n    \
+                 /// Same as is_flag() but also get_other().\n    \
+                 fn is_flag(&self) -> bool { true }\n    \
+                 fn get_generic<T: Default>(&self) -> T { T::default() } // is_flag()\n\
+             }\n\
+             impl Foo {\n    \
+                 /// Calls get_flag().\n    \
+                 #[cfg_attr(any(), fix_getters::skip)]\n    \
+                 fn get_raw_flag(&self) -> bool { self.get_flag() }\n\
+             }\n\
+             // fix-getters: skip\n\
+             // Mirrors get_flag().\n\
+             /// Same as get_flag().\n\
+             fn get_c_flag(foo: &Foo) -> bool {\n    \
+                 // get_flag()\n    \
+                 foo.get_flag()\n\
+             }\n",
+        );
+    }
+}
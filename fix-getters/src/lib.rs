@@ -0,0 +1,13 @@
+//! Getter definitions & calls fixer.
+//!
+//! Removes the `get` prefix from the eligible getter definitions and renames
+//! their call sites consistently.
+
+mod definitions;
+pub use definitions::{Definition, Definitions};
+
+mod fixer;
+pub use fixer::GetterFixer;
+
+pub mod workspace;
+pub use workspace::{Package, Workspace};
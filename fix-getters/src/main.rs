@@ -0,0 +1,200 @@
+use calls::GetterCallFixer;
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use def::{DocAliasMode, GetterDefFixer};
+use fix_getters::GetterFixer;
+use log::{error, info};
+use rules::dir_entry::Glob;
+use std::{path::Path, process, sync::Arc};
+use utils::{
+    cli::{common_args, init_logger, mentions_arg, no_doc_aliases_arg, path_args, Options},
+    Journal, UndoFilter,
+};
+
+fn main() {
+    let m = App::new(clap::crate_name!())
+        .version(clap::crate_version!())
+        .author(clap::crate_authors!())
+        .about(clap::crate_description!())
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(
+            SubCommand::with_name("def")
+                .about("Fix getters definition")
+                .arg(no_doc_aliases_arg())
+                .args(&common_args())
+                .args(&path_args()),
+        )
+        .subcommand(
+            SubCommand::with_name("calls")
+                .about("Fix getters call sites")
+                .args(&common_args())
+                .args(&path_args()),
+        )
+        .subcommand(
+            SubCommand::with_name("all")
+                .about("Fix getters definition & call sites, using the definitions for the calls")
+                .arg(no_doc_aliases_arg())
+                .arg(mentions_arg())
+                .args(&common_args())
+                .args(&path_args()),
+        )
+        .subcommand(
+            SubCommand::with_name("undo")
+                .about("Undo the changes recorded in a journal")
+                .arg(
+                    Arg::with_name("path")
+                        .long("path")
+                        .value_name("GLOB")
+                        .multiple(true)
+                        .number_of_values(1)
+                        .help("Only undo the changes to the files matching this pattern (can be repeated)"),
+                )
+                .arg(
+                    Arg::with_name("scope")
+                        .long("scope")
+                        .value_name("SCOPE")
+                        .multiple(true)
+                        .number_of_values(1)
+                        .help("Only undo the renaming of the functions defined in this scope, e.g. `Button` or `widget::impl Button`, along with their calls (can be repeated)"),
+                )
+                .arg(
+                    Arg::with_name("name")
+                        .long("name")
+                        .value_name("NAME")
+                        .multiple(true)
+                        .number_of_values(1)
+                        .help("Only undo the renaming of the functions with this original name (can be repeated)"),
+                )
+                .arg(
+                    Arg::with_name("quiet")
+                        .short("q")
+                        .long("quiet")
+                        .help("Run silently"),
+                )
+                .arg(
+                    Arg::with_name("verbose")
+                        .short("v")
+                        .long("verbose")
+                        .help("Show detailed logs"),
+                )
+                .arg(
+                    Arg::with_name("JOURNAL")
+                        .required(true)
+                        .help("Journal written using `--journal`"),
+                ),
+        )
+        .get_matches();
+
+    let (command, m) = match m.subcommand() {
+        (command, Some(m)) => (command, m),
+        _ => unreachable!("subcommand required"),
+    };
+
+    init_logger(m);
+    if command == "undo" {
+        undo(m);
+        return;
+    }
+
+    let options = Options::from_matches(m);
+
+    let identification_mode = options.identification_mode;
+    let doc_alias_mode = if m.is_present("no-doc-aliases") {
+        DocAliasMode::Discard
+    } else {
+        DocAliasMode::Generate
+    };
+
+    match command {
+        "def" => {
+            let fixer = GetterDefFixer::new(identification_mode, doc_alias_mode);
+            let fixer = match options.report.as_ref() {
+                Some(report) => fixer.with_report(Arc::clone(report)),
+                None => fixer,
+            };
+            options.run(fixer);
+        }
+        "calls" => {
+            let fixer = GetterCallFixer::new(identification_mode);
+            let fixer = match options.report.as_ref() {
+                Some(report) => fixer.with_report(Arc::clone(report)),
+                None => fixer,
+            };
+            options.run(fixer);
+        }
+        "all" => {
+            let mut fixer = GetterFixer::new(identification_mode, doc_alias_mode);
+            if m.is_present("mentions") {
+                fixer = fixer.with_mentions();
+            }
+            let fixer = match options.report.as_ref() {
+                Some(report) => fixer.with_report(Arc::clone(report)),
+                None => fixer,
+            };
+
+            // The decisions for the definitions apply to the calls
+            let options = options.with_decisions_by_name();
+            // Standard streams are processed on the fly
+            if !options.use_stdio() {
+                info!("Collecting definitions {:?}", options.path);
+                if let Err(error) = fixer.scan(&options.path, &options.settings()) {
+                    options.log_error(&error);
+                    process::exit(1);
+                }
+            }
+            options.run(fixer);
+        }
+        _ => unreachable!("unknown subcommand {}", command),
+    }
+}
+
+/// Undoes the changes recorded in the journal & selected by the arguments.
+///
+/// The journal is updated with the remaining changes.
+fn undo(m: &ArgMatches) {
+    let journal_path = Path::new(m.value_of("JOURNAL").expect("required argument"));
+    let journal = match Journal::load(journal_path) {
+        Ok(journal) => journal,
+        Err(error) => {
+            error!("{}", error);
+            process::exit(1);
+        }
+    };
+
+    let filter = UndoFilter {
+        paths: m
+            .values_of("path")
+            .into_iter()
+            .flatten()
+            .map(Glob::new)
+            .collect(),
+        scopes: m
+            .values_of("scope")
+            .into_iter()
+            .flatten()
+            .map(String::from)
+            .collect(),
+        names: m
+            .values_of("name")
+            .into_iter()
+            .flatten()
+            .map(String::from)
+            .collect(),
+    };
+
+    let undone = match journal.undo(&filter) {
+        Ok(undone) => undone,
+        Err(error) => {
+            error!("{}", error);
+            process::exit(1);
+        }
+    };
+    for (path, entries) in undone.iter() {
+        info!("Undone {} change(s) in {:?}", entries.len(), path);
+    }
+
+    if let Err(error) = journal.save(journal_path) {
+        error!("{}", error);
+        process::exit(1);
+    }
+    info!("{} change(s) left in {:?}", journal.len(), journal_path);
+}
@@ -0,0 +1,490 @@
+//! Cargo workspace discovery.
+
+use rules::dir_entry::{self, CheckError, CheckOk, DefaultDirEntryFilter, DirEntryFilter, Glob};
+use std::{
+    env,
+    fmt::{self, Display},
+    fs::{self, DirEntry},
+    path::{Path, PathBuf},
+};
+use utils::{manifest::TargetKind, module_graph::normalize, Manifest};
+
+/// Directories of the targets which are only processed with `--all-targets`.
+const EXTRA_TARGET_DIRS: [&str; 3] = ["tests", "examples", "benches"];
+
+/// Kinds of the targets which are only processed with `--all-targets`.
+const EXTRA_TARGET_KINDS: [TargetKind; 3] =
+    [TargetKind::Test, TargetKind::Example, TargetKind::Bench];
+
+/// A package member of a [`Workspace`].
+#[derive(Debug)]
+pub struct Package {
+    pub name: String,
+    pub version: Option<String>,
+    pub path: PathBuf,
+    /// Paths of the tests, examples & benches declared in the manifest.
+    extra_target_paths: Vec<PathBuf>,
+}
+
+impl Display for Package {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.name)?;
+        if let Some(version) = self.version.as_ref() {
+            write!(f, " v{}", version)?;
+        }
+        write!(f, " ({})", self.path.display())
+    }
+}
+
+/// A Cargo workspace.
+///
+/// A package which doesn't belong to a workspace is handled
+/// as a workspace with a single member.
+#[derive(Debug)]
+pub struct Workspace {
+    pub root: PathBuf,
+    pub members: Vec<Package>,
+    /// Index of the member for the manifest the [`Workspace`] was located from.
+    current: Option<usize>,
+}
+
+impl Workspace {
+    /// Locates the [`Workspace`] for the `manifest_path`.
+    ///
+    /// If no `manifest_path` is provided, the nearest `Cargo.toml` file
+    /// from the current directory is used, like `cargo` does.
+    pub fn locate(manifest_path: Option<&Path>) -> Result<Self, Error> {
+        let manifest_path = match manifest_path {
+            Some(manifest_path) => {
+                if !manifest_path.is_file() {
+                    return Err(Error::ManifestNotFound(manifest_path.to_owned()));
+                }
+                fs::canonicalize(manifest_path)
+                    .map_err(|err| utils::Error::ReadFile(manifest_path.to_owned(), err))?
+            }
+            None => {
+                let current_dir =
+                    env::current_dir().map_err(|err| utils::Error::ReadDir(".".into(), err))?;
+                current_dir
+                    .ancestors()
+                    .map(|dir| dir.join("Cargo.toml"))
+                    .find(|path| path.is_file())
+                    .ok_or(Error::ManifestNotFound(current_dir))?
+            }
+        };
+
+        let package_dir = manifest_path
+            .parent()
+            .expect("manifest in a directory")
+            .to_owned();
+        let manifest = Manifest::read(&manifest_path)?;
+
+        let mut workspace = if manifest.workspace.is_some() {
+            Workspace::from_root(package_dir.clone(), manifest)?
+        } else {
+            match Workspace::find_parent(&package_dir)? {
+                Some(workspace) => workspace,
+                None => Workspace {
+                    members: vec![Package::new(package_dir.clone(), &manifest)],
+                    root: package_dir.clone(),
+                    current: None,
+                },
+            }
+        };
+
+        workspace.current = workspace
+            .members
+            .iter()
+            .position(|package| package.path == package_dir);
+
+        Ok(workspace)
+    }
+
+    /// Selects the packages to process.
+    ///
+    /// - `specs` selects the packages matching the [`PackageSpec`]s.
+    /// - `all` selects all the members of the workspace.
+    ///
+    /// Otherwise, the current package is selected, or all the members
+    /// if the manifest is a virtual manifest.
+    pub fn select(&self, specs: &[&str], all: bool) -> Result<Vec<&Package>, Error> {
+        if !specs.is_empty() {
+            return specs
+                .iter()
+                .map(|spec| {
+                    let package_spec = PackageSpec::parse(spec);
+                    self.members
+                        .iter()
+                        .find(|package| package_spec.matches(package))
+                        .ok_or_else(|| Error::PackageNotFound(spec.to_string()))
+                })
+                .collect();
+        }
+
+        match self.current.filter(|_| !all) {
+            Some(current) => Ok(vec![&self.members[current]]),
+            None => Ok(self.members.iter().collect()),
+        }
+    }
+
+    /// Returns the [`PackageFilter`] for the `package`.
+    ///
+    /// The other members nested in the `package` directory are skipped.
+    /// The tests, examples & benches are skipped unless `all_targets` is set,
+    /// whether they are located in the conventional directories or declared
+    /// in the manifest.
+    pub fn package_filter(&self, package: &Package, all_targets: bool) -> PackageFilter {
+        let mut excluded: Vec<PathBuf> = self
+            .members
+            .iter()
+            .filter(|member| member.path != package.path && member.path.starts_with(&package.path))
+            .map(|member| member.path.clone())
+            .collect();
+
+        if !all_targets {
+            excluded.extend(EXTRA_TARGET_DIRS.iter().map(|dir| package.path.join(dir)));
+            excluded.extend(package.extra_target_paths.iter().cloned());
+        }
+
+        PackageFilter { excluded }
+    }
+
+    /// Searches the ancestors of `package_dir` for a workspace including it.
+    fn find_parent(package_dir: &Path) -> Result<Option<Self>, Error> {
+        for dir in package_dir.ancestors().skip(1) {
+            let manifest_path = dir.join("Cargo.toml");
+            if !manifest_path.is_file() {
+                continue;
+            }
+
+            let manifest = Manifest::read(&manifest_path)?;
+            if manifest.workspace.is_none() {
+                continue;
+            }
+
+            let workspace = Workspace::from_root(dir.to_owned(), manifest)?;
+            if workspace
+                .members
+                .iter()
+                .any(|package| package.path == package_dir)
+            {
+                return Ok(Some(workspace));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Builds the [`Workspace`] from the root `manifest`.
+    fn from_root(root: PathBuf, manifest: Manifest) -> Result<Self, Error> {
+        let section = manifest.workspace.as_ref().expect("workspace manifest");
+        // Anchor the patterns to the workspace root
+        let members: Vec<Glob> = section
+            .members
+            .iter()
+            .map(|member| Glob::new(&format!("./{}", member)))
+            .collect();
+        let exclude: Vec<PathBuf> = section.exclude.iter().map(|path| root.join(path)).collect();
+
+        let mut workspace = Workspace {
+            members: Vec::new(),
+            root,
+            current: None,
+        };
+        if manifest.is_package {
+            workspace
+                .members
+                .push(Package::new(workspace.root.clone(), &manifest));
+        }
+
+        let mut package_dirs = Vec::new();
+        find_package_dirs(&workspace.root, &mut package_dirs)?;
+        for package_dir in package_dirs {
+            let rel_path = package_dir
+                .strip_prefix(&workspace.root)
+                .expect("in the workspace");
+            if !members.iter().any(|glob| glob.matches(rel_path))
+                || exclude.iter().any(|path| package_dir.starts_with(path))
+            {
+                continue;
+            }
+
+            let manifest = Manifest::read(&package_dir.join("Cargo.toml"))?;
+            if manifest.is_package {
+                workspace.members.push(Package::new(package_dir, &manifest));
+            }
+        }
+
+        Ok(workspace)
+    }
+}
+
+impl Package {
+    fn new(path: PathBuf, manifest: &Manifest) -> Self {
+        Package {
+            name: manifest.name.clone().unwrap_or_else(|| {
+                path.file_name()
+                    .map_or_else(String::new, |name| name.to_string_lossy().to_string())
+            }),
+            version: manifest.version.clone(),
+            extra_target_paths: EXTRA_TARGET_KINDS
+                .iter()
+                .flat_map(|kind| manifest.target_paths(*kind))
+                .map(|target_path| normalize(&path.join(target_path)))
+                .collect(),
+            path,
+        }
+    }
+}
+
+/// A package ID specification, as accepted by `cargo --package`.
+///
+/// The supported forms are `name`, `name@version` and the URL forms,
+/// e.g. `path+file:///path/to/foo#foo@1.2.0`. A partial version such as
+/// `1.2` matches all the versions starting with the same components.
+#[derive(Debug, Default, PartialEq)]
+pub struct PackageSpec {
+    name: Option<String>,
+    version: Option<String>,
+    path: Option<PathBuf>,
+}
+
+impl PackageSpec {
+    /// Parses the `spec`.
+    pub fn parse(spec: &str) -> Self {
+        let (url, fragment) = match spec.split_once("://") {
+            Some(_) => match spec.split_once('#') {
+                Some((url, fragment)) => (Some(url), Some(fragment)),
+                None => (Some(spec), None),
+            },
+            None => (None, Some(spec)),
+        };
+
+        let mut package_spec = PackageSpec::default();
+        if let Some(url) = url {
+            let url = url.trim_end_matches('/');
+            package_spec.path = url
+                .strip_prefix("path+file://")
+                .or_else(|| url.strip_prefix("file://"))
+                .map(PathBuf::from);
+            package_spec.name = url.rsplit('/').next().map(String::from);
+        }
+
+        if let Some(fragment) = fragment {
+            match fragment
+                .split_once('@')
+                .or_else(|| fragment.split_once(':'))
+            {
+                Some((name, version)) => {
+                    package_spec.name = Some(name.to_string());
+                    package_spec.version = Some(version.to_string());
+                }
+                // A URL fragment can hold the version only, e.g. `file:///foo#1.2.0`
+                None if url.is_some() && fragment.starts_with(|c: char| c.is_ascii_digit()) => {
+                    package_spec.version = Some(fragment.to_string());
+                }
+                None => package_spec.name = Some(fragment.to_string()),
+            }
+        }
+
+        package_spec
+    }
+
+    /// Checks whether the `package` matches this [`PackageSpec`].
+    pub fn matches(&self, package: &Package) -> bool {
+        if self.name.as_ref().is_some_and(|name| *name != package.name) {
+            return false;
+        }
+
+        if let Some(path) = self.path.as_ref() {
+            if *path != package.path && fs::canonicalize(path).ok().as_ref() != Some(&package.path)
+            {
+                return false;
+            }
+        }
+
+        match (self.version.as_ref(), package.version.as_ref()) {
+            (None, _) => true,
+            (Some(version), Some(package_version)) => {
+                let mut package_components = package_version.split('.');
+                version
+                    .split('.')
+                    .all(|component| package_components.next() == Some(component))
+            }
+            (Some(_), None) => false,
+        }
+    }
+}
+
+/// Collects the sub-directories of `dir` containing a `Cargo.toml` file.
+fn find_package_dirs(dir: &Path, package_dirs: &mut Vec<PathBuf>) -> Result<(), Error> {
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(dir).map_err(|err| utils::Error::ReadDir(dir.to_owned(), err))? {
+        entries.push(entry.map_err(|err| utils::Error::ReadEntry(dir.to_owned(), err))?);
+    }
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        if let CheckOk::Directory = DefaultDirEntryFilter.check(&entry)? {
+            let path = entry.path();
+            if path.join("Cargo.toml").is_file() {
+                package_dirs.push(path.clone());
+            }
+            find_package_dirs(&path, package_dirs)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// A [`DirEntryFilter`] restricting the traversal to a [`Package`].
+#[derive(Debug)]
+pub struct PackageFilter {
+    /// The excluded directories & files.
+    excluded: Vec<PathBuf>,
+}
+
+impl DirEntryFilter for PackageFilter {
+    fn check(&self, entry: &DirEntry) -> Result<CheckOk, CheckError> {
+        let res = dir_entry::check(entry)?;
+        if let CheckOk::Directory | CheckOk::RustFile = res {
+            if self.excluded.contains(&entry.path()) {
+                return Ok(CheckOk::Skip(
+                    entry.file_name().to_string_lossy().to_string(),
+                ));
+            }
+        }
+
+        Ok(res)
+    }
+
+    fn accepts_file(&self, path: &Path) -> bool {
+        !self
+            .excluded
+            .iter()
+            .any(|excluded| path.starts_with(excluded))
+    }
+}
+
+/// Cargo workspace discovery `Error`.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    ManifestNotFound(PathBuf),
+    PackageNotFound(String),
+    Utils(utils::Error),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::Error::*;
+
+        match self {
+            ManifestNotFound(path) => write!(f, "Could not find `Cargo.toml` from {:?}", path),
+            PackageNotFound(name) => write!(f, "Package `{}` not found in workspace", name),
+            Utils(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<utils::Error> for Error {
+    fn from(err: utils::Error) -> Self {
+        Error::Utils(err)
+    }
+}
+
+impl From<CheckError> for Error {
+    fn from(err: CheckError) -> Self {
+        Error::Utils(err.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use utils::test_utils::{temp_dir, write};
+
+    #[test]
+    fn locate_and_select() {
+        let root = temp_dir(env!("CARGO_PKG_NAME"), "workspace");
+
+        write(
+            &root.join("Cargo.toml"),
+            "[package]\nname = \"app\"\nversion = \"1.0.0\"\n\n\
+             [workspace]\nmembers = [\n    \"crates/*\",\n]\nexclude = [\"crates/legacy\"]\n",
+        );
+        write(
+            &root.join("crates").join("core").join("Cargo.toml"),
+            "[package]\nname = \"core\"\nversion = \"0.2.1\"\n\n\
+             [[test]]\nname = \"it\"\npath = \"check/./it.rs\"\n",
+        );
+        write(
+            &root.join("crates").join("legacy").join("Cargo.toml"),
+            "[package]\nname = \"legacy\"\n",
+        );
+        let root = fs::canonicalize(&root).unwrap();
+
+        let workspace = Workspace::locate(Some(&root.join("Cargo.toml"))).unwrap();
+        assert_eq!(workspace.root, root);
+        let names: Vec<&str> = workspace
+            .members
+            .iter()
+            .map(|package| package.name.as_str())
+            .collect();
+        assert_eq!(names, ["app", "core"]);
+
+        let selected = workspace.select(&[], false).unwrap();
+        assert_eq!(selected.len(), 1);
+        assert_eq!(
+            selected[0].to_string(),
+            format!("app v1.0.0 ({})", root.display())
+        );
+        assert_eq!(workspace.select(&[], true).unwrap().len(), 2);
+        assert!(matches!(
+            workspace.select(&["legacy"], false),
+            Err(Error::PackageNotFound(_))
+        ));
+
+        let core_url = format!("path+file://{}", root.join("crates").join("core").display());
+        for spec in [
+            "core@0.2.1".to_string(),
+            "core:0.2".to_string(),
+            core_url.clone(),
+            format!("{}#core@0.2.1", core_url),
+            format!("{}#0.2.1", core_url),
+        ]
+        .iter()
+        {
+            assert_eq!(workspace.select(&[spec], false).unwrap()[0].name, "core");
+        }
+        for spec in ["core@0.3", "core@0.2.1.1", "app@1.0.0.0"].iter() {
+            assert!(matches!(
+                workspace.select(&[spec], false),
+                Err(Error::PackageNotFound(_))
+            ));
+        }
+        assert!(workspace
+            .select(&[&format!("{}#app", core_url)], false)
+            .is_err());
+
+        let core_manifest = root.join("crates").join("core").join("Cargo.toml");
+        let workspace = Workspace::locate(Some(&core_manifest)).unwrap();
+        assert_eq!(workspace.root, root);
+        assert_eq!(workspace.select(&[], false).unwrap()[0].name, "core");
+
+        let filter = workspace.package_filter(&workspace.members[0], false);
+        assert!(filter.excluded.contains(&root.join("crates").join("core")));
+        assert!(filter.excluded.contains(&root.join("tests")));
+
+        let core_dir = root.join("crates").join("core");
+        let filter = workspace.package_filter(&workspace.members[1], false);
+        assert!(!filter.accepts_file(&core_dir.join("check").join("it.rs")));
+        assert!(filter.accepts_file(&core_dir.join("check").join("other.rs")));
+        assert!(!filter.accepts_file(&core_dir.join("tests").join("it.rs")));
+
+        let filter = workspace.package_filter(&workspace.members[1], true);
+        assert!(filter.accepts_file(&core_dir.join("check").join("it.rs")));
+    }
+}
@@ -7,7 +7,7 @@ use std::{
     fmt::{self, Display},
     fs::DirEntry,
     io,
-    path::PathBuf,
+    path::{Component, Path, PathBuf},
 };
 
 /// Directories to exclude from the fix process.
@@ -24,7 +24,17 @@ pub static EXCLUDED: Lazy<HashSet<&'static str>> = Lazy::new(|| {
     excluded
 });
 
+/// Directories which are not replicated when the output is written to a different root.
+pub static NOT_MIRRORED: Lazy<HashSet<&'static str>> = Lazy::new(|| {
+    let mut not_mirrored = HashSet::new();
+    not_mirrored.insert(".git");
+    not_mirrored.insert("target");
+    not_mirrored
+});
+
 /// Checks the given directory entry.
+///
+/// This applies the rules of the [`DefaultDirEntryFilter`].
 #[inline]
 pub fn check(entry: &DirEntry) -> Result<CheckOk, CheckError> {
     let entry_type = entry
@@ -52,6 +62,232 @@ pub fn check(entry: &DirEntry) -> Result<CheckOk, CheckError> {
     Ok(CheckOk::Skip(entry_name.to_string()))
 }
 
+/// A directory entry filter.
+///
+/// The filter decides which directories should be traversed and which
+/// files should be processed.
+pub trait DirEntryFilter {
+    /// Checks the given directory entry.
+    fn check(&self, entry: &DirEntry) -> Result<CheckOk, CheckError>;
+
+    /// Checks whether the Rust file at `path` should be processed.
+    ///
+    /// This applies to the files which are found by other means than
+    /// walking the directories, e.g. following the module graph.
+    /// All the files are accepted by default.
+    fn accepts_file(&self, _path: &Path) -> bool {
+        true
+    }
+}
+
+/// The default [`DirEntryFilter`].
+///
+/// Rust files are processed and directories are traversed unless they
+/// are listed in [`EXCLUDED`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DefaultDirEntryFilter;
+
+impl DirEntryFilter for DefaultDirEntryFilter {
+    #[inline]
+    fn check(&self, entry: &DirEntry) -> Result<CheckOk, CheckError> {
+        check(entry)
+    }
+}
+
+/// A glob based [`DirEntryFilter`].
+///
+/// The rules of the [`DefaultDirEntryFilter`] are applied first, then:
+///
+/// - Directories and files matching one of the `exclude` patterns are skipped.
+/// - If `include` patterns are defined, only the files matching one of them
+///   are processed. Directories are traversed unless they are excluded.
+///
+/// The same rules apply to the files found by other means than walking
+/// the directories, see [`DirEntryFilter::accepts_file`].
+///
+/// The patterns are matched against the entry path relative to the `root`.
+/// See [`Glob`] for the supported syntax.
+#[derive(Debug)]
+pub struct GlobDirEntryFilter {
+    root: PathBuf,
+    include: Vec<Glob>,
+    exclude: Vec<Glob>,
+}
+
+impl GlobDirEntryFilter {
+    /// Builds a [`GlobDirEntryFilter`] for the tree starting at `root`.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        GlobDirEntryFilter {
+            root: root.into(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+        }
+    }
+
+    /// Adds a pattern for the files to process.
+    ///
+    /// See [`Glob`] for the supported syntax.
+    pub fn include(&mut self, pattern: &str) -> &mut Self {
+        self.include.push(Glob::new(pattern));
+        self
+    }
+
+    /// Adds a pattern for the directories and files to skip.
+    ///
+    /// See [`Glob`] for the supported syntax.
+    pub fn exclude(&mut self, pattern: &str) -> &mut Self {
+        self.exclude.push(Glob::new(pattern));
+        self
+    }
+
+    fn relative_path<'a>(&self, path: &'a Path) -> &'a Path {
+        path.strip_prefix(&self.root)
+            .or_else(|_| path.strip_prefix(self.root.strip_prefix(".").unwrap_or(&self.root)))
+            .unwrap_or(path)
+    }
+}
+
+impl DirEntryFilter for GlobDirEntryFilter {
+    fn check(&self, entry: &DirEntry) -> Result<CheckOk, CheckError> {
+        let res = check(entry)?;
+        let is_file = match res {
+            CheckOk::Directory => false,
+            CheckOk::RustFile => true,
+            other => return Ok(other),
+        };
+
+        let path = entry.path();
+        let rel_path = self.relative_path(&path);
+
+        if self.exclude.iter().any(|glob| glob.matches(rel_path)) {
+            return Ok(CheckOk::Skip(
+                entry.file_name().to_string_lossy().to_string(),
+            ));
+        }
+
+        if is_file
+            && !self.include.is_empty()
+            && !self.include.iter().any(|glob| glob.matches(rel_path))
+        {
+            return Ok(CheckOk::Skip(
+                entry.file_name().to_string_lossy().to_string(),
+            ));
+        }
+
+        Ok(res)
+    }
+
+    fn accepts_file(&self, path: &Path) -> bool {
+        let rel_path = self.relative_path(path);
+
+        // Excluding a directory excludes the files it contains
+        let is_excluded = rel_path
+            .ancestors()
+            .filter(|path| !path.as_os_str().is_empty())
+            .any(|path| self.exclude.iter().any(|glob| glob.matches(path)));
+
+        !is_excluded
+            && (self.include.is_empty() || self.include.iter().any(|glob| glob.matches(rel_path)))
+    }
+}
+
+/// A path matching pattern.
+///
+/// The pattern is split in `/` separated segments which can use:
+///
+/// - `*` to match any sequence of characters in a segment.
+/// - `?` to match exactly one character in a segment.
+/// - `**` as a whole segment to match any number of segments.
+///
+/// A pattern without any `/` matches entries at any depth,
+/// e.g. `generated` is equivalent to `**/generated`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Glob {
+    pattern: String,
+    segments: Vec<String>,
+}
+
+impl Glob {
+    /// Builds a [`Glob`] from the provided `pattern`.
+    pub fn new(pattern: &str) -> Self {
+        let trimmed = pattern.trim_start_matches("./").trim_matches('/');
+
+        let mut segments: Vec<String> = trimmed
+            .split('/')
+            .filter(|segment| !segment.is_empty() && *segment != ".")
+            .map(str::to_string)
+            .collect();
+        if !pattern.contains('/') && segments.len() == 1 && segments[0] != "**" {
+            segments.insert(0, "**".to_string());
+        }
+
+        Glob {
+            pattern: pattern.to_string(),
+            segments,
+        }
+    }
+
+    /// Returns the pattern this [`Glob`] was built from.
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Checks whether the given relative `path` matches this [`Glob`].
+    pub fn matches(&self, path: &Path) -> bool {
+        let mut components = Vec::new();
+        for component in path.components() {
+            match component {
+                Component::Normal(name) => match name.to_str() {
+                    Some(name) => components.push(name),
+                    None => return false,
+                },
+                Component::CurDir => (),
+                _ => return false,
+            }
+        }
+
+        match_segments(&self.segments, &components)
+    }
+}
+
+impl Display for Glob {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        f.write_str(&self.pattern)
+    }
+}
+
+fn match_segments(segments: &[String], components: &[&str]) -> bool {
+    match segments.split_first() {
+        None => components.is_empty(),
+        Some((segment, rest)) if segment == "**" => {
+            (0..=components.len()).any(|skip| match_segments(rest, &components[skip..]))
+        }
+        Some((segment, rest)) => match components.split_first() {
+            Some((component, components)) => {
+                match_segment(segment.as_bytes(), component.as_bytes())
+                    && match_segments(rest, components)
+            }
+            None => false,
+        },
+    }
+}
+
+fn match_segment(pattern: &[u8], name: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some((b'*', rest)) => (0..=name.len()).any(|skip| match_segment(rest, &name[skip..])),
+        Some((b'?', rest)) => {
+            // Skip one UTF-8 encoded char
+            let mut char_len = 1;
+            while char_len < name.len() && (name[char_len] & 0xc0) == 0x80 {
+                char_len += 1;
+            }
+            !name.is_empty() && match_segment(rest, &name[char_len..])
+        }
+        Some((byte, rest)) => name.first() == Some(byte) && match_segment(rest, &name[1..]),
+    }
+}
+
 #[derive(Debug)]
 pub enum CheckOk {
     Directory,
@@ -78,3 +314,62 @@ impl Display for CheckError {
 }
 
 impl Error for CheckError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_segments() {
+        let glob = Glob::new("crates/gui/**");
+        assert!(glob.matches(Path::new("crates/gui/src/lib.rs")));
+        assert!(glob.matches(Path::new("crates/gui")));
+        assert!(!glob.matches(Path::new("crates/net/src/lib.rs")));
+        assert!(!glob.matches(Path::new("gui/src/lib.rs")));
+
+        let glob = Glob::new("**/generated/**");
+        assert!(glob.matches(Path::new("generated")));
+        assert!(glob.matches(Path::new("crates/gui/generated")));
+        assert!(glob.matches(Path::new("crates/gui/generated/widget.rs")));
+        assert!(!glob.matches(Path::new("crates/gui/generated_widget.rs")));
+
+        let glob = Glob::new("./src/*.rs");
+        assert!(glob.matches(Path::new("src/lib.rs")));
+        assert!(!glob.matches(Path::new("src/auto/mod.rs")));
+    }
+
+    #[test]
+    fn glob_any_depth() {
+        let glob = Glob::new("generated");
+        assert!(glob.matches(Path::new("generated")));
+        assert!(glob.matches(Path::new("crates/gui/generated")));
+        assert!(!glob.matches(Path::new("crates/gui/generated/widget.rs")));
+
+        let glob = Glob::new("*_gen.rs");
+        assert!(glob.matches(Path::new("widget_gen.rs")));
+        assert!(glob.matches(Path::new("src/widget_gen.rs")));
+        assert!(!glob.matches(Path::new("src/widget.rs")));
+    }
+
+    #[test]
+    fn glob_wildcards() {
+        let glob = Glob::new("src/w?dget*.rs");
+        assert!(glob.matches(Path::new("src/widget.rs")));
+        assert!(glob.matches(Path::new("src/wédget_ext.rs")));
+        assert!(!glob.matches(Path::new("src/wdget.rs")));
+        assert!(!glob.matches(Path::new("src/widget.rs.orig")));
+    }
+
+    #[test]
+    fn glob_filter_accepts_file() {
+        let mut filter = GlobDirEntryFilter::new("./repo");
+        filter.include("crates/gui/**").exclude("generated");
+
+        assert!(filter.accepts_file(Path::new("repo/crates/gui/src/lib.rs")));
+        assert!(filter.accepts_file(Path::new("./repo/crates/gui/src/lib.rs")));
+        assert!(!filter.accepts_file(Path::new("repo/crates/net/src/lib.rs")));
+        assert!(!filter.accepts_file(Path::new("repo/crates/gui/generated/widget.rs")));
+
+        assert!(DefaultDirEntryFilter.accepts_file(Path::new("repo/generated/widget.rs")));
+    }
+}
@@ -397,6 +397,7 @@ impl Display for RenameError {
 impl Error for RenameError {}
 
 #[cfg(test)]
+#[allow(clippy::needless_borrow)]
 mod tests {
     use super::*;
 
@@ -0,0 +1,115 @@
+//! Atomic file writes.
+
+use std::{
+    ffi::OsString,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::Error;
+
+/// Default suffix for the backup of the modified files.
+pub const DEFAULT_BACKUP_SUFFIX: &str = ".orig";
+
+/// Writes `content` to the file at `path` atomically.
+///
+/// The `content` is written to a temporary file in the same directory, which
+/// is then renamed to `path`, so that `path` is never left truncated. The
+/// permissions of the existing file are kept.
+///
+/// If `backup_suffix` is specified, the existing file is first copied to a
+/// file with the suffix appended to its name, e.g. `foo.rs.orig`.
+pub fn write(path: &Path, content: &[u8], backup_suffix: Option<&str>) -> Result<(), Error> {
+    let metadata = fs::metadata(path).ok();
+
+    if let Some(backup_suffix) = backup_suffix.filter(|_| metadata.is_some()) {
+        let backup_path = suffixed(path, backup_suffix);
+        fs::copy(path, &backup_path)
+            .map_err(|err| Error::CopyFile(path.to_owned(), backup_path, err))?;
+    }
+
+    let tmp_path = suffixed(path, &tmp_suffix());
+    let res =
+        write_tmp(&tmp_path, content, metadata.as_ref()).and_then(|()| fs::rename(&tmp_path, path));
+    if let Err(err) = res {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(Error::WriteFile(path.to_owned(), err));
+    }
+
+    Ok(())
+}
+
+fn write_tmp(tmp_path: &Path, content: &[u8], metadata: Option<&fs::Metadata>) -> io::Result<()> {
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(tmp_path)?;
+    file.write_all(content)?;
+    if let Some(metadata) = metadata {
+        file.set_permissions(metadata.permissions())?;
+    }
+
+    file.sync_all()
+}
+
+/// Returns a suffix which is unique to this process & this call.
+fn tmp_suffix() -> String {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    format!(
+        ".{}-{}.fix-getters.tmp",
+        process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed),
+    )
+}
+
+/// Returns `path` with the `suffix` appended to its file name.
+fn suffixed(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = OsString::from(path);
+    path.push(suffix);
+
+    path.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::temp_dir;
+
+    #[test]
+    fn write_with_backup() {
+        let root = temp_dir(env!("CARGO_PKG_NAME"), "atomic_write");
+
+        let path = root.join("lib.rs");
+        write(&path, b"fn get_a() {}\n", Some(DEFAULT_BACKUP_SUFFIX)).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "fn get_a() {}\n");
+        // No backup for a new file
+        assert!(!root.join("lib.rs.orig").exists());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+        }
+
+        write(&path, b"fn a() {}\n", Some(DEFAULT_BACKUP_SUFFIX)).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "fn a() {}\n");
+        assert_eq!(
+            fs::read_to_string(root.join("lib.rs.orig")).unwrap(),
+            "fn get_a() {}\n",
+        );
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o640);
+        }
+
+        // No temporary file left
+        assert_eq!(fs::read_dir(&root).unwrap().count(), 2);
+    }
+}
@@ -0,0 +1,164 @@
+//! Check mode findings.
+
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use crate::{edit, Edit, EditKind};
+
+/// A function which should be renamed.
+#[derive(Debug)]
+pub struct Finding {
+    pub path: PathBuf,
+    pub line: usize,
+    pub column: usize,
+    pub name: String,
+    pub new_name: String,
+}
+
+impl Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}: {}() -> {}()",
+            self.path.display(),
+            self.line,
+            self.column,
+            self.name,
+            self.new_name,
+        )
+    }
+}
+
+/// Findings of the check mode.
+///
+/// The renaming edits of each Rust file are added as the file is processed.
+/// The derive attribute edits are added as the renamings of the getters they
+/// generate. Other edits, such as doc alias insertions, are ignored. The
+/// findings are rendered in the order of the paths.
+#[derive(Debug, Default)]
+pub struct Check {
+    files: Mutex<BTreeMap<PathBuf, Vec<Finding>>>,
+}
+
+impl Check {
+    /// Adds the renamings from the `edits` to the `source_code` of the file at `path`.
+    pub fn add(&self, path: &Path, source_code: &str, edits: &[Edit]) {
+        let mut findings = Vec::new();
+        for edit in edits {
+            let (line, column) = edit::position(source_code, edit.range.start);
+            match &edit.kind {
+                EditKind::Rename { .. } => findings.push(Finding {
+                    path: path.to_owned(),
+                    line,
+                    column,
+                    name: source_code[edit.range.clone()].to_string(),
+                    new_name: edit.text.clone(),
+                }),
+                EditKind::DeriveAttribute { getters, .. } => {
+                    for (name, new_name) in getters {
+                        findings.push(Finding {
+                            path: path.to_owned(),
+                            line,
+                            column,
+                            name: name.clone(),
+                            new_name: new_name.clone(),
+                        });
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        if !findings.is_empty() {
+            findings.sort_by_key(|finding| (finding.line, finding.column));
+            self.files
+                .lock()
+                .expect("check poisoned")
+                .insert(path.to_owned(), findings);
+        }
+    }
+
+    /// Returns the number of findings.
+    pub fn len(&self) -> usize {
+        self.files
+            .lock()
+            .expect("check poisoned")
+            .values()
+            .map(Vec::len)
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.lock().expect("check poisoned").is_empty()
+    }
+
+    /// Writes the findings to the given `writer`, one per line.
+    pub fn write_to(&self, writer: &mut dyn Write) -> io::Result<()> {
+        for findings in self.files.lock().expect("check poisoned").values() {
+            for finding in findings {
+                writeln!(writer, "{}", finding)?;
+            }
+        }
+
+        writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Scope, ScopeItem};
+
+    #[test]
+    fn findings() {
+        let source_code = "fn get_a() {}\n\nfn f() {\n    é.get_b();\n}\n";
+        let edits = [
+            Edit::doc_alias(0, "#[doc(alias = \"get_a\")] "),
+            Edit::rename(3..8, "a", &Scope::from(ScopeItem::Fn("get_a".to_string()))),
+            Edit::rename(31..36, "b", &Scope::from(ScopeItem::Fn("f".to_string()))),
+        ];
+
+        let check = Check::default();
+        check.add(Path::new("src/lib.rs"), source_code, &edits);
+        assert_eq!(check.len(), 2);
+
+        let mut output = Vec::new();
+        check.write_to(&mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "src/lib.rs:1:4: get_a() -> a()\nsrc/lib.rs:4:7: get_b() -> b()\n",
+        );
+    }
+
+    #[test]
+    fn derived_findings() {
+        let source_code =
+            "#[getset(get = \"pub\", with_prefix)]\nstruct Foo {\n    a: u32,\n    b: bool,\n}\n";
+        let getters = vec![
+            ("get_a".to_string(), "a".to_string()),
+            ("get_b".to_string(), "is_b".to_string()),
+        ];
+        let edits = [Edit::derive_attribute(
+            20..33,
+            "",
+            &Scope::from(ScopeItem::Struct("Foo".to_string())),
+            getters,
+        )];
+
+        let check = Check::default();
+        check.add(Path::new("src/lib.rs"), source_code, &edits);
+        assert_eq!(check.len(), 2);
+
+        let mut output = Vec::new();
+        check.write_to(&mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "src/lib.rs:1:21: get_a() -> a()\nsrc/lib.rs:1:21: get_b() -> is_b()\n",
+        );
+    }
+}
@@ -0,0 +1,757 @@
+//! Command line helpers shared by the `fix-getters` binaries.
+//!
+//! Requires `feature` **`cli`**.
+
+use clap::{Arg, ArgMatches};
+use log::{error, info, LevelFilter};
+use rules::dir_entry::{DefaultDirEntryFilter, DirEntryFilter, Glob, GlobDirEntryFilter};
+use std::{
+    fs::File,
+    io::{self, IsTerminal, Read},
+    path::{Path, PathBuf},
+    process,
+    sync::Arc,
+    thread,
+};
+use stderrlog::ColorChoice;
+
+use crate::{
+    atomic_write::{self, DEFAULT_BACKUP_SUFFIX},
+    logger::Logger,
+    manifest::Config,
+    prelude::*,
+    Check, Decisions, Error, Errors, Journal, Manifest, Patch, Report, Review,
+};
+
+pub fn no_doc_aliases_arg() -> Arg<'static, 'static> {
+    Arg::with_name("no-doc-aliases")
+        .short("n")
+        .long("no-doc-aliases")
+        .help("Don't had doc aliases to the renamed functions")
+}
+
+pub fn mentions_arg() -> Arg<'static, 'static> {
+    Arg::with_name("mentions")
+        .long("mentions")
+        .help("Also rename the getters mentioned in comments & doc prose when their definitions are renamed")
+}
+
+/// Returns the arguments common to the subcommands.
+pub fn common_args() -> Vec<Arg<'static, 'static>> {
+    vec![
+        Arg::with_name("conservative")
+            .short("c")
+            .long("conservative")
+            .help("Be conservative when selecting getter functions"),
+        Arg::with_name("jobs")
+            .short("j")
+            .long("jobs")
+            .value_name("N")
+            .help("Number of files to process concurrently (default: number of CPUs)"),
+        Arg::with_name("dry-run")
+            .long("dry-run")
+            .conflicts_with("OUTPUT")
+            .help("Print the changes as a unified diff instead of applying them"),
+        Arg::with_name("patch")
+            .long("patch")
+            .value_name("FILE")
+            .conflicts_with("OUTPUT")
+            .help("Write the changes to a patch file instead of applying them"),
+        Arg::with_name("check")
+            .long("check")
+            .conflicts_with_all(&["OUTPUT", "dry-run", "patch"])
+            .help("List the functions to rename and fail if any, without applying changes"),
+        Arg::with_name("report")
+            .long("report")
+            .value_name("FILE")
+            .help("Write a JSON report of the renamed and skipped functions to this file"),
+        Arg::with_name("stats")
+            .long("stats")
+            .help("Print a summary of the renamed and skipped functions at the end of the run"),
+        Arg::with_name("interactive")
+            .short("i")
+            .long("interactive")
+            .conflicts_with("check")
+            .help("Prompt before renaming each function"),
+        Arg::with_name("decisions")
+            .long("decisions")
+            .value_name("FILE")
+            .help(
+                "Apply the renaming decisions from this file and, with --interactive, \
+                 record new decisions there",
+            ),
+        Arg::with_name("backup")
+            .long("backup")
+            .value_name("SUFFIX")
+            .min_values(0)
+            .require_equals(true)
+            .conflicts_with_all(&["dry-run", "patch", "check"])
+            .help("Keep a copy of the modified files with this suffix (default: .orig)"),
+        Arg::with_name("journal")
+            .long("journal")
+            .value_name("FILE")
+            .conflicts_with_all(&["OUTPUT", "dry-run", "patch", "check"])
+            .help("Record the applied changes to this file so that they can be undone"),
+        Arg::with_name("keep-going")
+            .long("keep-going")
+            .help("Keep going after an error and print a summary of the errors at the end"),
+        Arg::with_name("quiet")
+            .short("q")
+            .long("quiet")
+            .help("Run silently"),
+        Arg::with_name("verbose")
+            .short("v")
+            .long("verbose")
+            .help("Show detailed logs"),
+    ]
+}
+
+/// Returns the arguments selecting the files to process.
+pub fn path_args() -> Vec<Arg<'static, 'static>> {
+    vec![
+        Arg::with_name("include")
+            .long("include")
+            .value_name("GLOB")
+            .multiple(true)
+            .number_of_values(1)
+            .help("Only process the files matching this pattern (can be repeated)"),
+        Arg::with_name("exclude")
+            .long("exclude")
+            .value_name("GLOB")
+            .multiple(true)
+            .number_of_values(1)
+            .help("Skip the directories and files matching this pattern (can be repeated)"),
+        Arg::with_name("markdown")
+            .long("markdown")
+            .value_name("GLOB")
+            .multiple(true)
+            .number_of_values(1)
+            .help("Also process the Rust code blocks of the Markdown files matching this pattern (can be repeated)"),
+        Arg::with_name("follow-modules")
+            .short("m")
+            .long("follow-modules")
+            .help(
+                "Process the files reachable from the crate roots instead of walking directories",
+            ),
+        Arg::with_name("PATH").help(
+            "Crate or workspace root path, Rust file or `-` for stdin (default: current directory)",
+        ),
+        Arg::with_name("OUTPUT").help("Output to a different root path, file or `-` for stdout"),
+    ]
+}
+
+/// Initializes the logger according to the verbosity arguments.
+pub fn init_logger(m: &ArgMatches) {
+    let (verbosity, max_level) = if m.is_present("verbose") {
+        (5, LevelFilter::Trace)
+    } else if m.is_present("quiet") {
+        (1, LevelFilter::Warn)
+    } else {
+        (2, LevelFilter::Info)
+    };
+    let mut stderr_log = stderrlog::new();
+    stderr_log
+        .verbosity(verbosity)
+        .color(if io::stderr().is_terminal() {
+            ColorChoice::Auto
+        } else {
+            ColorChoice::Never
+        });
+    Logger::init(Box::new(stderr_log), max_level).unwrap();
+}
+
+/// Reads the `fix-getters` config from the `Cargo.toml` file at the root `path`, if any.
+fn read_config(path: &Path) -> Result<Config, String> {
+    let manifest_path = path.join("Cargo.toml");
+    if !path.is_dir() || !manifest_path.is_file() {
+        return Ok(Config::default());
+    }
+
+    Manifest::read(&manifest_path)
+        .map(|manifest| manifest.config)
+        .map_err(|error| error.to_string())
+}
+
+/// Options common to the subcommands.
+pub struct Options {
+    pub path: PathBuf,
+    pub identification_mode: IdentificationMode,
+    output_path: Option<PathBuf>,
+    use_stdin: bool,
+    use_stdout: bool,
+    filter: Box<dyn DirEntryFilter>,
+    markdown: Vec<Glob>,
+    mode: TraversalMode,
+    jobs: usize,
+    interactive: bool,
+    patch: Option<Patch>,
+    patch_path: Option<String>,
+    check: Option<Check>,
+    errors: Option<Errors>,
+    backup_suffix: Option<String>,
+    journal: Option<Journal>,
+    journal_path: Option<PathBuf>,
+    pub report: Option<Arc<Report>>,
+    report_path: Option<String>,
+    must_print_stats: bool,
+    decisions_path: Option<PathBuf>,
+    are_decisions_by_name: bool,
+    is_verbose: bool,
+}
+
+impl Options {
+    /// Builds the [`Options`] from the command line arguments.
+    ///
+    /// Exits the process if the arguments are inconsistent.
+    pub fn from_matches(m: &ArgMatches) -> Self {
+        match Options::try_from_matches(m) {
+            Ok(options) => options,
+            Err(msg) => {
+                error!("{}", msg);
+                process::exit(1);
+            }
+        }
+    }
+
+    /// Builds the [`Options`] from the command line arguments.
+    ///
+    /// Returns a message explaining the error if the arguments are inconsistent.
+    pub fn try_from_matches(m: &ArgMatches) -> Result<Self, String> {
+        let jobs = match m.value_of("jobs") {
+            Some(jobs) => match jobs.parse::<usize>() {
+                Ok(jobs) if jobs > 0 => jobs,
+                _ => return Err(format!("invalid number of jobs {}", jobs)),
+            },
+            None => thread::available_parallelism().map_or(1, |jobs| jobs.get()),
+        };
+
+        let path: PathBuf = match m.value_of("PATH") {
+            Some(path) => path.into(),
+            None => PathBuf::from("."),
+        };
+
+        let use_stdin = path == Path::new("-");
+        if !use_stdin && !path.exists() {
+            return Err(format!(
+                "path not found {}",
+                path.to_str().expect("was a &str initially")
+            ));
+        }
+
+        let dry_run = m.is_present("dry-run") || m.is_present("patch");
+        let check_mode = m.is_present("check");
+        let interactive = m.is_present("interactive");
+        if (dry_run || check_mode || interactive) && use_stdin {
+            return Err("dry run, check & interactive modes can't be used with stdin".to_string());
+        }
+
+        let output = m.value_of("OUTPUT");
+        let use_stdout = output == Some("-") || (use_stdin && output.is_none());
+        if use_stdout && path.is_dir() {
+            return Err("output to stdout requires a single Rust file".to_string());
+        }
+
+        let output_path: Option<PathBuf> = if let Some(output) = output.filter(|_| !use_stdout) {
+            let output_path: PathBuf = output.to_string().into();
+            // A single Rust file can be written to a new file
+            if path.is_dir() && !output_path.exists() {
+                return Err(format!(
+                    "output path not found {}",
+                    output_path.to_str().expect("was a &str initially")
+                ));
+            }
+            Some(output_path)
+        } else {
+            None
+        };
+
+        let config = read_config(&path)?;
+        let filter: Box<dyn DirEntryFilter> =
+            if m.is_present("include") || m.is_present("exclude") || !config.is_empty() {
+                let mut filter = GlobDirEntryFilter::new(&path);
+                let includes = config.include.iter().map(String::as_str);
+                for pattern in includes.chain(m.values_of("include").into_iter().flatten()) {
+                    filter.include(pattern);
+                }
+                let excludes = config.exclude.iter().map(String::as_str);
+                for pattern in excludes.chain(m.values_of("exclude").into_iter().flatten()) {
+                    filter.exclude(pattern);
+                }
+                Box::new(filter)
+            } else {
+                Box::new(DefaultDirEntryFilter)
+            };
+
+        Ok(Options {
+            patch: dry_run.then(|| Patch::new(&path)),
+            patch_path: m.value_of("patch").map(String::from),
+            check: check_mode.then(Check::default),
+            errors: m.is_present("keep-going").then(Errors::default),
+            journal: m.is_present("journal").then(|| Journal::new(&path)),
+            journal_path: m.value_of("journal").map(PathBuf::from),
+            backup_suffix: m.is_present("backup").then(|| {
+                m.value_of("backup")
+                    .unwrap_or(DEFAULT_BACKUP_SUFFIX)
+                    .to_string()
+            }),
+            report: (m.is_present("report") || m.is_present("stats"))
+                .then(|| Arc::new(Report::default())),
+            report_path: m.value_of("report").map(String::from),
+            must_print_stats: m.is_present("stats"),
+            decisions_path: m.value_of("decisions").map(PathBuf::from),
+            are_decisions_by_name: false,
+            path,
+            identification_mode: if m.is_present("conservative") {
+                IdentificationMode::Conservative
+            } else {
+                IdentificationMode::AllGetFunctions
+            },
+            output_path,
+            use_stdin,
+            use_stdout,
+            filter,
+            markdown: m
+                .values_of("markdown")
+                .into_iter()
+                .flatten()
+                .map(Glob::new)
+                .collect(),
+            mode: if m.is_present("follow-modules") {
+                TraversalMode::ModuleGraph
+            } else {
+                TraversalMode::Directories
+            },
+            jobs,
+            interactive,
+            is_verbose: m.is_present("verbose"),
+        })
+    }
+
+    pub fn use_stdio(&self) -> bool {
+        self.use_stdin || self.use_stdout
+    }
+
+    /// Uses `root` as the root path for the traversal & the patch.
+    pub fn with_root(mut self, root: PathBuf) -> Self {
+        self.patch = self.patch.map(|_| Patch::new(&root));
+        self.journal = self.journal.map(|_| Journal::new(&root));
+        self.path = root;
+        self
+    }
+
+    /// Applies each review decision to all the functions with the same name.
+    ///
+    /// See [`Review::by_name`].
+    pub fn with_decisions_by_name(mut self) -> Self {
+        self.are_decisions_by_name = true;
+        self
+    }
+
+    pub fn is_check(&self) -> bool {
+        self.check.is_some()
+    }
+
+    pub fn is_dry_run(&self) -> bool {
+        self.patch.is_some()
+    }
+
+    pub fn settings(&self) -> TraversalSettings<'_> {
+        self.settings_with(self.filter.as_ref())
+    }
+
+    /// Returns the [`TraversalSettings`] using the given `filter`.
+    pub fn settings_with<'a>(&'a self, filter: &'a dyn DirEntryFilter) -> TraversalSettings<'a> {
+        let action = match (self.patch.as_ref(), self.check.as_ref()) {
+            (Some(patch), _) => Action::Diff(patch),
+            (None, Some(check)) => Action::Check(check),
+            (None, None) => Action::Apply,
+        };
+
+        TraversalSettings {
+            filter,
+            mode: self.mode,
+            // Prompt in traversal order
+            jobs: if self.interactive { 1 } else { self.jobs },
+            action,
+            errors: self.errors.as_ref(),
+            backup_suffix: self.backup_suffix.as_deref(),
+            journal: self.journal.as_ref(),
+            markdown: &self.markdown,
+        }
+    }
+
+    /// Runs the `fixer` according to the options.
+    ///
+    /// Exits the process in case of an error or if the check mode finds
+    /// functions to rename.
+    pub fn run(&self, fixer: impl CrateTraverser) {
+        let fixer = self.review(fixer);
+
+        if self.use_stdio() {
+            if let Err(error) = fix_stdio(&fixer, &self.path, &self.output_path) {
+                self.log_error(&error);
+                process::exit(1);
+            }
+            self.write_report();
+            return;
+        }
+
+        info!("Processing {:?}", self.path);
+        if let Err(error) = fixer.traverse(&self.path, &self.settings(), &self.output_path) {
+            self.log_error(&error);
+            self.save_journal();
+            process::exit(1);
+        }
+        self.finish(&fixer);
+        info!("Done {:?}", self.path);
+    }
+
+    /// Wraps the `fixer` in a [`Review`] applying the decisions from the options.
+    ///
+    /// Exits the process if the decisions can't be loaded.
+    pub fn review<T: CrateTraverser>(&self, fixer: T) -> Review<T> {
+        let decisions = match self.decisions_path.as_ref() {
+            Some(decisions_path) => match Decisions::load(decisions_path) {
+                Ok(decisions) => decisions,
+                Err(error) => {
+                    error!("{}", error);
+                    process::exit(1);
+                }
+            },
+            None => Decisions::default(),
+        };
+
+        let review = if self.interactive {
+            Review::interactive(
+                fixer,
+                decisions,
+                io::BufReader::new(io::stdin()),
+                io::stderr(),
+            )
+        } else {
+            Review::new(fixer, decisions)
+        };
+        let review = if self.are_decisions_by_name {
+            review.by_name()
+        } else {
+            review
+        };
+
+        match self.report.as_ref() {
+            Some(report) => review.with_report(Arc::clone(report)),
+            None => review,
+        }
+    }
+
+    /// Writes the patch, the report & the decisions after the traversal.
+    ///
+    /// Exits the process in case of an error, if errors were recorded in
+    /// keep-going mode or if the check mode finds functions to rename.
+    pub fn finish<T: CrateTraverser>(&self, fixer: &Review<T>) {
+        if let Some(patch) = self.patch.as_ref() {
+            if let Err(error) = write_patch(patch, self.patch_path.as_deref()) {
+                error!("{}", error);
+                process::exit(1);
+            }
+        }
+        self.write_report();
+        self.save_journal();
+
+        if let Some(decisions_path) = self.decisions_path.as_ref().filter(|_| self.interactive) {
+            if let Err(error) = fixer.decisions().save(decisions_path) {
+                error!("{}", error);
+                process::exit(1);
+            }
+        }
+
+        let has_failed = self.write_errors();
+
+        if let Some(check) = self.check.as_ref() {
+            if let Err(error) = check.write_to(&mut io::stdout().lock()) {
+                error!("{}", error);
+                process::exit(1);
+            }
+            if !check.is_empty() {
+                info!("{} function(s) to rename", check.len());
+                process::exit(1);
+            }
+        }
+
+        if has_failed {
+            process::exit(1);
+        }
+    }
+
+    /// Prints the summary of the errors recorded in keep-going mode.
+    ///
+    /// Returns `true` if any error was recorded.
+    fn write_errors(&self) -> bool {
+        match self.errors.as_ref().filter(|errors| !errors.is_empty()) {
+            Some(errors) => {
+                if let Err(err) = errors.write_to(&mut io::stderr().lock(), self.is_verbose) {
+                    error!("{}", Error::WriteFile("<stderr>".into(), err));
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Saves the journal of the changes applied so far, if requested.
+    pub fn save_journal(&self) {
+        if let Some((journal, journal_path)) = self.journal.as_ref().zip(self.journal_path.as_ref())
+        {
+            if let Err(error) = journal.save(journal_path) {
+                error!("{}", error);
+                process::exit(1);
+            }
+        }
+    }
+
+    /// Logs the `error`, including the whole source code of the file in verbose mode.
+    pub fn log_error(&self, error: &Error) {
+        if self.is_verbose {
+            error!("{:#}", error);
+        } else {
+            error!("{}", error);
+        }
+    }
+
+    /// Writes the report & prints the statistics if requested.
+    fn write_report(&self) {
+        if let Some(report) = self.report.as_ref() {
+            if let Err(error) =
+                write_report(report, self.report_path.as_deref(), self.must_print_stats)
+            {
+                error!("{}", error);
+                process::exit(1);
+            }
+        }
+    }
+}
+
+/// Fixes a single Rust source using stdin and / or stdout.
+///
+/// The source is read from stdin if `path` is `-`. The result is written
+/// to `output_path` if specified, otherwise to stdout.
+fn fix_stdio(
+    fixer: &impl CrateTraverser,
+    path: &Path,
+    output_path: &Option<PathBuf>,
+) -> Result<(), Error> {
+    let (path, mut input): (&Path, Box<dyn Read>) = if path == Path::new("-") {
+        (Path::new("<stdin>"), Box::new(io::stdin().lock()))
+    } else {
+        let file = File::open(path).map_err(|err| Error::ReadFile(path.to_owned(), err))?;
+        (path, Box::new(file))
+    };
+
+    match output_path {
+        Some(output_path) => {
+            let mut output = Vec::new();
+            fixer.fix_stream(path, &mut input, &mut output)?;
+            atomic_write::write(output_path, &output, None)
+        }
+        None => fixer.fix_stream(path, &mut input, &mut io::stdout().lock()),
+    }
+}
+
+/// Writes the `patch` to the file at `patch_path` if specified, otherwise to stdout.
+fn write_patch(patch: &Patch, patch_path: Option<&str>) -> Result<(), Error> {
+    match patch_path {
+        Some(patch_path) => {
+            let mut file =
+                File::create(patch_path).map_err(|err| Error::WriteFile(patch_path.into(), err))?;
+            patch
+                .write_to(&mut file)
+                .map_err(|err| Error::WriteFile(patch_path.into(), err))
+        }
+        None => patch
+            .write_to(&mut io::stdout().lock())
+            .map_err(|err| Error::WriteFile("<stdout>".into(), err)),
+    }
+}
+
+/// Writes the `report` to the file at `report_path` if specified.
+///
+/// Prints the statistics summary to stderr if `must_print_stats` is set.
+fn write_report(
+    report: &Report,
+    report_path: Option<&str>,
+    must_print_stats: bool,
+) -> Result<(), Error> {
+    if let Some(report_path) = report_path {
+        let mut file =
+            File::create(report_path).map_err(|err| Error::WriteFile(report_path.into(), err))?;
+        report
+            .write_to(&mut file)
+            .map_err(|err| Error::WriteFile(report_path.into(), err))?;
+    }
+
+    if must_print_stats {
+        report
+            .statistics()
+            .write_to(&mut io::stderr().lock())
+            .map_err(|err| Error::WriteFile("<stderr>".into(), err))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::temp_dir;
+    use crate::Edit;
+    use clap::App;
+    use std::fs;
+
+    fn matches(args: &[&str]) -> ArgMatches<'static> {
+        App::new("fix-getters")
+            .args(&common_args())
+            .args(&path_args())
+            .get_matches_from(std::iter::once("fix-getters").chain(args.iter().copied()))
+    }
+
+    #[test]
+    fn config_filter() {
+        let root = temp_dir(env!("CARGO_PKG_NAME"), "config_filter");
+        fs::write(
+            root.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"crates/*\"]\n\n\
+             [workspace.metadata.fix-getters]\n\
+             include = [\"crates/gui/**\"]\n\
+             exclude = [\"**/generated/**\"]\n",
+        )
+        .unwrap();
+        let root_str = root.to_str().unwrap();
+
+        let options = Options::from_matches(&matches(&[root_str]));
+        assert!(options
+            .filter
+            .accepts_file(&root.join("crates/gui/src/lib.rs")));
+        assert!(!options
+            .filter
+            .accepts_file(&root.join("crates/net/src/lib.rs")));
+        assert!(!options
+            .filter
+            .accepts_file(&root.join("crates/gui/generated/widget.rs")));
+
+        // The command line patterns are combined with the config
+        let options = Options::from_matches(&matches(&[
+            "--include",
+            "crates/net/**",
+            "--exclude",
+            "legacy",
+            root_str,
+        ]));
+        assert!(options
+            .filter
+            .accepts_file(&root.join("crates/net/src/lib.rs")));
+        assert!(!options
+            .filter
+            .accepts_file(&root.join("crates/net/legacy/lib.rs")));
+        assert!(!options
+            .filter
+            .accepts_file(&root.join("crates/gui/generated/widget.rs")));
+
+        // No config: all the files are accepted
+        fs::remove_file(root.join("Cargo.toml")).unwrap();
+        let options = Options::from_matches(&matches(&[root_str]));
+        assert!(options
+            .filter
+            .accepts_file(&root.join("crates/net/legacy/lib.rs")));
+    }
+
+    /// Renames `get_a` to `a`.
+    struct Renamer;
+
+    impl CrateTraverser for Renamer {
+        fn collect_edits(&self, _path: &Path, source_code: &str) -> Result<Vec<Edit>, Error> {
+            Ok(source_code
+                .match_indices("get_a")
+                .map(|(idx, name)| Edit::new(idx..idx + name.len(), "a"))
+                .collect())
+        }
+    }
+
+    #[test]
+    fn stdio() {
+        let root = temp_dir(env!("CARGO_PKG_NAME"), "cli_stdio");
+        let root_str = root.to_str().unwrap();
+        let lib_path = root.join("lib.rs");
+        fs::write(&lib_path, "fn get_a() {}\n").unwrap();
+        let lib_str = lib_path.to_str().unwrap();
+        let output_str = root.join("output.rs");
+        let output_str = output_str.to_str().unwrap();
+
+        let options = Options::try_from_matches(&matches(&["-"])).unwrap();
+        assert!(options.use_stdin && options.use_stdout);
+        assert_eq!(options.output_path, None);
+
+        let options = Options::try_from_matches(&matches(&["-", output_str])).unwrap();
+        assert!(options.use_stdin && !options.use_stdout);
+        assert_eq!(options.output_path.as_deref(), Some(Path::new(output_str)));
+
+        let options = Options::try_from_matches(&matches(&[lib_str, "-"])).unwrap();
+        assert!(!options.use_stdin && options.use_stdout);
+        assert_eq!(options.output_path, None);
+
+        for args in &[
+            &["--dry-run", "-"][..],
+            &["--patch", "fix.patch", "-"][..],
+            &["--check", "-"][..],
+            &["--interactive", "-"][..],
+        ] {
+            assert_eq!(
+                Options::try_from_matches(&matches(args)).err().as_deref(),
+                Some("dry run, check & interactive modes can't be used with stdin"),
+            );
+        }
+
+        assert_eq!(
+            Options::try_from_matches(&matches(&[root_str, "-"]))
+                .err()
+                .as_deref(),
+            Some("output to stdout requires a single Rust file"),
+        );
+        assert!(Options::try_from_matches(&matches(&[root_str, output_str])).is_err());
+    }
+
+    #[test]
+    fn single_file() {
+        let root = temp_dir(env!("CARGO_PKG_NAME"), "cli_single_file");
+        fs::create_dir(root.join("output")).unwrap();
+        let lib_path = root.join("lib.rs");
+        fs::write(&lib_path, "fn get_a() {}\n").unwrap();
+        let lib_str = lib_path.to_str().unwrap();
+
+        // A single Rust file can be written to a new file
+        let new_path = root.join("new.rs");
+        let options =
+            Options::try_from_matches(&matches(&[lib_str, new_path.to_str().unwrap()])).unwrap();
+        assert!(!options.use_stdio());
+        assert_eq!(options.output_path.as_deref(), Some(new_path.as_path()));
+        options.run(Renamer);
+        assert_eq!(fs::read_to_string(&new_path).unwrap(), "fn a() {}\n");
+        assert_eq!(fs::read_to_string(&lib_path).unwrap(), "fn get_a() {}\n");
+
+        // ... or to an existing directory
+        let output_dir = root.join("output");
+        Options::try_from_matches(&matches(&[lib_str, output_dir.to_str().unwrap()]))
+            .unwrap()
+            .run(Renamer);
+        assert_eq!(
+            fs::read_to_string(output_dir.join("lib.rs")).unwrap(),
+            "fn a() {}\n",
+        );
+        assert_eq!(fs::read_to_string(&lib_path).unwrap(), "fn get_a() {}\n");
+
+        // Fixed in place without an output path
+        let options = Options::try_from_matches(&matches(&[lib_str])).unwrap();
+        assert!(!options.use_stdio());
+        assert_eq!(options.output_path, None);
+        options.run(Renamer);
+        assert_eq!(fs::read_to_string(&lib_path).unwrap(), "fn a() {}\n");
+    }
+}
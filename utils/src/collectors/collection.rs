@@ -12,10 +12,12 @@
 /// `macro`s. However, the code appears at a particular `offset` in the
 /// actual Rust file.
 ///
+/// Collections must be [`Send`] so that files can be processed concurrently.
+///
 /// See also [`TokenStreamGetterCollector`](crate::TokenStreamGetterCollector),
 /// [`SyntaxTreeGetterCollector`](crate::SyntaxTreeGetterCollector) and
 /// [`DocCodeGetterCollector`](crate::DocCodeGetterCollector).
-pub trait GetterCollection {
+pub trait GetterCollection: Send {
     /// Clones a view on the provided shared collection.
     ///
     /// Any [`Getter`](crate::Getter) added to the resulting collection
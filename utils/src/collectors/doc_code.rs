@@ -2,7 +2,9 @@
 
 use std::path::{Path, PathBuf};
 
-use crate::{GetterCollection, IdentificationMode, Scope, TokenStreamGetterCollector};
+#[cfg(feature = "log")]
+use crate::Diagnostic;
+use crate::{GetterCollection, IdentificationMode, Scope, ScopeItem, TokenStreamGetterCollector};
 
 /// A generic [`Getter`](crate::Getter)s collector visting documentation.
 ///
@@ -15,10 +17,13 @@ use crate::{GetterCollection, IdentificationMode, Scope, TokenStreamGetterCollec
 #[derive(Debug)]
 pub struct DocCodeGetterCollector<P: TokenStreamGetterCollector> {
     code: String,
+    /// Line & column offset in the Rust file for each line of `code`.
+    code_lines: Vec<(usize, usize)>,
     state: State,
     identification_mode: IdentificationMode,
     getter_collection: P::GetterCollection,
     path: PathBuf,
+    scope: Scope,
 }
 
 impl<P: TokenStreamGetterCollector> DocCodeGetterCollector<P> {
@@ -36,92 +41,340 @@ impl<P: TokenStreamGetterCollector> DocCodeGetterCollector<P> {
 
         DocCodeGetterCollector {
             code: String::with_capacity(512),
+            code_lines: Vec::new(),
             state: State::None,
             identification_mode,
             getter_collection,
             path: path.to_owned(),
+            scope: Scope::from_file(path).join(ScopeItem::Documentation),
         }
     }
 
     /// Analyses the documentation in the provided [`Attribute`](syn::Attribute).
     ///
-    /// Note that documentation code is parsed by [`syn`] one line at a time,
+    /// Note that documentation code is parsed by [`syn`] one attribute at a time,
     /// this method will take care of parsing any code found in the provided
     /// [`Attribute`](syn::Attribute)s and feeding the [`GetterCollection`].
+    ///
+    /// An attribute can hold multiple lines, e.g. a `/** ... */` block or a
+    /// `#[doc = "..."]` literal spanning multiple lines.
     pub fn have_attribute(&mut self, node: &syn::Attribute) {
-        if let Some((_, cursor)) = syn::buffer::TokenBuffer::new2(node.tokens.clone())
+        if let Some((punct, cursor)) = syn::buffer::TokenBuffer::new2(node.tokens.clone())
             .begin()
             .punct()
         {
             if let Some((literal, _)) = cursor.literal() {
-                self.process(
-                    &literal.to_string().trim_matches('"').trim(),
-                    literal.span().start().line,
-                );
+                let start = literal.span().start();
+                let end = literal.span().end();
+                // Doc comments tokens all share the span of the comment
+                let is_sugared = punct.span().start() == start;
+                let prefix_len = if is_sugared { "///".len() } else { "\"".len() };
+
+                let value = match syn::Lit::new(literal) {
+                    syn::Lit::Str(lit) => lit.value(),
+                    _ => return,
+                };
+
+                // Each line of the value is located on its own line in the
+                // Rust file, unless the newlines are escaped in a literal.
+                let is_multiline = end.line - start.line == value.matches('\n').count();
+                // Like rustdoc, strip the leading `*` of the lines in a block
+                // if all the lines start with one.
+                let must_strip_star = is_sugared
+                    && is_multiline
+                    && value.lines().skip(1).all(|line| {
+                        let line = line.trim_start();
+                        line.is_empty() || line.starts_with('*')
+                    });
+
+                for (idx, content) in value.split('\n').enumerate() {
+                    let (line, mut column) = if idx == 0 {
+                        (start.line, start.column + prefix_len)
+                    } else if is_multiline {
+                        (start.line + idx, 0)
+                    } else {
+                        (start.line, start.column + prefix_len)
+                    };
+
+                    let mut doc_line = content.trim_end();
+                    if must_strip_star && idx > 0 {
+                        let trimmed = doc_line.trim_start();
+                        if let Some(stripped) = trimmed.strip_prefix('*') {
+                            column += doc_line.len() - stripped.len();
+                            doc_line = stripped;
+                        }
+                    }
+                    let trimmed = doc_line.trim_start();
+                    column += doc_line.len() - trimmed.len();
+
+                    self.process(trimmed, line, column);
+                }
             }
         }
     }
 
-    fn process(&mut self, doc_line: &str, offset: usize) {
-        if doc_line.starts_with("```") {
-            if !self.state.is_code_block() {
-                // starting a doc code block
-                self.getter_collection.set_offset(offset);
-                if doc_line.len() == 3 || doc_line.ends_with("rust") {
-                    self.state = State::RustCodeBlock;
-                } else {
-                    self.state = State::CodeBlock;
-                };
-            } else {
-                // terminating a doc code block
-                if self.state.is_rust() {
-                    self.collect();
+    /// Analyses the code blocks in the Markdown `text`, e.g. a `README.md` file.
+    ///
+    /// The [`Getter`](crate::Getter)s are located in the Markdown file.
+    pub fn have_markdown(&mut self, text: &str) {
+        for (idx, md_line) in text.lines().enumerate() {
+            let md_line = md_line.trim_end();
+            let trimmed = md_line.trim_start();
+            self.process(trimmed, idx + 1, md_line.len() - trimmed.len());
+        }
+    }
+
+    /// Processes the `doc_line` located at `line` & `column` in the Rust file.
+    fn process(&mut self, doc_line: &str, line: usize, column: usize) {
+        match &self.state {
+            State::None => {
+                if let Some(fence) = Fence::parse(doc_line) {
+                    // starting a doc code block
+                    self.getter_collection.set_offset(line);
+                    self.state = State::CodeBlock(fence);
+                }
+            }
+            State::CodeBlock(fence) => {
+                if fence.is_closed_by(doc_line) {
+                    // terminating a doc code block
+                    if fence.is_rust {
+                        self.collect();
+                    }
+                    self.state = State::None;
+                } else if fence.is_rust {
+                    let (code_line, marker_len) = strip_hidden_marker(doc_line);
+                    self.push_code_line(code_line, line, column + marker_len);
                 }
-                self.state = State::None;
             }
-        } else if self.state.is_rust() && !doc_line.starts_with('#') {
-            self.code.push_str(&doc_line.replace('\\', &""));
+        }
+    }
+
+    /// Pushes the `code_line` located at `line` & `column` in the Rust file.
+    ///
+    /// The [`GetterCollection`] locates the getters using the offset of the
+    /// code block, so empty lines are inserted for the lines which are not
+    /// part of the code, e.g. other attributes between the doc comments.
+    fn push_code_line(&mut self, code_line: &str, line: usize, column: usize) {
+        let offset = self.getter_collection.offset();
+        while offset + self.code_lines.len() + 1 < line {
             self.code.push('\n');
+            self.code_lines
+                .push((offset + self.code_lines.len() + 1, 0));
         }
+
+        self.code.push_str(code_line);
+        self.code.push('\n');
+        self.code_lines.push((line, column));
     }
 
     fn collect(&mut self) {
         match syn::parse_str::<proc_macro2::TokenStream>(&self.code) {
             Ok(syntax_tree) => P::collect(
                 &self.path,
-                &Scope::Documentation,
+                &self.scope,
                 &syntax_tree,
                 self.identification_mode,
                 &self.getter_collection,
             ),
             Err(_err) => {
                 #[cfg(feature = "log")]
-                log::warn!(
-                    "{:?} doc @ {}: {:?}",
-                    self.path,
-                    self.getter_collection.offset(),
-                    _err
-                );
+                log::warn!("{}", self.diagnostic(&_err));
             }
         }
 
         self.code.clear();
+        self.code_lines.clear();
+    }
+}
+
+#[cfg(feature = "log")]
+impl<P: TokenStreamGetterCollector> DocCodeGetterCollector<P> {
+    /// Returns the diagnostic for the doc code parse `err`, located in the Rust file.
+    fn diagnostic(&self, err: &syn::Error) -> String {
+        let message = format!("doc code: {}", err);
+        let start = err.span().start();
+
+        let mut diagnostic =
+            Diagnostic::new(&self.path, &self.code, start, err.span().end(), &message);
+        match start
+            .line
+            .checked_sub(1)
+            .and_then(|line_idx| self.code_lines.get(line_idx))
+        {
+            Some((line, column)) => {
+                diagnostic.line = *line;
+                diagnostic.column += column;
+            }
+            None => {
+                // Unknown location, e.g. unexpected end of code
+                diagnostic.line = self.getter_collection.offset();
+                diagnostic.column = 1;
+                diagnostic.snippet = None;
+            }
+        }
+
+        diagnostic.to_string()
     }
 }
 
 #[derive(Debug)]
 enum State {
     None,
-    CodeBlock,
-    RustCodeBlock,
+    CodeBlock(Fence),
 }
 
-impl State {
-    fn is_code_block(&self) -> bool {
-        matches!(self, State::RustCodeBlock | State::CodeBlock)
+/// The opening fence of a code block.
+#[derive(Debug)]
+pub(crate) struct Fence {
+    delimiter: char,
+    len: usize,
+    is_rust: bool,
+}
+
+impl Fence {
+    /// Parses the opening fence of a code block from the `doc_line`, if any.
+    ///
+    /// Fences consist of at least 3 backticks or tildes, followed by the info string.
+    pub(crate) fn parse(doc_line: &str) -> Option<Self> {
+        let delimiter = doc_line.chars().next().filter(|c| *c == '`' || *c == '~')?;
+        let len = doc_line.chars().take_while(|c| *c == delimiter).count();
+        if len < 3 {
+            return None;
+        }
+
+        let info = &doc_line[len..];
+        if delimiter == '`' && info.contains('`') {
+            // Inline code
+            return None;
+        }
+
+        Some(Fence {
+            delimiter,
+            len,
+            is_rust: is_rust_info(info),
+        })
+    }
+
+    /// Checks whether the `doc_line` is a closing fence for this code block.
+    pub(crate) fn is_closed_by(&self, doc_line: &str) -> bool {
+        let len = doc_line
+            .chars()
+            .take_while(|c| *c == self.delimiter)
+            .count();
+
+        len >= self.len && doc_line[len..].trim().is_empty()
+    }
+}
+
+/// Returns the code in the `doc_line` & the length of its doctest marker.
+///
+/// Follows the rules of rustdoc: `# ` hides the rest of the line from the
+/// documentation while keeping it in the doctest & `##` escapes a leading `#`.
+/// Other lines starting with `#`, such as `#[derive(Debug)]`, are code.
+///
+/// Only the code is renamed, so the marker is kept in the file.
+fn strip_hidden_marker(doc_line: &str) -> (&str, usize) {
+    if doc_line.starts_with("##") {
+        (&doc_line[1..], 1)
+    } else if let Some(code_line) = doc_line.strip_prefix("# ") {
+        (code_line, 2)
+    } else if doc_line == "#" {
+        ("", 1)
+    } else {
+        (doc_line, 0)
+    }
+}
+
+/// Checks whether the `info` string of a code block denotes Rust code.
+///
+/// Follows the rules of rustdoc: a code block is a doctest unless its info
+/// string only contains tags which are not known to rustdoc, such as `text`.
+fn is_rust_info(info: &str) -> bool {
+    let mut has_rust_tags = false;
+    let mut has_other_tags = false;
+
+    for tag in info
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .map(|tag| tag.trim_matches(|c| c == '{' || c == '}' || c == '.'))
+        .filter(|tag| !tag.is_empty())
+    {
+        match tag {
+            "rust" | "should_panic" | "no_run" | "ignore" | "test_harness" | "compile_fail"
+            | "standalone_crate" => has_rust_tags = true,
+            _ if tag.starts_with("ignore-") || tag.starts_with("edition") => has_rust_tags = true,
+            // Error codes, e.g. `compile_fail,E0277`
+            _ if tag.len() == 5
+                && tag.starts_with('E')
+                && tag[1..].chars().all(|c| c.is_ascii_digit()) =>
+            {
+                has_rust_tags = true
+            }
+            _ => has_other_tags = true,
+        }
+    }
+
+    has_rust_tags || !has_other_tags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fences() {
+        for rust_fence in &[
+            "```",
+            "```rust",
+            "```no_run",
+            "```ignore",
+            "```should_panic",
+            "```compile_fail,E0277",
+            "```edition2021",
+            "```rust,no_run",
+            "``` rust ignore",
+            "```{.rust}",
+            "~~~",
+            "````rust",
+        ] {
+            let fence = Fence::parse(rust_fence).unwrap();
+            assert!(fence.is_rust, "{}", rust_fence);
+        }
+
+        for other_fence in &["```text", "```sh", "~~~toml"] {
+            let fence = Fence::parse(other_fence).unwrap();
+            assert!(!fence.is_rust, "{}", other_fence);
+        }
+
+        assert!(Fence::parse("``").is_none());
+        assert!(Fence::parse("```a` b```").is_none());
+        assert!(Fence::parse("let a = 1;").is_none());
+
+        let fence = Fence::parse("````").unwrap();
+        assert!(!fence.is_closed_by("```"));
+        assert!(fence.is_closed_by("`````"));
+        assert!(!fence.is_closed_by("~~~~"));
+        assert!(!fence.is_closed_by("````rust"));
     }
 
-    fn is_rust(&self) -> bool {
-        matches!(self, State::RustCodeBlock)
+    #[test]
+    fn hidden_lines() {
+        assert_eq!(
+            strip_hidden_marker("# let a = b.get_a();"),
+            ("let a = b.get_a();", 2),
+        );
+        assert_eq!(strip_hidden_marker("#"), ("", 1));
+        assert_eq!(
+            strip_hidden_marker("##[derive(Debug)]"),
+            ("#[derive(Debug)]", 1)
+        );
+        assert_eq!(
+            strip_hidden_marker("#[derive(Debug)]"),
+            ("#[derive(Debug)]", 0)
+        );
+        assert_eq!(
+            strip_hidden_marker("#![allow(unused)]"),
+            ("#![allow(unused)]", 0)
+        );
+        assert_eq!(strip_hidden_marker("let a = 1; # 2"), ("let a = 1; # 2", 0));
     }
 }
@@ -1,28 +1,236 @@
 //! Crate traversal mechanism.
 
 #[cfg(feature = "log")]
-use log::debug;
-use rules::dir_entry;
+use log::{debug, warn};
+use rules::dir_entry::{self, DirEntryFilter, Glob, NOT_MIRRORED};
 use std::{
-    fs::DirEntry,
+    collections::{BTreeMap, BTreeSet, HashSet},
+    fs::{self, DirEntry},
+    io::{Read, Write},
     path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread,
 };
 
-use crate::Error;
+use crate::{
+    atomic_write, edit, markdown, module_graph, Check, Edit, Error, Errors, Journal, Patch,
+};
+
+/// Crate traversal mode.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum TraversalMode {
+    /// Walk the directories, processing the Rust files accepted by the filter.
+    Directories,
+    /// Follow the module graph from the crate roots.
+    ///
+    /// The filter is used to find the packages and to select the files
+    /// reached through the module graph.
+    /// See [`module_graph`](crate::module_graph).
+    ModuleGraph,
+}
+
+impl TraversalMode {
+    pub fn is_module_graph(self) -> bool {
+        matches!(self, TraversalMode::ModuleGraph)
+    }
+}
+
+/// What to do with the changes to the Rust files.
+#[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
+pub enum Action<'a> {
+    /// Apply the changes, writing the Rust files.
+    Apply,
+    /// Collect the changes in the [`Patch`] instead of applying them.
+    Diff(&'a Patch),
+    /// Collect the renamings in the [`Check`] instead of applying them.
+    Check(&'a Check),
+    /// Only collect the edits, e.g. to gather information about all the Rust
+    /// files before processing them.
+    Scan,
+}
+
+impl Action<'_> {
+    pub fn is_apply(self) -> bool {
+        matches!(self, Action::Apply)
+    }
+}
+
+/// Crate traversal settings.
+pub struct TraversalSettings<'a> {
+    /// The filter to apply to the dir entries.
+    pub filter: &'a dyn DirEntryFilter,
+    pub mode: TraversalMode,
+    /// Maximum number of Rust files to process concurrently.
+    pub jobs: usize,
+    pub action: Action<'a>,
+    /// Records the errors & keeps going instead of stopping at the first error.
+    pub errors: Option<&'a Errors>,
+    /// Keeps a copy of the overwritten Rust files with this suffix.
+    pub backup_suffix: Option<&'a str>,
+    /// Records the applied edits in this [`Journal`].
+    pub journal: Option<&'a Journal>,
+    /// Selects [Markdown](markdown) files to process, in addition to those
+    /// included in the documentation of the Rust files.
+    pub markdown: &'a [Glob],
+}
+
+impl<'a> TraversalSettings<'a> {
+    /// Builds [`TraversalSettings`] walking the directories with a single job.
+    pub fn new(filter: &'a dyn DirEntryFilter) -> Self {
+        TraversalSettings {
+            filter,
+            mode: TraversalMode::Directories,
+            jobs: 1,
+            action: Action::Apply,
+            errors: None,
+            backup_suffix: None,
+            journal: None,
+            markdown: &[],
+        }
+    }
+}
 
 /// Crate traversal mechanism.
 ///
-/// By default, the dir entries are traversed according to the rules defined
-/// in [`rules::dir_entry`].
+/// The dir entries are traversed according to the [`DirEntryFilter`] from
+/// the [`TraversalSettings`]. Use [`DefaultDirEntryFilter`](rules::dir_entry::DefaultDirEntryFilter)
+/// to apply the rules defined in [`rules::dir_entry`].
+///
+/// If `output_path` is specified, the traversed tree is replicated there,
+/// including the files which are not processed, except for the directories
+/// listed in [`NOT_MIRRORED`]. The output directories are reused if they
+/// already exist.
+/// When traversing a single file, `output_path` can be either a file
+/// or an existing directory.
 ///
-/// If `output_path` is specified, the traversed tree is replicated there.
-pub trait CrateTraverser {
+/// The Rust files can be processed concurrently. In this case, the logs
+/// are emitted in traversal order if the global logger is a
+/// [`Logger`](crate::logger::Logger).
+pub trait CrateTraverser: Sync {
+    /// Collects the [`Edit`]s to apply to the Rust `source_code` read from `path`.
+    ///
+    /// This is also called for the [Markdown](markdown) files selected by the
+    /// [`TraversalSettings`]: use [`markdown::is_markdown`] to tell them apart.
+    fn collect_edits(&self, path: &Path, source_code: &str) -> Result<Vec<Edit>, Error>;
+
+    /// Fixes the Rust `source_code` read from `path`.
+    ///
+    /// Returns `None` if nothing needs to be changed.
+    fn fix(&self, path: &Path, source_code: &str) -> Result<Option<String>, Error> {
+        let edits = self.collect_edits(path, source_code)?;
+        if edits.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(edit::apply(source_code, &edits)))
+    }
+
     /// Called when the path points to a Rust file.
-    fn handle_rust_file(&mut self, path: &Path, output_path: &Option<PathBuf>)
-        -> Result<(), Error>;
+    ///
+    /// Returns the [`FixedFile`] to write to `output_path` if specified,
+    /// otherwise to `path`. When the file doesn't need to be changed, it is
+    /// copied to `output_path` if specified. Returns `None` if there is
+    /// nothing to write.
+    fn fix_rust_file(
+        &self,
+        path: &Path,
+        output_path: &Option<PathBuf>,
+    ) -> Result<Option<FixedFile>, Error> {
+        let source_code =
+            fs::read_to_string(path).map_err(|err| Error::ReadFile(path.to_owned(), err))?;
+
+        let edits = self.collect_edits(path, &source_code)?;
+        let output = (!edits.is_empty()).then(|| edit::apply(&source_code, &edits));
+        let (output_path, output, is_in_place) = match (output_path, output) {
+            (Some(output_path), output) => (
+                output_path.clone(),
+                output.unwrap_or_else(|| source_code.clone()),
+                false,
+            ),
+            (None, Some(output)) => (path.to_owned(), output, true),
+            (None, None) => {
+                // Nothing to do for this file
+                return Ok(None);
+            }
+        };
+
+        Ok(Some(FixedFile {
+            output_path,
+            source_code,
+            edits,
+            output,
+            is_in_place,
+        }))
+    }
 
+    /// Called in dry run mode when the path points to a Rust file.
+    ///
+    /// The changes are added to the `patch` instead of being written.
+    fn diff_rust_file(&self, path: &Path, patch: &Patch) -> Result<(), Error> {
+        let source_code =
+            fs::read_to_string(path).map_err(|err| Error::ReadFile(path.to_owned(), err))?;
+
+        let edits = self.collect_edits(path, &source_code)?;
+        patch.add(path, &source_code, &edits);
+
+        Ok(())
+    }
+
+    /// Called in check mode when the path points to a Rust file.
+    ///
+    /// The renamings are added to the `check` instead of being written.
+    fn check_rust_file(&self, path: &Path, check: &Check) -> Result<(), Error> {
+        let source_code =
+            fs::read_to_string(path).map_err(|err| Error::ReadFile(path.to_owned(), err))?;
+
+        let edits = self.collect_edits(path, &source_code)?;
+        check.add(path, &source_code, &edits);
+
+        Ok(())
+    }
+
+    /// Called in scan mode when the path points to a Rust file.
+    ///
+    /// The edits are collected and discarded.
+    fn scan_rust_file(&self, path: &Path) -> Result<(), Error> {
+        let source_code =
+            fs::read_to_string(path).map_err(|err| Error::ReadFile(path.to_owned(), err))?;
+
+        self.collect_edits(path, &source_code).map(drop)
+    }
+
+    /// Fixes the Rust code read from `input` and writes the result to `output`.
+    ///
+    /// `path` is only used to identify the source in logs and errors.
+    /// The code is written unchanged if nothing needs to be changed.
+    fn fix_stream(
+        &self,
+        path: &Path,
+        input: &mut dyn Read,
+        output: &mut dyn Write,
+    ) -> Result<(), Error> {
+        let mut source_code = String::new();
+        input
+            .read_to_string(&mut source_code)
+            .map_err(|err| Error::ReadFile(path.to_owned(), err))?;
+
+        let fixed = self.fix(path, &source_code)?;
+        output
+            .write_all(fixed.as_deref().unwrap_or(&source_code).as_bytes())
+            .and_then(|()| output.flush())
+            .map_err(|err| Error::WriteFile(path.to_owned(), err))
+    }
+
+    /// Called when the filter skips a dir entry.
+    ///
+    /// If `output_path` is specified, the entry is already replicated there.
     fn handle_skipped_dir_entry(
-        &mut self,
+        &self,
         _entry: &DirEntry,
         _output_path: &Option<PathBuf>,
     ) -> Result<(), Error> {
@@ -30,50 +238,799 @@ pub trait CrateTraverser {
     }
 
     /// Traverses the crate or workspace from the specified path.
-    fn traverse(&mut self, path: &Path, output_path: &Option<PathBuf>) -> Result<(), Error> {
+    ///
+    /// Up to `settings.jobs` Rust files are processed concurrently. The files
+    /// are written in traversal order. In case of an error, the first error in
+    /// traversal order is returned and the following files are not written,
+    /// like with a single job, unless `settings.errors` is specified: the error
+    /// is then recorded against the path of the dir entry or Rust file and the
+    /// traversal goes on.
+    ///
+    /// `output_path` is ignored unless `settings.action` is [`Action::Apply`].
+    fn traverse(
+        &self,
+        path: &Path,
+        settings: &TraversalSettings,
+        output_path: &Option<PathBuf>,
+    ) -> Result<(), Error> {
+        let output_path = if settings.action.is_apply() {
+            output_path
+        } else {
+            &None
+        };
+
+        // A single file can be written to an existing output directory
+        let output_path = match output_path {
+            Some(output_dir) if !path.is_dir() && output_dir.is_dir() => {
+                path.file_name().map(|file_name| output_dir.join(file_name))
+            }
+            _ => output_path.clone(),
+        };
+        let output_path = &output_path;
+
+        // The output root is skipped in case it is located in the traversed tree
+        let output_root = output_path
+            .as_ref()
+            .and_then(|output_path| output_path.canonicalize().ok());
+        let output_root = output_root.as_deref();
+
+        let mut rust_files = Vec::new();
+        if settings.mode.is_module_graph() && path.is_dir() {
+            let res = collect_module_graph_files(
+                path,
+                settings.filter,
+                output_path,
+                output_root,
+                &mut rust_files,
+            )
+            .and_then(|()| match output_path.as_ref() {
+                Some(output_path) => {
+                    let processed = rust_files
+                        .iter()
+                        .map(|rust_file| rust_file.path.clone())
+                        .collect();
+                    mirror(path, output_path, output_root, &processed)
+                }
+                None => Ok(()),
+            });
+            keep_going(settings.errors, path, res)?;
+        } else {
+            collect_rust_files(
+                self,
+                path,
+                settings.filter,
+                output_path,
+                output_root,
+                settings.errors,
+                &mut rust_files,
+            )?;
+        }
+
         if path.is_dir() {
+            let res = collect_markdown_files(
+                path,
+                settings.markdown,
+                output_path,
+                output_root,
+                &mut rust_files,
+            );
+            keep_going(settings.errors, path, res)?;
+        }
+
+        if settings.jobs <= 1 || rust_files.len() <= 1 {
+            let process_settings = ProcessSettings::from(settings);
+            for rust_file in rust_files.iter() {
+                keep_going(
+                    settings.errors,
+                    &rust_file.path,
+                    rust_file
+                        .process(self, &process_settings)
+                        .and_then(|fixed| process_settings.write(fixed)),
+                )?;
+            }
+
+            return Ok(());
+        }
+
+        process_concurrently(self, &rust_files, settings)
+    }
+}
+
+/// A Rust file fixed by a [`CrateTraverser`], ready to be written.
+#[derive(Debug)]
+pub struct FixedFile {
+    output_path: PathBuf,
+    source_code: String,
+    edits: Vec<Edit>,
+    output: String,
+    /// Whether the input file is overwritten.
+    is_in_place: bool,
+}
+
+impl FixedFile {
+    /// Writes the file [atomically](atomic_write::write).
+    ///
+    /// When the input file is overwritten, a copy is kept with the
+    /// `backup_suffix` if specified. The applied edits are recorded in the
+    /// `journal` if specified.
+    pub fn write(
+        self,
+        backup_suffix: Option<&str>,
+        journal: Option<&Journal>,
+    ) -> Result<(), Error> {
+        let backup_suffix = backup_suffix.filter(|_| self.is_in_place);
+        atomic_write::write(&self.output_path, self.output.as_bytes(), backup_suffix)?;
+        if let Some(journal) = journal {
+            journal.add(
+                &self.output_path,
+                &self.source_code,
+                &self.edits,
+                &self.output,
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// The [`TraversalSettings`] used to process a [`RustFile`].
+///
+/// Unlike the [`DirEntryFilter`], these settings can be shared between threads.
+#[derive(Clone, Copy)]
+struct ProcessSettings<'a> {
+    action: Action<'a>,
+    backup_suffix: Option<&'a str>,
+    journal: Option<&'a Journal>,
+}
+
+impl<'a> From<&TraversalSettings<'a>> for ProcessSettings<'a> {
+    fn from(settings: &TraversalSettings<'a>) -> Self {
+        ProcessSettings {
+            action: settings.action,
+            backup_suffix: settings.backup_suffix,
+            journal: settings.journal,
+        }
+    }
+}
+
+impl ProcessSettings<'_> {
+    /// Writes the `fixed` file, if any.
+    fn write(&self, fixed: Option<FixedFile>) -> Result<(), Error> {
+        match fixed {
+            Some(fixed) => fixed.write(self.backup_suffix, self.journal),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A file to process: either a Rust file or a [Markdown](markdown) file.
+#[derive(Debug)]
+struct RustFile {
+    path: PathBuf,
+    output_path: Option<PathBuf>,
+}
+
+impl RustFile {
+    /// Processes the file according to the `settings`.
+    ///
+    /// Returns the [`FixedFile`] to write, if any: the files are written
+    /// in traversal order by the caller.
+    fn process<T: CrateTraverser + ?Sized>(
+        &self,
+        traverser: &T,
+        settings: &ProcessSettings,
+    ) -> Result<Option<FixedFile>, Error> {
+        #[cfg(feature = "log")]
+        debug!("processing {:?}", self.path);
+        match settings.action {
+            Action::Apply => traverser.fix_rust_file(&self.path, &self.output_path),
+            Action::Diff(patch) => traverser.diff_rust_file(&self.path, patch).map(|()| None),
+            Action::Check(check) => traverser.check_rust_file(&self.path, check).map(|()| None),
+            Action::Scan => traverser.scan_rust_file(&self.path).map(|()| None),
+        }
+    }
+}
+
+/// Records the `error` against `path` if `errors` is specified, otherwise returns it.
+fn keep_going(errors: Option<&Errors>, path: &Path, res: Result<(), Error>) -> Result<(), Error> {
+    match (res, errors) {
+        (Err(error), Some(errors)) => {
+            #[cfg(feature = "log")]
+            debug!("keeping going after error in {:?}", path);
+            errors.add(path, error);
+            Ok(())
+        }
+        (res, _) => res,
+    }
+}
+
+/// Traverses the tree from `path` collecting the Rust files to process.
+///
+/// The dir entries are sorted so that the traversal order is deterministic.
+fn collect_rust_files<T: CrateTraverser + ?Sized>(
+    traverser: &T,
+    path: &Path,
+    filter: &dyn DirEntryFilter,
+    output_path: &Option<PathBuf>,
+    output_root: Option<&Path>,
+    errors: Option<&Errors>,
+    rust_files: &mut Vec<RustFile>,
+) -> Result<(), Error> {
+    if !path.is_dir() {
+        rust_files.push(RustFile {
+            path: path.to_owned(),
+            output_path: output_path.clone(),
+        });
+
+        return Ok(());
+    }
+
+    #[cfg(feature = "log")]
+    debug!("entering {:?}", path);
+
+    let entries = match read_sorted_dir(path) {
+        Ok(entries) => entries,
+        Err(error) => return keep_going(errors, path, Err(error)),
+    };
+
+    for entry in entries {
+        if is_output_root(&entry.path(), output_root) {
             #[cfg(feature = "log")]
-            debug!("entering {:?}", path);
+            debug!("skipping output {:?}", entry.path());
+            continue;
+        }
 
-            for entry in
-                std::fs::read_dir(path).map_err(|err| Error::ReadDir(path.to_owned(), err))?
+        let res = collect_dir_entry(
+            traverser,
+            &entry,
+            filter,
+            output_path,
+            output_root,
+            errors,
+            rust_files,
+        );
+        keep_going(errors, &entry.path(), res)?;
+    }
+
+    Ok(())
+}
+
+/// Reads the entries of the directory at `path`, sorted by file name.
+fn read_sorted_dir(path: &Path) -> Result<Vec<DirEntry>, Error> {
+    let mut entries = Vec::new();
+    for entry in std::fs::read_dir(path).map_err(|err| Error::ReadDir(path.to_owned(), err))? {
+        entries.push(entry.map_err(|err| Error::ReadEntry(path.to_owned(), err))?);
+    }
+    entries.sort_by_key(|entry| entry.file_name());
+
+    Ok(entries)
+}
+
+/// Checks the dir `entry` collecting the Rust files to process.
+///
+/// `output_path` is the output path of the parent directory.
+fn collect_dir_entry<T: CrateTraverser + ?Sized>(
+    traverser: &T,
+    entry: &DirEntry,
+    filter: &dyn DirEntryFilter,
+    output_path: &Option<PathBuf>,
+    output_root: Option<&Path>,
+    errors: Option<&Errors>,
+    rust_files: &mut Vec<RustFile>,
+) -> Result<(), Error> {
+    use dir_entry::CheckOk::*;
+    let is_dir = match filter.check(entry)? {
+        Directory => true,
+        RustFile => false,
+        Skip(_) => {
+            #[cfg(feature = "log")]
+            debug!("skipping {:?}", entry.file_name().to_str());
+            if let Some(output_path) = output_path.as_ref() {
+                let output_path = output_path.join(entry.file_name());
+                mirror(&entry.path(), &output_path, output_root, &HashSet::new())?;
+            }
+            return traverser.handle_skipped_dir_entry(entry, output_path);
+        }
+        SkipUnspecified => return Ok(()),
+    };
+
+    let output_path = match output_path.as_ref() {
+        Some(output_path) => {
+            let output_path = output_path.join(entry.file_name());
+            if is_dir {
+                fs::create_dir_all(&output_path)
+                    .map_err(|err| Error::CreateDir(output_path.to_owned(), err))?;
+            }
+            Some(output_path)
+        }
+        None => None,
+    };
+
+    collect_rust_files(
+        traverser,
+        &entry.path(),
+        filter,
+        &output_path,
+        output_root,
+        errors,
+        rust_files,
+    )
+}
+
+/// Collects the Rust files following the module graph from the crates roots.
+fn collect_module_graph_files(
+    path: &Path,
+    filter: &dyn DirEntryFilter,
+    output_path: &Option<PathBuf>,
+    output_root: Option<&Path>,
+    rust_files: &mut Vec<RustFile>,
+) -> Result<(), Error> {
+    let root = module_graph::normalize(path);
+    for file_path in module_graph::rust_files(path, filter)? {
+        // Don't process the output of a previous run
+        if let Some(output_root) = output_root {
+            if file_path
+                .canonicalize()
+                .is_ok_and(|file_path| file_path.starts_with(output_root))
             {
-                let entry = entry.map_err(|err| Error::ReadEntry(path.to_owned(), err))?;
-
-                use dir_entry::CheckOk::*;
-                let is_dir = match dir_entry::check(&entry)? {
-                    Directory => true,
-                    RustFile => false,
-                    Skip(_) => {
-                        #[cfg(feature = "log")]
-                        debug!("skipping {:?}", entry.file_name().to_str());
-                        self.handle_skipped_dir_entry(&entry, output_path)?;
-                        continue;
+                continue;
+            }
+        }
+
+        let output_path = match output_path.as_ref() {
+            Some(output_path) => match file_path.strip_prefix(&root) {
+                Ok(rel_path) => {
+                    let output_path = output_path.join(rel_path);
+                    if let Some(parent) = output_path.parent() {
+                        fs::create_dir_all(parent)
+                            .map_err(|err| Error::CreateDir(parent.to_owned(), err))?;
                     }
-                    SkipUnspecified => continue,
-                };
+                    Some(output_path)
+                }
+                Err(_) => {
+                    #[cfg(feature = "log")]
+                    warn!("skipping {:?}: located out of {:?}", file_path, path);
+                    continue;
+                }
+            },
+            None => None,
+        };
+
+        rust_files.push(RustFile {
+            path: file_path,
+            output_path,
+        });
+    }
+
+    Ok(())
+}
+
+/// Adds the [Markdown](markdown) files to process to `rust_files`.
+///
+/// The Markdown files are selected using the `globs` or included in the
+/// documentation of the Rust files already collected. Only the files located
+/// under `path` are processed.
+fn collect_markdown_files(
+    path: &Path,
+    globs: &[Glob],
+    output_path: &Option<PathBuf>,
+    output_root: Option<&Path>,
+    rust_files: &mut Vec<RustFile>,
+) -> Result<(), Error> {
+    let root = path
+        .canonicalize()
+        .map_err(|err| Error::ReadDir(path.to_owned(), err))?;
+
+    let mut md_files = BTreeSet::new();
+    markdown::collect(&root, globs, output_root, &mut md_files)?;
+
+    for rust_file in rust_files.iter() {
+        // Read errors are reported when processing the Rust file
+        let source_code = match fs::read_to_string(&rust_file.path) {
+            Ok(source_code) => source_code,
+            Err(_) => continue,
+        };
+        let dir = rust_file.path.parent().unwrap_or_else(|| Path::new(""));
+        for include in markdown::doc_includes(&source_code) {
+            if let Ok(md_file) = dir.join(include).canonicalize() {
+                md_files.insert(md_file);
+            }
+        }
+    }
+
+    for md_file in md_files {
+        let rel_path = match md_file.strip_prefix(&root) {
+            Ok(rel_path) => rel_path,
+            Err(_) => {
+                #[cfg(feature = "log")]
+                debug!("skipping {:?}: located out of {:?}", md_file, path);
+                continue;
+            }
+        };
+        if output_root.is_some_and(|output_root| md_file.starts_with(output_root)) {
+            continue;
+        }
+
+        let output_path = match output_path.as_ref() {
+            Some(output_path) => {
+                let output_path = output_path.join(rel_path);
+                if let Some(parent) = output_path.parent() {
+                    fs::create_dir_all(parent)
+                        .map_err(|err| Error::CreateDir(parent.to_owned(), err))?;
+                }
+                Some(output_path)
+            }
+            None => None,
+        };
+
+        rust_files.push(RustFile {
+            path: path.join(rel_path),
+            output_path,
+        });
+    }
+
+    Ok(())
+}
+
+/// Replicates the dir entry at `path` to `output_path`.
+///
+/// Directories are replicated recursively, except for those listed in
+/// [`NOT_MIRRORED`] and the `output_root`. The files in `processed` are
+/// skipped, as well as the symbolic links to directories.
+fn mirror(
+    path: &Path,
+    output_path: &Path,
+    output_root: Option<&Path>,
+    processed: &HashSet<PathBuf>,
+) -> Result<(), Error> {
+    if path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| NOT_MIRRORED.contains(name))
+    {
+        return Ok(());
+    }
+
+    let file_type = fs::symlink_metadata(path)
+        .map_err(|err| Error::ReadEntry(path.to_owned(), err))?
+        .file_type();
+
+    if file_type.is_dir() {
+        if is_output_root(path, output_root) {
+            return Ok(());
+        }
+
+        fs::create_dir_all(output_path)
+            .map_err(|err| Error::CreateDir(output_path.to_owned(), err))?;
+
+        for entry in fs::read_dir(path).map_err(|err| Error::ReadDir(path.to_owned(), err))? {
+            let entry = entry.map_err(|err| Error::ReadEntry(path.to_owned(), err))?;
+            mirror(
+                &entry.path(),
+                &output_path.join(entry.file_name()),
+                output_root,
+                processed,
+            )?;
+        }
+    } else if path.is_file() && !processed.contains(&module_graph::normalize(path)) {
+        fs::copy(path, output_path)
+            .map_err(|err| Error::CopyFile(path.to_owned(), output_path.to_owned(), err))?;
+    }
+
+    Ok(())
+}
+
+/// Checks whether `path` is the `output_root`.
+fn is_output_root(path: &Path, output_root: Option<&Path>) -> bool {
+    output_root.is_some_and(|output_root| {
+        path.is_dir() && path.canonicalize().is_ok_and(|path| path == output_root)
+    })
+}
+
+/// Processes the `rust_files` using up to `settings.jobs` threads.
+///
+/// Results are handled in the order of `rust_files`: the fixed files are
+/// written by the calling thread, so that no files are written after an error.
+fn process_concurrently<T: CrateTraverser + ?Sized>(
+    traverser: &T,
+    rust_files: &[RustFile],
+    settings: &TraversalSettings,
+) -> Result<(), Error> {
+    let next_idx = AtomicUsize::new(0);
+    let must_stop = AtomicBool::new(false);
+    let (sender, receiver) = mpsc::channel();
+
+    thread::scope(|scope| {
+        for _ in 0..settings.jobs.min(rust_files.len()) {
+            let sender = sender.clone();
+            let next_idx = &next_idx;
+            let must_stop = &must_stop;
+            let process_settings = ProcessSettings::from(settings);
 
-                let path = entry.path();
-                let output_path = match output_path.as_ref() {
-                    Some(output_path) => {
-                        let output_path = output_path.join(entry.file_name());
-                        if is_dir {
-                            std::fs::create_dir(&output_path)
-                                .map_err(|err| Error::CreateDir(output_path.to_owned(), err))?;
-                        }
-                        Some(output_path)
+            scope.spawn(move || {
+                while !must_stop.load(Ordering::Relaxed) {
+                    let idx = next_idx.fetch_add(1, Ordering::Relaxed);
+                    let rust_file = match rust_files.get(idx) {
+                        Some(rust_file) => rust_file,
+                        None => break,
+                    };
+
+                    #[cfg(feature = "log")]
+                    let res =
+                        crate::logger::capture(|| rust_file.process(traverser, &process_settings));
+                    #[cfg(not(feature = "log"))]
+                    let res = rust_file.process(traverser, &process_settings);
+
+                    if sender.send((idx, res)).is_err() {
+                        break;
                     }
-                    None => None,
+                }
+            });
+        }
+        drop(sender);
+
+        // Handle the results in order
+        let process_settings = ProcessSettings::from(settings);
+        let mut pending = BTreeMap::new();
+        let mut expected_idx = 0;
+        for (idx, res) in receiver.iter() {
+            pending.insert(idx, res);
+
+            while let Some(res) = pending.remove(&expected_idx) {
+                #[cfg(feature = "log")]
+                let res = {
+                    let (res, logs) = res;
+                    logs.emit();
+                    res
                 };
 
-                self.traverse(&path, &output_path)?;
+                let path = &rust_files[expected_idx].path;
+                let res = res.and_then(|fixed| process_settings.write(fixed));
+                if let Err(err) = keep_going(settings.errors, path, res) {
+                    must_stop.store(true, Ordering::Relaxed);
+                    return Err(err);
+                }
+
+                expected_idx += 1;
             }
+        }
 
-            return Ok(());
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{temp_dir, write};
+    use rules::dir_entry::DefaultDirEntryFilter;
+
+    struct Unchanged;
+
+    impl CrateTraverser for Unchanged {
+        fn collect_edits(&self, _path: &Path, _source_code: &str) -> Result<Vec<Edit>, Error> {
+            Ok(Vec::new())
         }
+    }
+
+    struct Parser;
+
+    impl CrateTraverser for Parser {
+        fn collect_edits(&self, path: &Path, source_code: &str) -> Result<Vec<Edit>, Error> {
+            syn::parse_file(source_code).map_err(|err| {
+                crate::ParseFileError::new(err, path.to_owned(), source_code.to_string())
+            })?;
+            Ok(Vec::new())
+        }
+    }
+
+    /// Appends a comment to the Rust files which can be parsed.
+    struct Marker;
+
+    impl CrateTraverser for Marker {
+        fn collect_edits(&self, path: &Path, source_code: &str) -> Result<Vec<Edit>, Error> {
+            #[cfg(feature = "log")]
+            log::info!(
+                "concurrent: {}",
+                path.file_name().unwrap().to_str().unwrap()
+            );
+            Parser.collect_edits(path, source_code)?;
+            Ok(vec![Edit::insert(source_code.len(), "// fixed\n")])
+        }
+    }
+
+    #[test]
+    fn mirror_tree() {
+        let root = temp_dir(env!("CARGO_PKG_NAME"), "mirror");
+
+        write(&root.join("Cargo.toml"), "[package]\nname = \"test\"\n");
+        write(&root.join("README.md"), "# test\n");
+        write(&root.join("src").join("lib.rs"), "mod sys;\n");
+        write(&root.join("src").join("data").join("asset.txt"), "asset\n");
+        write(&root.join("sys").join("lib.rs"), "fn get_sys() {}\n");
+        write(&root.join("target").join("debug").join("test"), "binary");
+
+        // Output located in the input tree
+        let output_path = root.join("output");
+        fs::create_dir_all(&output_path).unwrap();
+
+        let settings = TraversalSettings::new(&DefaultDirEntryFilter);
+        for _ in 0..2 {
+            Unchanged
+                .traverse(&root, &settings, &Some(output_path.clone()))
+                .unwrap();
+        }
+
+        for file in &[
+            Path::new("Cargo.toml"),
+            Path::new("README.md"),
+            &Path::new("src").join("lib.rs"),
+            &Path::new("src").join("data").join("asset.txt"),
+            &Path::new("sys").join("lib.rs"),
+        ] {
+            assert_eq!(
+                fs::read_to_string(output_path.join(file)).unwrap(),
+                fs::read_to_string(root.join(file)).unwrap(),
+            );
+        }
+        assert!(!output_path.join("target").exists());
+        assert!(!output_path.join("output").exists());
+    }
+
+    #[test]
+    fn rerun_output() {
+        let root = temp_dir(env!("CARGO_PKG_NAME"), "rerun_input");
+        let output_path = temp_dir(env!("CARGO_PKG_NAME"), "rerun_output");
+
+        write(&root.join("Cargo.toml"), "[package]\nname = \"test\"\n");
+        write(&root.join("src").join("lib.rs"), "mod a;\n");
+        write(&root.join("src").join("a.rs"), "fn a() {}\n");
+        write(&root.join("src").join("data").join("asset.txt"), "asset\n");
+
+        let settings = TraversalSettings::new(&DefaultDirEntryFilter);
+        Marker
+            .traverse(&root, &settings, &Some(output_path.clone()))
+            .unwrap();
+        assert_eq!(
+            fs::read_to_string(output_path.join("src").join("a.rs")).unwrap(),
+            "fn a() {}\n// fixed\n",
+        );
+
+        // Re-run into the existing output tree after changing the input
+        write(&root.join("src").join("a.rs"), "fn b() {}\n");
+        write(
+            &root.join("src").join("data").join("asset.txt"),
+            "updated\n",
+        );
+        write(&root.join("src").join("new").join("b.rs"), "fn c() {}\n");
+        Marker
+            .traverse(&root, &settings, &Some(output_path.clone()))
+            .unwrap();
+
+        for (file, expected) in &[
+            (Path::new("Cargo.toml"), "[package]\nname = \"test\"\n"),
+            (&Path::new("src").join("lib.rs"), "mod a;\n// fixed\n"),
+            (&Path::new("src").join("a.rs"), "fn b() {}\n// fixed\n"),
+            (
+                &Path::new("src").join("data").join("asset.txt"),
+                "updated\n",
+            ),
+            (
+                &Path::new("src").join("new").join("b.rs"),
+                "fn c() {}\n// fixed\n",
+            ),
+        ] {
+            assert_eq!(
+                fs::read_to_string(output_path.join(file)).unwrap(),
+                *expected,
+                "{:?}",
+                file,
+            );
+        }
+    }
+
+    #[test]
+    fn keep_going() {
+        let root = temp_dir(env!("CARGO_PKG_NAME"), "keep_going");
+
+        write(&root.join("src").join("a.rs"), "fn a() {}\n");
+        write(&root.join("src").join("b.rs"), "fn b( {}\n");
+        write(&root.join("src").join("c.rs"), "fn c( {}\n");
+        write(&root.join("src").join("d.rs"), "fn d() {}\n");
+
+        let mut settings = TraversalSettings::new(&DefaultDirEntryFilter);
+        settings.action = Action::Scan;
+        match Parser.traverse(&root, &settings, &None) {
+            Err(Error::ParseFile(_)) => (),
+            other => panic!("unexpected {:?}", other),
+        }
+
+        for jobs in 1..=2 {
+            let errors = Errors::default();
+            let settings = TraversalSettings {
+                jobs,
+                errors: Some(&errors),
+                ..settings
+            };
+            Parser.traverse(&root, &settings, &None).unwrap();
+            assert_eq!(errors.len(), 2);
+        }
+    }
+
+    #[test]
+    fn concurrent() {
+        #[cfg(feature = "log")]
+        crate::logger::tests::init();
+
+        let root = temp_dir(env!("CARGO_PKG_NAME"), "concurrent");
+        let names: Vec<String> = (0..16).map(|idx| format!("f{:02}.rs", idx)).collect();
+        let is_invalid = |name: &str| name == "f05.rs" || name == "f09.rs";
+        let reset = || {
+            let _ = fs::remove_dir_all(&root);
+            for name in names.iter() {
+                let content = if is_invalid(name) {
+                    "fn f( {}\n"
+                } else {
+                    "fn f() {}\n"
+                };
+                write(&root.join("src").join(name), content);
+            }
+        };
+        let written = || -> Vec<&str> {
+            names
+                .iter()
+                .filter(|name| {
+                    fs::read_to_string(root.join("src").join(name))
+                        .unwrap()
+                        .ends_with("// fixed\n")
+                })
+                .map(String::as_str)
+                .collect()
+        };
+
+        // Stops at the first error in traversal order
+        reset();
+        let mut settings = TraversalSettings::new(&DefaultDirEntryFilter);
+        settings.jobs = 4;
+        match Marker.traverse(&root, &settings, &None) {
+            Err(Error::ParseFile(err)) => assert!(err.to_string().contains("f05.rs"), "{}", err),
+            other => panic!("unexpected {:?}", other),
+        }
+        // The files following the failing file are not written
+        assert_eq!(
+            written(),
+            names[..5].iter().map(String::as_str).collect::<Vec<_>>()
+        );
+        #[cfg(feature = "log")]
+        assert_eq!(
+            crate::logger::tests::recorded("concurrent:"),
+            names[..6]
+                .iter()
+                .map(|name| format!("concurrent: {}", name))
+                .collect::<Vec<_>>(),
+        );
 
+        // Keeps going
+        reset();
+        let errors = Errors::default();
+        settings.errors = Some(&errors);
+        Marker.traverse(&root, &settings, &None).unwrap();
+        assert_eq!(errors.len(), 2);
+        assert_eq!(
+            written(),
+            names
+                .iter()
+                .map(String::as_str)
+                .filter(|name| !is_invalid(name))
+                .collect::<Vec<_>>(),
+        );
         #[cfg(feature = "log")]
-        debug!("processing {:?}", path);
-        self.handle_rust_file(path, output_path)
+        assert_eq!(
+            crate::logger::tests::recorded("concurrent:")[6..],
+            names
+                .iter()
+                .map(|name| format!("concurrent: {}", name))
+                .collect::<Vec<_>>()[..],
+        );
     }
 }
@@ -0,0 +1,122 @@
+//! rustc-style diagnostics.
+
+use proc_macro2::LineColumn;
+use std::{
+    fmt::{self, Display},
+    ops::Range,
+    path::Path,
+};
+
+/// A rustc-style diagnostic pointing at a location in a file.
+///
+/// ```text
+/// src/lib.rs:3:13: expected `;`
+///   |
+/// 3 |     let a = b c;
+///   |               ^
+/// ```
+pub struct Diagnostic<'a> {
+    pub path: &'a Path,
+    /// Line number in the file, starting at 1.
+    pub line: usize,
+    /// Column number in the file, starting at 1.
+    pub column: usize,
+    pub message: &'a dyn Display,
+    /// The line to display with the range of chars to underline.
+    pub snippet: Option<(&'a str, Range<usize>)>,
+}
+
+impl<'a> Diagnostic<'a> {
+    /// Builds a [`Diagnostic`] for the code from `start` to `end` in `source_code`.
+    ///
+    /// The line of `start` is displayed. The underline stops at the end
+    /// of the line if the code covers multiple lines.
+    pub fn new(
+        path: &'a Path,
+        source_code: &'a str,
+        start: LineColumn,
+        end: LineColumn,
+        message: &'a dyn Display,
+    ) -> Self {
+        let snippet = start
+            .line
+            .checked_sub(1)
+            .and_then(|line_idx| source_code.lines().nth(line_idx))
+            .map(|source_line| {
+                let end = if end.line == start.line {
+                    end.column
+                } else {
+                    source_line.chars().count()
+                };
+                (source_line, start.column..end)
+            });
+
+        Diagnostic {
+            path,
+            line: start.line,
+            column: start.column + 1,
+            message,
+            snippet,
+        }
+    }
+}
+
+impl<'a> Display for Diagnostic<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.line == 0 {
+            // Unknown location
+            return write!(f, "{}: {}", self.path.display(), self.message);
+        }
+
+        write!(
+            f,
+            "{}:{}:{}: {}",
+            self.path.display(),
+            self.line,
+            self.column,
+            self.message
+        )?;
+
+        if let Some((source_line, range)) = self.snippet.as_ref() {
+            let line_nb = self.line.to_string();
+            let margin = " ".repeat(line_nb.len());
+
+            // Keep tabs so that the underline is aligned
+            let padding: String = source_line
+                .chars()
+                .take(range.start)
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+            let underline = "^".repeat(range.end.saturating_sub(range.start).max(1));
+
+            write!(
+                f,
+                "\n{} |\n{} | {}\n{} | {}{}",
+                margin, line_nb, source_line, margin, padding, underline
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snippet() {
+        let diagnostic = Diagnostic {
+            path: Path::new("src/lib.rs"),
+            line: 3,
+            column: 13,
+            message: &"expected `;`",
+            snippet: Some(("\tlet a = b c;", 11..12)),
+        };
+
+        assert_eq!(
+            diagnostic.to_string(),
+            "src/lib.rs:3:13: expected `;`\n  |\n3 | \tlet a = b c;\n  | \t          ^",
+        );
+    }
+}
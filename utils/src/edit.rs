@@ -0,0 +1,169 @@
+//! Source code edits.
+
+use std::ops::Range;
+
+use crate::Scope;
+
+/// An edit to apply to a source code.
+///
+/// Edits are expressed as byte ranges in the original source code so that
+/// they can be applied or rendered as a diff exactly.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Edit {
+    /// Byte range of the text to replace in the source code.
+    pub range: Range<usize>,
+    /// The replacement text.
+    pub text: String,
+    pub kind: EditKind,
+}
+
+/// The kind of an [`Edit`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum EditKind {
+    /// Renames the function named after the text in `range` found in `scope`.
+    Rename { scope: Scope },
+    /// Inserts an attribute for the function renamed on the same line.
+    DocAlias,
+    /// Rewrites a derive attribute of the struct found in `scope` so that it
+    /// generates its `getters` under their new names.
+    ///
+    /// The `getters` hold the original & the new names.
+    DeriveAttribute {
+        scope: Scope,
+        getters: Vec<(String, String)>,
+    },
+    #[default]
+    Other,
+}
+
+impl EditKind {
+    pub fn is_rename(&self) -> bool {
+        matches!(self, EditKind::Rename { .. })
+    }
+
+    pub fn is_doc_alias(&self) -> bool {
+        matches!(self, EditKind::DocAlias)
+    }
+
+    pub fn is_derive_attribute(&self) -> bool {
+        matches!(self, EditKind::DeriveAttribute { .. })
+    }
+}
+
+impl Edit {
+    /// Builds an [`Edit`] replacing the text in `range` with `text`.
+    pub fn new(range: Range<usize>, text: impl Into<String>) -> Self {
+        Edit {
+            range,
+            text: text.into(),
+            kind: EditKind::Other,
+        }
+    }
+
+    /// Builds an [`Edit`] renaming the function in `range` as `new_name`.
+    pub fn rename(range: Range<usize>, new_name: impl Into<String>, scope: &Scope) -> Self {
+        Edit {
+            kind: EditKind::Rename {
+                scope: scope.clone(),
+            },
+            ..Edit::new(range, new_name)
+        }
+    }
+
+    /// Builds an [`Edit`] inserting the doc alias `attribute` at byte offset `at`.
+    pub fn doc_alias(at: usize, attribute: impl Into<String>) -> Self {
+        Edit {
+            kind: EditKind::DocAlias,
+            ..Edit::insert(at, attribute)
+        }
+    }
+
+    /// Builds an [`Edit`] rewriting the derive attribute in `range` as `text`
+    /// so that it generates the `getters` of the struct found in `scope`
+    /// under their new names.
+    pub fn derive_attribute(
+        range: Range<usize>,
+        text: impl Into<String>,
+        scope: &Scope,
+        getters: Vec<(String, String)>,
+    ) -> Self {
+        Edit {
+            kind: EditKind::DeriveAttribute {
+                scope: scope.clone(),
+                getters,
+            },
+            ..Edit::new(range, text)
+        }
+    }
+
+    /// Builds an [`Edit`] inserting `text` at byte offset `at`.
+    pub fn insert(at: usize, text: impl Into<String>) -> Self {
+        Edit::new(at..at, text)
+    }
+
+    /// Checks whether this [`Edit`] overlaps the given byte `range`.
+    pub fn overlaps(&self, range: &Range<usize>) -> bool {
+        self.range.start < range.end && range.start < self.range.end
+    }
+}
+
+/// Applies the `edits` to the `source_code`.
+///
+/// The `edits` must not overlap. They are applied in the order of their
+/// start offset, insertions at the same offset in the order of the slice.
+pub fn apply(source_code: &str, edits: &[Edit]) -> String {
+    let mut edits: Vec<&Edit> = edits.iter().collect();
+    edits.sort_by_key(|edit| edit.range.start);
+
+    let mut output = String::with_capacity(source_code.len());
+    let mut cursor = 0;
+    for edit in edits {
+        debug_assert!(edit.range.start >= cursor, "overlapping edits");
+        output.push_str(&source_code[cursor..edit.range.start]);
+        output.push_str(&edit.text);
+        cursor = edit.range.end;
+    }
+    output.push_str(&source_code[cursor..]);
+
+    output
+}
+
+/// Returns the line & column of the byte `offset` in the `source_code`.
+///
+/// Both line & column start at 1. The column is expressed in `char`s.
+pub fn position(source_code: &str, offset: usize) -> (usize, usize) {
+    let before = &source_code[..offset];
+    let line_start = before.rfind('\n').map_or(0, |pos| pos + 1);
+
+    (
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
+
+/// Returns the byte offset of the `line` & `column` in the `source_code`.
+///
+/// The line starts at 1 and the column, expressed in `char`s, starts at 0,
+/// as with the spans of the parsed code. Returns `None` if the position is
+/// out of the `source_code`.
+pub fn offset(source_code: &str, line: usize, column: usize) -> Option<usize> {
+    let line_start = match line {
+        0 => return None,
+        1 => 0,
+        _ => source_code
+            .match_indices('\n')
+            .nth(line - 2)
+            .map(|(pos, _)| pos + 1)?,
+    };
+    let line_end = source_code[line_start..]
+        .find('\n')
+        .map_or(source_code.len(), |len| line_start + len);
+
+    let text = &source_code[line_start..line_end];
+    match text.char_indices().nth(column) {
+        Some((pos, _)) => Some(line_start + pos),
+        None if text.chars().count() == column => Some(line_end),
+        None => None,
+    }
+}
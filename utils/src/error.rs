@@ -1,19 +1,31 @@
 //! `fix-getters` global level `Error`.
 
+use proc_macro2::LineColumn;
 use std::fmt::{self, Display};
-use std::{io, path::PathBuf};
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use crate::Diagnostic;
 
 /// `fix-getters` global level `Error`.
 #[derive(Debug)]
 #[non_exhaustive]
 pub enum Error {
     CheckEntry(rules::dir_entry::CheckError),
+    CopyFile(PathBuf, PathBuf, io::Error),
     CreateDir(PathBuf, io::Error),
     ReadDir(PathBuf, io::Error),
     ReadEntry(PathBuf, io::Error),
     ReadFile(PathBuf, io::Error),
     WriteFile(PathBuf, io::Error),
     ParseFile(ParseFileError),
+    ParseJournal(PathBuf, usize),
+    FileChanged(PathBuf),
+    Prompt(io::Error),
 }
 
 impl Display for Error {
@@ -22,12 +34,24 @@ impl Display for Error {
 
         match self {
             CheckEntry(err) => err.fmt(f),
+            CopyFile(path, output_path, err) => write!(
+                f,
+                "Unable to copy file {:?} to {:?}: {}",
+                path, output_path, err
+            ),
             CreateDir(path, err) => write!(f, "Unable to create dir {:?} {}", path, err),
             ReadDir(path, err) => write!(f, "Unable to read dir {:?}: {}", path, err),
             ReadEntry(path, err) => write!(f, "Unable to read dir entry {:?}: {}", path, err),
             ReadFile(path, err) => write!(f, "Unable to read file {:?}: {}", path, err),
             WriteFile(path, err) => write!(f, "Unable to write file {:?}: {}", path, err),
             ParseFile(err) => err.fmt(f),
+            ParseJournal(path, line) => write!(f, "Invalid journal {:?} at line {}", path, line),
+            FileChanged(path) => write!(
+                f,
+                "File {:?} changed since the journal was written, refusing to undo",
+                path
+            ),
+            Prompt(err) => write!(f, "Unable to prompt for decision: {}", err),
         }
     }
 }
@@ -41,16 +65,26 @@ impl From<rules::dir_entry::CheckError> for Error {
 }
 
 /// Rust code parser error wrapper.
+///
+/// Displays as a rustc-style [`Diagnostic`]. Use the alternate form `{:#}`
+/// to also display the whole source code.
 #[derive(Debug)]
 pub struct ParseFileError {
     error: syn::Error,
+    // The location is retrieved when the error is built
+    // because spans can't be used from other threads.
+    start: LineColumn,
+    end: LineColumn,
     filepath: PathBuf,
     source_code: String,
 }
 
 impl ParseFileError {
     pub fn new(error: syn::Error, filepath: PathBuf, source_code: String) -> Self {
+        let span = error.span();
         ParseFileError {
+            start: span.start(),
+            end: span.end(),
             error,
             filepath,
             source_code,
@@ -60,11 +94,20 @@ impl ParseFileError {
 
 impl Display for ParseFileError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "failed to parse file {:?}: {:?}\n\t{}",
-            self.filepath, self.error, self.source_code
+        Diagnostic::new(
+            &self.filepath,
+            &self.source_code,
+            self.start,
+            self.end,
+            &self.error,
         )
+        .fmt(f)?;
+
+        if f.alternate() {
+            write!(f, "\n\n{}", self.source_code)?;
+        }
+
+        Ok(())
     }
 }
 
@@ -75,3 +118,86 @@ impl From<ParseFileError> for super::Error {
         Error::ParseFile(err)
     }
 }
+
+/// Errors recorded against their path when the traversal keeps going.
+///
+/// Only the first error of each path is kept. The errors are rendered in the
+/// order of the paths.
+#[derive(Debug, Default)]
+pub struct Errors {
+    paths: Mutex<BTreeMap<PathBuf, Error>>,
+}
+
+impl Errors {
+    /// Records the `error` which occurred while processing `path`.
+    pub fn add(&self, path: &Path, error: Error) {
+        self.paths
+            .lock()
+            .expect("errors poisoned")
+            .entry(path.to_owned())
+            .or_insert(error);
+    }
+
+    /// Returns the number of paths which couldn't be processed.
+    pub fn len(&self) -> usize {
+        self.paths.lock().expect("errors poisoned").len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.lock().expect("errors poisoned").is_empty()
+    }
+
+    /// Writes the summary of the errors to `w`.
+    ///
+    /// If `is_verbose` is set, the whole source code is displayed for the
+    /// files which couldn't be parsed.
+    pub fn write_to(&self, w: &mut dyn Write, is_verbose: bool) -> io::Result<()> {
+        let paths = self.paths.lock().expect("errors poisoned");
+        for error in paths.values() {
+            if is_verbose {
+                writeln!(w, "{:#}\n", error)?;
+            } else {
+                writeln!(w, "{}\n", error)?;
+            }
+        }
+        writeln!(w, "{} path(s) could not be processed", paths.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors() {
+        let errors = Errors::default();
+        assert!(errors.is_empty());
+
+        let not_found = || io::Error::from(io::ErrorKind::NotFound);
+        errors.add(
+            Path::new("src/b.rs"),
+            Error::ReadFile("src/b.rs".into(), not_found()),
+        );
+        errors.add(
+            Path::new("src/a.rs"),
+            Error::ReadFile("src/a.rs".into(), not_found()),
+        );
+        errors.add(
+            Path::new("src/b.rs"),
+            Error::WriteFile("src/b.rs".into(), not_found()),
+        );
+        assert_eq!(errors.len(), 2);
+
+        let mut summary = Vec::new();
+        errors.write_to(&mut summary, false).unwrap();
+        assert_eq!(
+            String::from_utf8(summary).unwrap(),
+            format!(
+                "Unable to read file \"src/a.rs\": {0}\n\n\
+                 Unable to read file \"src/b.rs\": {0}\n\n\
+                 2 path(s) could not be processed\n",
+                not_found(),
+            ),
+        );
+    }
+}
@@ -11,6 +11,8 @@ use std::{
 
 use rules::{self, NewName, RenameError, ReturnsBool};
 
+use crate::Scope;
+
 /// `Getter` helper.
 ///
 /// A `Getter` is a function for which the renaming rules defined in crate
@@ -20,6 +22,10 @@ pub struct Getter {
     pub name: String,
     pub new_name: NewName,
     pub line: usize,
+    /// The scope in which the getter was found.
+    ///
+    /// This is defined when the getter is added to a collection.
+    pub scope: Scope,
 }
 
 #[derive(Debug)]
@@ -61,6 +67,7 @@ impl Getter {
                 name,
                 new_name,
                 line,
+                scope: Scope::default(),
             }),
             Err(err) => Err(GetterError { name, err, line }),
         }
@@ -102,7 +109,7 @@ pub fn skip(scope: &dyn Display, name: &str, reason: &dyn Display, line: usize)
 }
 
 /// Reason for considering a function is not a getter.
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 #[non_exhaustive]
 pub enum NonGetterReason {
     GenericTypeParam,
@@ -111,6 +118,20 @@ pub enum NonGetterReason {
     NotAMethod,
     NonSelfUniqueArg,
     NoArgs,
+    /// The getter definition is kept unchanged.
+    DefinitionKept,
+    /// No renamed definition is known for the getter.
+    NoRenamedDefinition,
+    /// The item is opted out by a marker, see [`opt_out`](crate::opt_out).
+    OptedOut,
+    /// The name is generated by a concatenation, see [`paste`](crate::paste).
+    Generated,
+    /// The returned type can't be determined, e.g. it's a macro variable.
+    UnknownReturnType,
+    /// The getter is generated by a derive attribute which can't be rewritten.
+    Derived,
+    /// The renaming was declined during the review, see [`review`](crate::review).
+    Declined,
 }
 
 impl Display for NonGetterReason {
@@ -123,6 +144,13 @@ impl Display for NonGetterReason {
             NotAMethod => f.write_str("not a method"),
             NonSelfUniqueArg => f.write_str("unique argument is not self"),
             NoArgs => f.write_str("no arguments"),
+            DefinitionKept => f.write_str("definition kept unchanged"),
+            NoRenamedDefinition => f.write_str("no renamed definition found"),
+            OptedOut => f.write_str("opted out by a marker"),
+            Generated => f.write_str("name generated by a concatenation"),
+            UnknownReturnType => f.write_str("unknown return type"),
+            Derived => f.write_str("generated by a derive attribute"),
+            Declined => f.write_str("declined during the review"),
         }
     }
 }
@@ -0,0 +1,590 @@
+//! Journal of the applied edits.
+//!
+//! The [`Journal`] records the edits applied to each Rust file so that they
+//! can be [undone](Journal::undo) selectively later on. For each file, the
+//! journal holds a hash of the content after the edits: a file which changed
+//! since the journal was written is not modified.
+//!
+//! The journal is a text file with one record per line & tab separated fields
+//! (displayed as spaces below):
+//!
+//! ```text
+//! root /path/to/project
+//! file src/lib.rs 8f3a6c1e2b4d5a79
+//! edit 42 46 Buffer get_size get_size size
+//! ```
+//!
+//! The `file` records hold the path relative to the `root` & the hash. The
+//! `edit` records hold the byte range of the new text in the edited file,
+//! the scope & the comma separated names of the functions, the old text &
+//! the new text.
+
+use rules::dir_entry::Glob;
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::Write as _,
+    fs,
+    io::{self, Write},
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use crate::{atomic_write, edit, scope, Edit, EditKind, Error};
+
+/// An edit recorded in the [`Journal`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JournalEntry {
+    /// Byte range of the `new_text` in the edited file.
+    pub range: Range<usize>,
+    /// The scope of the function, as displayed in the reports.
+    pub scope: String,
+    /// The original names of the functions the edit applies to.
+    ///
+    /// This is empty for the edits which don't apply to a function.
+    pub names: Vec<String>,
+    pub old_text: String,
+    pub new_text: String,
+}
+
+/// The edits recorded for a file in the [`Journal`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JournalFile {
+    /// Hash of the content of the file after the edits.
+    pub hash: u64,
+    pub entries: Vec<JournalEntry>,
+}
+
+/// Selection of the [`JournalEntry`]s to undo.
+///
+/// An entry is selected if it matches one of the patterns of each non-empty
+/// criterion.
+#[derive(Debug, Default)]
+pub struct UndoFilter {
+    /// Path patterns, relative to the root of the journal.
+    pub paths: Vec<Glob>,
+    /// Scope patterns, matching the end of the scope paths displayed in the
+    /// reports, e.g. `Button` or `widget::impl Widget for Button`.
+    ///
+    /// The scopes select the functions renamed in the matching scopes. Since
+    /// the calls are renamed by name, the calls & the definitions with the
+    /// same names are selected too, whatever their scopes.
+    ///
+    /// See [`scope::matches`](crate::scope::matches).
+    pub scopes: Vec<String>,
+    /// Original names of the functions.
+    pub names: Vec<String>,
+}
+
+impl UndoFilter {
+    /// Returns the names of the functions renamed in the `scopes`, if any
+    /// scope pattern is defined.
+    fn scope_names(&self, files: &BTreeMap<PathBuf, JournalFile>) -> Option<HashSet<String>> {
+        if self.scopes.is_empty() {
+            return None;
+        }
+
+        let names = files
+            .values()
+            .flat_map(|file| file.entries.iter())
+            .filter(|entry| {
+                self.scopes
+                    .iter()
+                    .any(|pattern| scope::matches(&entry.scope, pattern))
+            })
+            .flat_map(|entry| entry.names.iter().cloned())
+            .collect();
+
+        Some(names)
+    }
+
+    fn matches(
+        &self,
+        rel_path: &Path,
+        entry: &JournalEntry,
+        scope_names: Option<&HashSet<String>>,
+    ) -> bool {
+        (self.paths.is_empty() || self.paths.iter().any(|glob| glob.matches(rel_path)))
+            && scope_names
+                .is_none_or(|scope_names| entry.names.iter().any(|name| scope_names.contains(name)))
+            && (self.names.is_empty() || entry.names.iter().any(|name| self.names.contains(name)))
+    }
+}
+
+/// Journal of the edits applied to the Rust files of a tree.
+///
+/// The paths are recorded relative to the `root` of the tree.
+#[derive(Debug)]
+pub struct Journal {
+    root: PathBuf,
+    files: Mutex<BTreeMap<PathBuf, JournalFile>>,
+}
+
+impl Journal {
+    /// Builds a [`Journal`] for the files in the tree starting at `root`.
+    pub fn new(root: impl AsRef<Path>) -> Self {
+        let root = root.as_ref();
+        let root = if root.is_dir() {
+            root
+        } else {
+            root.parent().unwrap_or(root)
+        };
+
+        Journal {
+            root: absolute(root),
+            files: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Records the `edits` applied to the `source_code` of the file at `path`,
+    /// resulting in the `output`.
+    ///
+    /// Doc alias insertions are recorded with the scope & the name of the
+    /// function renamed next. Derive attribute edits are recorded with the
+    /// names of the generated getters. Other edits are recorded without
+    /// scope nor name.
+    pub fn add(&self, path: &Path, source_code: &str, edits: &[Edit], output: &str) {
+        if edits.is_empty() {
+            return;
+        }
+
+        let mut edits: Vec<&Edit> = edits.iter().collect();
+        edits.sort_by_key(|edit| edit.range.start);
+
+        let mut entries = Vec::with_capacity(edits.len());
+        let mut delta = 0isize;
+        for (idx, edit) in edits.iter().enumerate() {
+            let start = (edit.range.start as isize + delta) as usize;
+            delta += edit.text.len() as isize - edit.range.len() as isize;
+
+            let (scope, names) = match &edit.kind {
+                EditKind::Rename { scope } => (
+                    scope.to_string(),
+                    vec![source_code[edit.range.clone()].to_string()],
+                ),
+                EditKind::DeriveAttribute { scope, getters } => (
+                    scope.to_string(),
+                    getters.iter().map(|(name, _)| name.clone()).collect(),
+                ),
+                EditKind::DocAlias => edits[idx..]
+                    .iter()
+                    .find_map(|edit| match &edit.kind {
+                        EditKind::Rename { scope } => Some((
+                            scope.to_string(),
+                            vec![source_code[edit.range.clone()].to_string()],
+                        )),
+                        _ => None,
+                    })
+                    .unwrap_or_default(),
+                EditKind::Other => Default::default(),
+            };
+
+            entries.push(JournalEntry {
+                range: start..start + edit.text.len(),
+                scope,
+                names,
+                old_text: source_code[edit.range.clone()].to_string(),
+                new_text: edit.text.clone(),
+            });
+        }
+
+        let path = self.relative_path(path);
+        self.files.lock().expect("journal poisoned").insert(
+            path,
+            JournalFile {
+                hash: hash(output.as_bytes()),
+                entries,
+            },
+        );
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.lock().expect("journal poisoned").is_empty()
+    }
+
+    /// Returns the number of recorded edits.
+    pub fn len(&self) -> usize {
+        self.files
+            .lock()
+            .expect("journal poisoned")
+            .values()
+            .map(|file| file.entries.len())
+            .sum()
+    }
+
+    /// Writes the journal to the given `writer`.
+    pub fn write_to(&self, writer: &mut dyn Write) -> io::Result<()> {
+        let mut content = String::new();
+        let _ = writeln!(content, "root\t{}", escape(&self.root.to_string_lossy()));
+        for (path, file) in self.files.lock().expect("journal poisoned").iter() {
+            let _ = writeln!(
+                content,
+                "file\t{}\t{:016x}",
+                escape(&path.to_string_lossy()),
+                file.hash,
+            );
+            for entry in file.entries.iter() {
+                let _ = writeln!(
+                    content,
+                    "edit\t{}\t{}\t{}\t{}\t{}\t{}",
+                    entry.range.start,
+                    entry.range.end,
+                    escape(&entry.scope),
+                    escape(&entry.names.join(",")),
+                    escape(&entry.old_text),
+                    escape(&entry.new_text),
+                );
+            }
+        }
+
+        writer.write_all(content.as_bytes())?;
+        writer.flush()
+    }
+
+    /// Saves the journal to the file at `path`.
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let mut content = Vec::new();
+        self.write_to(&mut content)
+            .map_err(|err| Error::WriteFile(path.to_owned(), err))?;
+
+        atomic_write::write(path, &content, None)
+    }
+
+    /// Loads the journal from the file at `path`.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let content =
+            fs::read_to_string(path).map_err(|err| Error::ReadFile(path.to_owned(), err))?;
+
+        let mut root = None;
+        let mut files = BTreeMap::new();
+        let mut cur_file = None;
+        for (line_idx, line) in content.lines().enumerate() {
+            let invalid = || Error::ParseJournal(path.to_owned(), line_idx + 1);
+
+            let fields: Vec<String> = line.split('\t').map(unescape).collect();
+            match fields.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+                ["root", root_path] => root = Some(PathBuf::from(root_path)),
+                ["file", file_path, hash] => {
+                    let hash = u64::from_str_radix(hash, 16).map_err(|_| invalid())?;
+                    cur_file = Some(PathBuf::from(file_path));
+                    files.insert(
+                        PathBuf::from(file_path),
+                        JournalFile {
+                            hash,
+                            entries: Vec::new(),
+                        },
+                    );
+                }
+                ["edit", start, end, scope, names, old_text, new_text] => {
+                    let file = cur_file
+                        .as_ref()
+                        .and_then(|cur_file| files.get_mut(cur_file))
+                        .ok_or_else(invalid)?;
+                    let start = start.parse().map_err(|_| invalid())?;
+                    let end = end.parse().map_err(|_| invalid())?;
+                    file.entries.push(JournalEntry {
+                        range: start..end,
+                        scope: scope.to_string(),
+                        names: names
+                            .split(',')
+                            .filter(|name| !name.is_empty())
+                            .map(String::from)
+                            .collect(),
+                        old_text: old_text.to_string(),
+                        new_text: new_text.to_string(),
+                    });
+                }
+                [""] => (),
+                _ => return Err(invalid()),
+            }
+        }
+
+        Ok(Journal {
+            root: root.ok_or_else(|| Error::ParseJournal(path.to_owned(), 0))?,
+            files: Mutex::new(files),
+        })
+    }
+
+    /// Reverts the edits selected by the `filter`.
+    ///
+    /// The scopes of the `filter` are resolved to the names of the functions
+    /// renamed in these scopes, so that their calls are reverted too.
+    ///
+    /// The files are only modified if none of the files to revert changed
+    /// since the journal was written. The reverted edits are removed from the
+    /// journal & the remaining edits are updated accordingly.
+    ///
+    /// Returns the reverted entries by file.
+    pub fn undo(&self, filter: &UndoFilter) -> Result<Vec<(PathBuf, Vec<JournalEntry>)>, Error> {
+        let mut files = self.files.lock().expect("journal poisoned");
+        let scope_names = filter.scope_names(&files);
+        let matches = |rel_path: &Path, entry: &JournalEntry| {
+            filter.matches(rel_path, entry, scope_names.as_ref())
+        };
+
+        // Check all the files before modifying any
+        let mut to_undo = Vec::new();
+        for (rel_path, file) in files.iter() {
+            if !file.entries.iter().any(|entry| matches(rel_path, entry)) {
+                continue;
+            }
+
+            let path = self.root.join(rel_path);
+            let content =
+                fs::read_to_string(&path).map_err(|err| Error::ReadFile(path.clone(), err))?;
+            let is_unchanged = hash(content.as_bytes()) == file.hash
+                && file
+                    .entries
+                    .iter()
+                    .all(|entry| content.get(entry.range.clone()) == Some(entry.new_text.as_str()));
+            if !is_unchanged {
+                return Err(Error::FileChanged(path));
+            }
+
+            to_undo.push((rel_path.clone(), path, content));
+        }
+
+        let mut undone = Vec::new();
+        for (rel_path, path, content) in to_undo {
+            let file = files.get_mut(&rel_path).expect("checked above");
+            let (reverted, kept): (Vec<JournalEntry>, Vec<JournalEntry>) = file
+                .entries
+                .drain(..)
+                .partition(|entry| matches(&rel_path, entry));
+
+            let edits: Vec<Edit> = reverted
+                .iter()
+                .map(|entry| Edit::new(entry.range.clone(), entry.old_text.clone()))
+                .collect();
+            let output = edit::apply(&content, &edits);
+            atomic_write::write(&path, output.as_bytes(), None)?;
+
+            // Shift the remaining entries
+            file.entries = kept
+                .into_iter()
+                .map(|mut entry| {
+                    let delta: isize = reverted
+                        .iter()
+                        .filter(|reverted| reverted.range.start < entry.range.start)
+                        .map(|reverted| {
+                            reverted.old_text.len() as isize - reverted.new_text.len() as isize
+                        })
+                        .sum();
+                    let start = (entry.range.start as isize + delta) as usize;
+                    entry.range = start..start + entry.new_text.len();
+                    entry
+                })
+                .collect();
+            file.hash = hash(output.as_bytes());
+
+            undone.push((rel_path, reverted));
+        }
+
+        files.retain(|_, file| !file.entries.is_empty());
+
+        Ok(undone)
+    }
+
+    fn relative_path(&self, path: &Path) -> PathBuf {
+        let path = absolute(path);
+        match path.strip_prefix(&self.root) {
+            Ok(rel_path) => rel_path.to_owned(),
+            Err(_) => path,
+        }
+    }
+}
+
+/// Returns the absolute & canonical form of `path` if possible.
+fn absolute(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_owned())
+}
+
+/// Computes the 64 bits FNV-1a hash of the `content`.
+///
+/// Unlike the std `Hasher`s, the result is stable across Rust versions.
+fn hash(content: &[u8]) -> u64 {
+    content.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('t') => unescaped.push('\t'),
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some(c) => unescaped.push(c),
+            None => unescaped.push('\\'),
+        }
+    }
+
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_utils::temp_dir, Scope, ScopeItem};
+
+    #[test]
+    fn undo() {
+        let root = temp_dir(env!("CARGO_PKG_NAME"), "journal");
+
+        let path = root.join("lib.rs");
+        let source_code = "fn get_a() {}\nfn get_b() {}\n";
+        let edits = vec![
+            Edit::doc_alias(0, "#[doc(alias = \"get_a\")] "),
+            Edit::rename(3..8, "a", &Scope::from(ScopeItem::struct_impl("A"))),
+            Edit::rename(17..22, "b", &Scope::from(ScopeItem::struct_impl("B"))),
+        ];
+        let output = edit::apply(source_code, &edits);
+        fs::write(&path, &output).unwrap();
+
+        let journal = Journal::new(&root);
+        journal.add(&path, source_code, &edits, &output);
+        assert_eq!(journal.len(), 3);
+
+        let journal_path = root.join("journal.txt");
+        journal.save(&journal_path).unwrap();
+        let journal = Journal::load(&journal_path).unwrap();
+
+        let filter = UndoFilter {
+            scopes: vec!["A".to_string()],
+            ..UndoFilter::default()
+        };
+        let undone = journal.undo(&filter).unwrap();
+        assert_eq!(undone[0].1.len(), 2);
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "fn get_a() {}\nfn b() {}\n"
+        );
+
+        // Modified file
+        fs::write(&path, "fn get_a() {}\nfn b() {}\n// changed\n").unwrap();
+        match journal.undo(&UndoFilter::default()) {
+            Err(Error::FileChanged(_)) => (),
+            other => panic!("unexpected {:?}", other),
+        }
+
+        fs::write(&path, "fn get_a() {}\nfn b() {}\n").unwrap();
+        let filter = UndoFilter {
+            names: vec!["get_b".to_string()],
+            paths: vec![Glob::new("*.rs")],
+            ..UndoFilter::default()
+        };
+        journal.undo(&filter).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), source_code);
+        assert!(journal.is_empty());
+    }
+
+    #[test]
+    fn undo_scope() {
+        let root = temp_dir(env!("CARGO_PKG_NAME"), "journal_scope");
+        let journal = Journal::new(&root);
+
+        let def_path = root.join("lib.rs");
+        let def_source = "fn get_a() {}\nfn get_b() {}\n";
+        let def_edits = vec![
+            Edit::rename(3..8, "a", &Scope::from(ScopeItem::struct_impl("A"))),
+            Edit::rename(17..22, "b", &Scope::from(ScopeItem::struct_impl("B"))),
+        ];
+        let output = edit::apply(def_source, &def_edits);
+        fs::write(&def_path, &output).unwrap();
+        journal.add(&def_path, def_source, &def_edits, &output);
+
+        // The calls are recorded in the scope of the caller
+        let call_path = root.join("main.rs");
+        let call_source = "fn main() { x.get_a(); y.get_b(); }\n";
+        let caller = Scope::from(ScopeItem::Fn("main".to_string()));
+        let call_edits = vec![
+            Edit::rename(14..19, "a", &caller),
+            Edit::rename(25..30, "b", &caller),
+        ];
+        let output = edit::apply(call_source, &call_edits);
+        fs::write(&call_path, &output).unwrap();
+        journal.add(&call_path, call_source, &call_edits, &output);
+
+        let filter = UndoFilter {
+            scopes: vec!["A".to_string()],
+            ..UndoFilter::default()
+        };
+        let undone = journal.undo(&filter).unwrap();
+        assert_eq!(undone.len(), 2);
+        assert_eq!(
+            fs::read_to_string(&def_path).unwrap(),
+            "fn get_a() {}\nfn b() {}\n"
+        );
+        assert_eq!(
+            fs::read_to_string(&call_path).unwrap(),
+            "fn main() { x.get_a(); y.b(); }\n"
+        );
+        assert_eq!(journal.len(), 2);
+    }
+
+    #[test]
+    fn attribution() {
+        let root = temp_dir(env!("CARGO_PKG_NAME"), "journal_attribution");
+        let path = root.join("lib.rs");
+        let source_code = "#[getset(get, with_prefix)]\nstruct A;\n// TODO\nfn get_b() {}\n";
+        let edits = vec![
+            Edit::derive_attribute(
+                12..25,
+                "",
+                &Scope::from(ScopeItem::Struct("A".to_string())),
+                vec![
+                    ("get_x".to_string(), "x".to_string()),
+                    ("get_y".to_string(), "y".to_string()),
+                ],
+            ),
+            Edit::new(41..45, "FIXME"),
+            Edit::doc_alias(46, "#[doc(alias = \"get_b\")] "),
+            Edit::rename(49..54, "b", &Scope::from(ScopeItem::struct_impl("B"))),
+        ];
+        let output = edit::apply(source_code, &edits);
+
+        let journal = Journal::new(&root);
+        journal.add(&path, source_code, &edits, &output);
+        let journal_path = root.join("journal.txt");
+        journal.save(&journal_path).unwrap();
+        let journal = Journal::load(&journal_path).unwrap();
+
+        let files = journal.files.lock().unwrap();
+        let names: Vec<&[String]> = files
+            .values()
+            .flat_map(|file| file.entries.iter())
+            .map(|entry| entry.names.as_slice())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                &["get_x".to_string(), "get_y".to_string()][..],
+                &[],
+                &["get_b".to_string()],
+                &["get_b".to_string()],
+            ],
+        );
+    }
+}
@@ -3,13 +3,57 @@
 //! # Features
 //!
 //! - **`log`** *(enabled by default)* — Logging via the `log` crate.
+//! - **`cli`** — Command line helpers shared by the binaries, see [`cli`].
+//! - **`test-utils`** — Helpers for the tests of the `fix-getters` crates.
+
+pub mod diagnostic;
+pub use diagnostic::Diagnostic;
 
 pub mod error;
 pub use error::Error;
+pub use error::Errors;
 pub use error::ParseFileError;
 
+pub mod atomic_write;
+
 pub mod crate_traverser;
-pub use crate_traverser::CrateTraverser;
+pub use crate_traverser::{Action, CrateTraverser, FixedFile, TraversalMode, TraversalSettings};
+
+pub mod markdown;
+
+pub mod manifest;
+pub use manifest::Manifest;
+
+pub mod module_graph;
+
+pub mod edit;
+pub use edit::{Edit, EditKind};
+
+pub mod patch;
+pub use patch::Patch;
+
+pub mod check;
+pub use check::Check;
+
+pub mod journal;
+pub use journal::{Journal, UndoFilter};
+
+pub mod report;
+pub use report::{Candidate, Report};
+
+pub mod statistics;
+pub use statistics::Statistics;
+
+pub mod mention;
+pub use mention::Mention;
+
+pub mod paste;
+
+pub mod opt_out;
+pub use opt_out::OptOut;
+
+pub mod review;
+pub use review::{Decisions, Review};
 
 pub mod getter;
 pub use getter::{Getter, GetterError, NonGetterReason};
@@ -17,15 +61,24 @@ pub use getter::{Getter, GetterError, NonGetterReason};
 pub mod identification_mode;
 pub use identification_mode::IdentificationMode;
 
+#[cfg(feature = "log")]
+pub mod logger;
+
+#[cfg(feature = "cli")]
+pub mod cli;
+
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
+
 pub mod collectors;
 pub use collectors::*;
 
 pub mod scope;
-pub use scope::Scope;
+pub use scope::{ImplGenerics, Scope, ScopeItem};
 
 pub mod prelude {
     pub use super::{
-        CrateTraverser, GetterCollection, IdentificationMode, SyntaxTreeGetterCollector,
-        TokenStreamGetterCollector,
+        Action, CrateTraverser, GetterCollection, IdentificationMode, SyntaxTreeGetterCollector,
+        TokenStreamGetterCollector, TraversalMode, TraversalSettings,
     };
 }
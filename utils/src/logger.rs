@@ -0,0 +1,188 @@
+//! A logger wrapper which allows deferring the logs emitted by a thread.
+//!
+//! When files are processed concurrently, the logs emitted by each file
+//! would interleave. The [`Logger`] allows capturing the logs emitted
+//! while processing a file, so that they can be emitted later in a
+//! deterministic order. See [`capture`].
+
+use log::{Level, LevelFilter, Log, Metadata, Record, SetLoggerError};
+use std::cell::RefCell;
+
+thread_local! {
+    static CAPTURED: RefCell<Option<Vec<CapturedRecord>>> = const { RefCell::new(None) };
+}
+
+/// A logger wrapper which allows deferring the logs emitted by a thread.
+pub struct Logger {
+    inner: Box<dyn Log>,
+}
+
+impl Logger {
+    /// Sets a [`Logger`] wrapping `inner` as the global logger.
+    pub fn init(inner: Box<dyn Log>, max_level: LevelFilter) -> Result<(), SetLoggerError> {
+        log::set_boxed_logger(Box::new(Logger { inner }))?;
+        log::set_max_level(max_level);
+
+        Ok(())
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.inner.enabled(record.metadata()) {
+            return;
+        }
+
+        let is_captured = CAPTURED.with(|captured| {
+            if let Some(captured) = captured.borrow_mut().as_mut() {
+                captured.push(CapturedRecord::from(record));
+                true
+            } else {
+                false
+            }
+        });
+
+        if !is_captured {
+            self.inner.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// Executes `func` capturing the logs emitted by current thread.
+///
+/// The logs are only captured if the global logger is a [`Logger`].
+/// Use [`CapturedLogs::emit`] to actually log them.
+pub fn capture<R>(func: impl FnOnce() -> R) -> (R, CapturedLogs) {
+    let prev = CAPTURED.with(|captured| captured.borrow_mut().replace(Vec::new()));
+    let res = func();
+    let records = CAPTURED.with(|captured| std::mem::replace(&mut *captured.borrow_mut(), prev));
+
+    (res, CapturedLogs(records.unwrap_or_default()))
+}
+
+/// Logs captured by [`capture`].
+#[derive(Debug, Default)]
+pub struct CapturedLogs(Vec<CapturedRecord>);
+
+impl CapturedLogs {
+    /// Emits the captured logs using the global logger.
+    pub fn emit(self) {
+        for record in self.0 {
+            log::logger().log(
+                &Record::builder()
+                    .level(record.level)
+                    .target(&record.target)
+                    .module_path(record.module_path.as_deref())
+                    .file(record.file.as_deref())
+                    .line(record.line)
+                    .args(format_args!("{}", record.msg))
+                    .build(),
+            );
+        }
+    }
+}
+
+#[derive(Debug)]
+struct CapturedRecord {
+    level: Level,
+    target: String,
+    module_path: Option<String>,
+    file: Option<String>,
+    line: Option<u32>,
+    msg: String,
+}
+
+impl From<&Record<'_>> for CapturedRecord {
+    fn from(record: &Record) -> Self {
+        CapturedRecord {
+            level: record.level(),
+            target: record.target().to_string(),
+            module_path: record.module_path().map(str::to_string),
+            file: record.file().map(str::to_string),
+            line: record.line(),
+            msg: record.args().to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use log::info;
+    use std::sync::{Mutex, Once};
+
+    static RECORDED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    /// Records the messages logged from the test modules.
+    struct Recorder;
+
+    impl Log for Recorder {
+        fn enabled(&self, metadata: &Metadata) -> bool {
+            metadata.target().ends_with("::tests")
+        }
+
+        fn log(&self, record: &Record) {
+            RECORDED.lock().unwrap().push(record.args().to_string());
+        }
+
+        fn flush(&self) {}
+    }
+
+    /// Sets a [`Logger`] recording the messages logged from the test modules
+    /// as the global logger.
+    pub fn init() {
+        static INIT: Once = Once::new();
+        INIT.call_once(|| Logger::init(Box::new(Recorder), LevelFilter::Trace).unwrap());
+    }
+
+    /// Returns the recorded messages starting with `prefix`.
+    ///
+    /// Tests run concurrently: use a `prefix` specific to the test.
+    pub fn recorded(prefix: &str) -> Vec<String> {
+        RECORDED
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|msg| msg.starts_with(prefix))
+            .cloned()
+            .collect()
+    }
+
+    #[test]
+    fn capture() {
+        init();
+
+        let (res, logs) = super::capture(|| {
+            info!("capture: first");
+            42
+        });
+        assert_eq!(res, 42);
+        info!("capture: direct");
+        assert_eq!(recorded("capture:"), ["capture: direct"]);
+
+        logs.emit();
+        assert_eq!(recorded("capture:"), ["capture: direct", "capture: first"]);
+
+        // Nested captures
+        let ((), outer) = super::capture(|| {
+            let ((), inner) = super::capture(|| info!("capture: inner"));
+            info!("capture: outer");
+            inner.emit();
+        });
+        assert_eq!(recorded("capture:").len(), 2);
+
+        outer.emit();
+        assert_eq!(
+            recorded("capture:")[2..],
+            ["capture: outer", "capture: inner"],
+        );
+    }
+}
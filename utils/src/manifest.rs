@@ -0,0 +1,393 @@
+//! `Cargo.toml` manifest reader.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::Error;
+
+/// The parts of a `Cargo.toml` file which are relevant to `fix-getters`.
+///
+/// Only the simple `key = "value"`, `key = bool` and `key = ["value", ...]`
+/// forms are supported, which is enough to get the package name, the targets,
+/// the build script, the workspace members and the `fix-getters` config.
+#[derive(Debug, Default)]
+pub struct Manifest {
+    /// Whether the manifest has a `[package]` section.
+    pub is_package: bool,
+    pub name: Option<String>,
+    pub version: Option<String>,
+    /// The targets and build script declared in the manifest.
+    pub targets: Vec<Target>,
+    /// The kinds of targets which `cargo` must not discover automatically,
+    /// e.g. due to `autobins = false` or `build = false`.
+    pub no_auto: Vec<TargetKind>,
+    /// The `[workspace]` section, if any.
+    pub workspace: Option<WorkspaceSection>,
+    /// The `[package.metadata.fix-getters]` & `[workspace.metadata.fix-getters]`
+    /// entries.
+    pub config: Config,
+}
+
+/// The kind of a [`Target`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TargetKind {
+    Lib,
+    Bin,
+    Example,
+    Test,
+    Bench,
+    BuildScript,
+}
+
+impl TargetKind {
+    fn from_table(table: &str) -> Option<Self> {
+        use TargetKind::*;
+
+        match table {
+            "lib" => Some(Lib),
+            "bin" => Some(Bin),
+            "example" => Some(Example),
+            "test" => Some(Test),
+            "bench" => Some(Bench),
+            _ => None,
+        }
+    }
+
+    fn from_auto_key(key: &str) -> Option<Self> {
+        use TargetKind::*;
+
+        match key {
+            "autobins" => Some(Bin),
+            "autoexamples" => Some(Example),
+            "autotests" => Some(Test),
+            "autobenches" => Some(Bench),
+            "build" => Some(BuildScript),
+            _ => None,
+        }
+    }
+}
+
+/// A target declared in a [`Manifest`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Target {
+    pub kind: TargetKind,
+    pub name: Option<String>,
+    /// The path of the target, if not the conventional one.
+    pub path: Option<PathBuf>,
+}
+
+impl Target {
+    fn new(kind: TargetKind) -> Self {
+        Target {
+            kind,
+            name: None,
+            path: None,
+        }
+    }
+}
+
+/// The `[workspace]` section of a [`Manifest`].
+#[derive(Debug, Default)]
+pub struct WorkspaceSection {
+    /// The members patterns, relative to the workspace root.
+    pub members: Vec<String>,
+    /// The excluded paths, relative to the workspace root.
+    pub exclude: Vec<String>,
+}
+
+/// The `fix-getters` entries of a [`Manifest`].
+///
+/// E.g.:
+///
+/// ```toml
+/// [workspace.metadata.fix-getters]
+/// include = ["crates/gui/**"]
+/// exclude = ["**/generated/**"]
+/// ```
+#[derive(Debug, Default)]
+pub struct Config {
+    /// Glob patterns of the files to process, relative to the manifest directory.
+    pub include: Vec<String>,
+    /// Glob patterns of the entries to skip, relative to the manifest directory.
+    pub exclude: Vec<String>,
+}
+
+impl Config {
+    const TABLES: [&'static str; 2] = [
+        "package.metadata.fix-getters",
+        "workspace.metadata.fix-getters",
+    ];
+
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+}
+
+impl Manifest {
+    /// Reads the `Cargo.toml` file at `path`.
+    pub fn read(path: &Path) -> Result<Self, Error> {
+        let content =
+            fs::read_to_string(path).map_err(|err| Error::ReadFile(path.to_owned(), err))?;
+
+        Ok(Manifest::parse(&content))
+    }
+
+    /// Parses the content of a `Cargo.toml` file.
+    pub fn parse(content: &str) -> Self {
+        let mut manifest = Manifest::default();
+        let mut table = String::new();
+        let mut lines = content.lines();
+        while let Some(line) = lines.next() {
+            let line = strip_comment(line).trim();
+            if line.starts_with('[') {
+                table = line
+                    .trim_matches(|c| c == '[' || c == ']')
+                    .trim()
+                    .to_string();
+                match table.as_str() {
+                    "package" => manifest.is_package = true,
+                    "workspace" => manifest.workspace = Some(WorkspaceSection::default()),
+                    other => {
+                        if let Some(kind) = TargetKind::from_table(other) {
+                            manifest.targets.push(Target::new(kind));
+                        }
+                    }
+                }
+                continue;
+            }
+
+            let mut splits = line.splitn(2, '=');
+            let (key, value) = match (splits.next(), splits.next()) {
+                (Some(key), Some(value)) => (key.trim(), value.trim()),
+                _ => continue,
+            };
+
+            if value.starts_with('[') {
+                // Arrays can span multiple lines
+                let mut array = value.to_string();
+                while !array.contains(']') {
+                    match lines.next() {
+                        Some(line) => array.push_str(strip_comment(line)),
+                        None => break,
+                    }
+                }
+
+                let values = array
+                    .trim_matches(|c| c == '[' || c == ']')
+                    .split(',')
+                    .filter_map(|value| unquote(value.trim()))
+                    .map(String::from);
+                match (table.as_str(), key, manifest.workspace.as_mut()) {
+                    ("workspace", "members", Some(workspace)) => workspace.members.extend(values),
+                    ("workspace", "exclude", Some(workspace)) => workspace.exclude.extend(values),
+                    (table, "include", _) if Config::TABLES.contains(&table) => {
+                        manifest.config.include.extend(values)
+                    }
+                    (table, "exclude", _) if Config::TABLES.contains(&table) => {
+                        manifest.config.exclude.extend(values)
+                    }
+                    _ => (),
+                }
+                continue;
+            }
+
+            if table == "package" && value == "false" {
+                if let Some(kind) = TargetKind::from_auto_key(key) {
+                    manifest.no_auto.push(kind);
+                }
+                continue;
+            }
+
+            let value = match unquote(value) {
+                Some(value) => value,
+                None => continue,
+            };
+
+            match (table.as_str(), key) {
+                ("package", "name") => manifest.name = Some(value.to_string()),
+                ("package", "version") => manifest.version = Some(value.to_string()),
+                ("package", "build") => manifest.targets.push(Target {
+                    path: Some(PathBuf::from(value)),
+                    ..Target::new(TargetKind::BuildScript)
+                }),
+                (table, "name") | (table, "path") => {
+                    let target = match manifest.targets.last_mut() {
+                        Some(target) if TargetKind::from_table(table) == Some(target.kind) => {
+                            target
+                        }
+                        _ => continue,
+                    };
+                    if key == "name" {
+                        target.name = Some(value.to_string());
+                    } else {
+                        target.path = Some(PathBuf::from(value));
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        manifest
+    }
+
+    /// Returns the paths of the targets of the given `kind` declared in the manifest.
+    pub fn target_paths(&self, kind: TargetKind) -> impl Iterator<Item = &Path> {
+        self.targets
+            .iter()
+            .filter(move |target| target.kind == kind)
+            .filter_map(|target| target.path.as_deref())
+    }
+
+    /// Checks whether `cargo` looks for targets of the given `kind`
+    /// at their conventional location.
+    ///
+    /// This is not the case if the automatic discovery is disabled
+    /// or if the manifest declares the path of the library or build script.
+    pub fn is_auto_discovered(&self, kind: TargetKind) -> bool {
+        match kind {
+            TargetKind::Lib | TargetKind::BuildScript
+                if self.target_paths(kind).next().is_some() =>
+            {
+                false
+            }
+            _ => !self.no_auto.contains(&kind),
+        }
+    }
+
+    /// Checks whether the manifest declares the path of the binary target
+    /// named after the package, which `cargo` otherwise finds in `src/main.rs`.
+    pub fn has_main_bin_path(&self) -> bool {
+        self.targets.iter().any(|target| {
+            target.kind == TargetKind::Bin && target.path.is_some() && target.name == self.name
+        })
+    }
+}
+
+/// Strips the comment from the `line`, ignoring `#` in quoted strings.
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut is_escaped = false;
+    for (idx, c) in line.char_indices() {
+        match quote {
+            Some(_) if is_escaped => is_escaped = false,
+            Some('"') if c == '\\' => is_escaped = true,
+            Some(q) if c == q => quote = None,
+            Some(_) => (),
+            None => match c {
+                '"' | '\'' => quote = Some(c),
+                '#' => return &line[..idx],
+                _ => (),
+            },
+        }
+    }
+
+    line
+}
+
+fn unquote(value: &str) -> Option<&str> {
+    ['"', '\''].iter().find_map(|quote| {
+        value
+            .strip_prefix(*quote)
+            .and_then(|value| value.strip_suffix(*quote))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let manifest = Manifest::parse(
+            r#"
+[package]
+name = "foo" # the name
+version = "0.1.0"
+build = "build/main.rs"
+autotests = false
+
+[lib]
+path = "src/#gen/lib.rs"
+
+[[bin]]
+name = "tool#1"
+path = "tool/main.rs"
+
+[[bin]]
+name = "foo"
+
+[package.metadata.fix-getters]
+exclude = ["**/generated/**"]
+
+[workspace]
+members = [
+    "crates/*", # all the crates
+    "tools",
+]
+exclude = ["crates/legacy"]
+
+[workspace.metadata.fix-getters]
+include = [
+    "crates/gui/**",
+]
+"#,
+        );
+
+        assert!(manifest.is_package);
+        assert_eq!(manifest.name.as_deref(), Some("foo"));
+        assert_eq!(manifest.version.as_deref(), Some("0.1.0"));
+        assert_eq!(
+            manifest
+                .target_paths(TargetKind::BuildScript)
+                .collect::<Vec<_>>(),
+            [Path::new("build/main.rs")],
+        );
+        assert_eq!(
+            manifest.target_paths(TargetKind::Lib).collect::<Vec<_>>(),
+            [Path::new("src/#gen/lib.rs")],
+        );
+        assert_eq!(
+            manifest.targets[2],
+            Target {
+                kind: TargetKind::Bin,
+                name: Some("tool#1".to_string()),
+                path: Some(PathBuf::from("tool/main.rs")),
+            },
+        );
+        assert_eq!(manifest.targets[3].name.as_deref(), Some("foo"));
+        assert_eq!(manifest.targets[3].path, None);
+        assert!(!manifest.has_main_bin_path());
+
+        assert!(!manifest.is_auto_discovered(TargetKind::Lib));
+        assert!(!manifest.is_auto_discovered(TargetKind::BuildScript));
+        assert!(!manifest.is_auto_discovered(TargetKind::Test));
+        assert!(manifest.is_auto_discovered(TargetKind::Bin));
+
+        let workspace = manifest.workspace.unwrap();
+        assert_eq!(workspace.members, ["crates/*", "tools"]);
+        assert_eq!(workspace.exclude, ["crates/legacy"]);
+
+        assert_eq!(manifest.config.include, ["crates/gui/**"]);
+        assert_eq!(manifest.config.exclude, ["**/generated/**"]);
+
+        let manifest = Manifest::parse(
+            "[package]\nname = 'bar' # 'quoted'\nbuild = false\n\n\
+             [[bin]]\nname = \"bar\"\npath = \"main.rs\"\n",
+        );
+        assert_eq!(manifest.name.as_deref(), Some("bar"));
+        assert!(manifest.has_main_bin_path());
+        assert!(!manifest.is_auto_discovered(TargetKind::BuildScript));
+        assert!(manifest.is_auto_discovered(TargetKind::Lib));
+    }
+
+    #[test]
+    fn comments() {
+        assert_eq!(strip_comment("a = 1 # comment"), "a = 1 ");
+        assert_eq!(strip_comment(r##"a = "#1" # c"##), r##"a = "#1" "##);
+        assert_eq!(strip_comment(r##"a = "\"#" # c"##), r##"a = "\"#" "##);
+        assert_eq!(strip_comment("a = '#1' # c"), "a = '#1' ");
+    }
+}
@@ -0,0 +1,145 @@
+//! Markdown files holding Rust code blocks.
+//!
+//! The Rust code blocks of a Markdown file are processed like the code
+//! blocks of the documentation in a Rust file. Markdown files are selected
+//! using globs or found in the documentation attributes of the Rust files,
+//! e.g. `#![doc = include_str!("../README.md")]`.
+
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use rules::dir_entry::{Glob, NOT_MIRRORED};
+
+use crate::Error;
+
+const INCLUDE_STR: &str = "include_str!(";
+
+/// Checks whether the file at `path` is a Markdown file.
+pub fn is_markdown(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("md"))
+}
+
+/// Returns the paths of the Markdown files included in the documentation
+/// attributes of the Rust `source_code`.
+///
+/// The paths are relative to the directory of the Rust file, as with
+/// `include_str!`. Both `#[doc = include_str!(...)]` and
+/// `#[cfg_attr(..., doc = include_str!(...))]` forms are supported, as long
+/// as the attribute is on a single line.
+pub fn doc_includes(source_code: &str) -> Vec<PathBuf> {
+    let mut includes = Vec::new();
+    for line in source_code.lines() {
+        let line = line.trim_start();
+        if !line.starts_with('#') {
+            continue;
+        }
+
+        let include = line.match_indices("doc").find_map(|(pos, _)| {
+            line[pos + "doc".len()..]
+                .trim_start()
+                .strip_prefix('=')?
+                .trim_start()
+                .strip_prefix(INCLUDE_STR)?
+                .trim_start()
+                .strip_prefix('"')?
+                .split_once('"')
+                .map(|(include, _)| Path::new(include))
+        });
+        let include = match include {
+            Some(include) => include,
+            None => continue,
+        };
+
+        if is_markdown(include) {
+            includes.push(include.to_owned());
+        }
+    }
+
+    includes
+}
+
+/// Collects the Markdown files located under `root` which match one of the `globs`.
+///
+/// The directories listed in [`NOT_MIRRORED`] & the `skipped` directory are
+/// not traversed. The paths are added to `md_files`.
+pub fn collect(
+    root: &Path,
+    globs: &[Glob],
+    skipped: Option<&Path>,
+    md_files: &mut BTreeSet<PathBuf>,
+) -> Result<(), Error> {
+    if globs.is_empty() {
+        return Ok(());
+    }
+
+    collect_(root, Path::new(""), globs, skipped, md_files)
+}
+
+fn collect_(
+    root: &Path,
+    rel_path: &Path,
+    globs: &[Glob],
+    skipped: Option<&Path>,
+    md_files: &mut BTreeSet<PathBuf>,
+) -> Result<(), Error> {
+    let path = root.join(rel_path);
+    for entry in fs::read_dir(&path).map_err(|err| Error::ReadDir(path.to_owned(), err))? {
+        let entry = entry.map_err(|err| Error::ReadEntry(path.to_owned(), err))?;
+        let file_type = entry
+            .file_type()
+            .map_err(|err| Error::ReadEntry(entry.path(), err))?;
+        let rel_path = rel_path.join(entry.file_name());
+
+        if file_type.is_dir() {
+            let is_skipped = entry
+                .file_name()
+                .to_str()
+                .is_none_or(|name| NOT_MIRRORED.contains(name))
+                || skipped.is_some_and(|skipped| {
+                    entry
+                        .path()
+                        .canonicalize()
+                        .is_ok_and(|path| path == skipped)
+                });
+            if !is_skipped {
+                collect_(root, &rel_path, globs, skipped, md_files)?;
+            }
+        } else if file_type.is_file()
+            && is_markdown(&rel_path)
+            && globs.iter().any(|glob| glob.matches(&rel_path))
+        {
+            md_files.insert(root.join(rel_path));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn includes() {
+        let source_code = r#"#![doc = include_str!("../README.md")]
+#![cfg_attr(docsrs, doc = include_str!("../docs/guide.md"))]
+//! Not an include_str!("a.md")
+#[doc = include_str!("snippet.rs")]
+#[doc = "include_str!(\"b.md\")"]
+#[path = "include_str!(\"c.md\")"]
+mod widget;
+"#;
+
+        assert_eq!(
+            doc_includes(source_code),
+            vec![
+                PathBuf::from("../README.md"),
+                PathBuf::from("../docs/guide.md"),
+            ],
+        );
+    }
+}
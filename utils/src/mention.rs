@@ -0,0 +1,362 @@
+//! Getters mentioned in comments & documentation prose.
+//!
+//! A mention is an identifier starting with `get_` which is either followed
+//! by an opening parenthesis, e.g. `// see get_state() for details`, or
+//! enclosed in backticks, e.g. ``/// Returns [`get_state`].``.
+//!
+//! The code blocks of the documentation are skipped: they are handled by the
+//! [`DocCodeGetterCollector`](crate::DocCodeGetterCollector).
+
+use std::ops::Range;
+
+use crate::collectors::doc_code::Fence;
+
+const GET_PREFIX: &str = "get_";
+
+/// A getter mentioned in a comment or in documentation prose.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mention {
+    pub name: String,
+    /// Byte range of the name in the source code.
+    pub range: Range<usize>,
+}
+
+/// Finds the getters mentioned in the comments of the Rust `source_code`.
+pub fn find(source_code: &str) -> Vec<Mention> {
+    let mut finder = Finder::default();
+    for comment in comments(source_code) {
+        finder.have_comment(source_code, &comment);
+    }
+
+    finder.mentions
+}
+
+/// Finds the getters mentioned in the prose of the Markdown `text`.
+pub fn find_in_markdown(text: &str) -> Vec<Mention> {
+    let mut finder = Finder::default();
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        finder.have_prose_line(line, offset, false);
+        offset += line.len();
+    }
+
+    finder.mentions
+}
+
+/// The kind of a comment.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum CommentKind {
+    Regular,
+    OuterDoc,
+    InnerDoc,
+}
+
+/// A comment in a Rust source code.
+#[derive(Debug)]
+pub(crate) struct Comment {
+    /// Byte range of the text of the comment, without the delimiters.
+    pub(crate) text: Range<usize>,
+    pub(crate) kind: CommentKind,
+    pub(crate) is_block: bool,
+    /// Whether this comment continues the line comments on the previous line.
+    is_continued: bool,
+}
+
+#[derive(Debug, Default)]
+struct Finder {
+    fence: Option<Fence>,
+    mentions: Vec<Mention>,
+}
+
+impl Finder {
+    fn have_comment(&mut self, source_code: &str, comment: &Comment) {
+        if !comment.is_continued {
+            self.fence = None;
+        }
+
+        let mut offset = comment.text.start;
+        for line in source_code[comment.text.clone()].split_inclusive('\n') {
+            self.have_prose_line(line, offset, comment.is_block);
+            offset += line.len();
+        }
+
+        if comment.is_block {
+            self.fence = None;
+        }
+    }
+
+    /// Processes the prose `line` located at `offset` in the source code.
+    fn have_prose_line(&mut self, line: &str, offset: usize, is_block: bool) {
+        let mut trimmed = line.trim();
+        if is_block {
+            trimmed = trimmed.strip_prefix('*').unwrap_or(trimmed).trim_start();
+        }
+
+        if let Some(fence) = self.fence.as_ref() {
+            if fence.is_closed_by(trimmed) {
+                self.fence = None;
+            }
+            return;
+        }
+        if let Some(fence) = Fence::parse(trimmed) {
+            self.fence = Some(fence);
+            return;
+        }
+
+        for (pos, _) in line.match_indices(GET_PREFIX) {
+            let before = &line[..pos];
+            if before.chars().next_back().is_some_and(is_ident_char) {
+                continue;
+            }
+
+            let len = line[pos..]
+                .find(|c: char| !is_ident_char(c))
+                .unwrap_or(line.len() - pos);
+            let after = &line[pos + len..];
+
+            let is_call = after.starts_with('(');
+            // Also accept paths, e.g. `Widget::get_state`
+            let is_code = after.starts_with('`')
+                && before
+                    .trim_end_matches(|c: char| is_ident_char(c) || c == ':')
+                    .ends_with('`');
+
+            if (is_call || is_code) && len > GET_PREFIX.len() {
+                self.mentions.push(Mention {
+                    name: line[pos..pos + len].to_string(),
+                    range: offset + pos..offset + pos + len,
+                });
+            }
+        }
+    }
+}
+
+fn is_ident_char(c: char) -> bool {
+    c == '_' || c.is_alphanumeric()
+}
+
+/// Returns the comments in the Rust `source_code`.
+///
+/// String & char literals are skipped so that `"//"` is not mistaken for a comment.
+pub(crate) fn comments(source_code: &str) -> Vec<Comment> {
+    let bytes = source_code.as_bytes();
+    let mut comments: Vec<Comment> = Vec::new();
+    let mut last_line_comment_end = None;
+
+    let mut idx = 0;
+    while idx < bytes.len() {
+        match bytes[idx] {
+            b'/' if bytes.get(idx + 1) == Some(&b'/') => {
+                let end = source_code[idx..]
+                    .find('\n')
+                    .map_or(source_code.len(), |len| idx + len);
+                let (kind, prefix_len) = match bytes.get(idx + 2) {
+                    Some(b'/') if bytes.get(idx + 3) != Some(&b'/') => (CommentKind::OuterDoc, 3),
+                    Some(b'!') => (CommentKind::InnerDoc, 3),
+                    _ => (CommentKind::Regular, 2),
+                };
+
+                let is_continued = last_line_comment_end.is_some_and(|(last_kind, last_end)| {
+                    let between = &source_code[last_end..idx];
+                    last_kind == kind
+                        && between.trim().is_empty()
+                        && between.matches('\n').count() == 1
+                });
+                comments.push(Comment {
+                    text: idx + prefix_len..end,
+                    kind,
+                    is_block: false,
+                    is_continued,
+                });
+
+                last_line_comment_end = Some((kind, end));
+                idx = end;
+            }
+            b'/' if bytes.get(idx + 1) == Some(&b'*') => {
+                let end = block_comment_end(bytes, idx);
+                let text_end = end.saturating_sub(2).max(idx + 2);
+                let (kind, prefix_len) = match bytes.get(idx + 2) {
+                    Some(b'*') if !matches!(bytes.get(idx + 3), Some(b'*') | Some(b'/')) => {
+                        (CommentKind::OuterDoc, 3)
+                    }
+                    Some(b'!') => (CommentKind::InnerDoc, 3),
+                    _ => (CommentKind::Regular, 2),
+                };
+
+                comments.push(Comment {
+                    text: (idx + prefix_len).min(text_end)..text_end,
+                    kind,
+                    is_block: true,
+                    is_continued: false,
+                });
+
+                last_line_comment_end = None;
+                idx = end;
+            }
+            b'"' => idx = string_end(bytes, idx + 1),
+            b'r' if is_raw_string_prefix(bytes, idx) => {
+                idx = raw_string_end(bytes, idx + 1).unwrap_or(idx + 1);
+            }
+            b'\'' => idx = char_end(source_code, idx),
+            _ => idx += 1,
+        }
+    }
+
+    comments
+}
+
+fn is_ident_byte(byte: u8) -> bool {
+    byte == b'_' || byte.is_ascii_alphanumeric()
+}
+
+/// Checks whether the `r` at `idx` can start a raw string, e.g. `r"` or `br#"`.
+fn is_raw_string_prefix(bytes: &[u8], idx: usize) -> bool {
+    let is_ident_start = |idx: usize| idx == 0 || !is_ident_byte(bytes[idx - 1]);
+
+    is_ident_start(idx) || (bytes[idx - 1] == b'b' && is_ident_start(idx - 1))
+}
+
+/// Returns the index following the block comment starting at `start`.
+///
+/// Block comments can be nested.
+fn block_comment_end(bytes: &[u8], start: usize) -> usize {
+    let mut depth = 0;
+    let mut idx = start;
+    while idx + 1 < bytes.len() {
+        match (bytes[idx], bytes[idx + 1]) {
+            (b'/', b'*') => {
+                depth += 1;
+                idx += 2;
+            }
+            (b'*', b'/') => {
+                depth -= 1;
+                idx += 2;
+                if depth == 0 {
+                    return idx;
+                }
+            }
+            _ => idx += 1,
+        }
+    }
+
+    bytes.len()
+}
+
+/// Returns the index following the string which content starts at `start`.
+fn string_end(bytes: &[u8], start: usize) -> usize {
+    let mut idx = start;
+    while idx < bytes.len() {
+        match bytes[idx] {
+            b'\\' => idx += 2,
+            b'"' => return idx + 1,
+            _ => idx += 1,
+        }
+    }
+
+    bytes.len()
+}
+
+/// Returns the index following the raw string which `#`s start at `start`, if any.
+fn raw_string_end(bytes: &[u8], start: usize) -> Option<usize> {
+    let hashes = bytes[start..]
+        .iter()
+        .take_while(|byte| **byte == b'#')
+        .count();
+    if bytes.get(start + hashes) != Some(&b'"') {
+        return None;
+    }
+
+    let mut idx = start + hashes + 1;
+    while idx < bytes.len() {
+        if bytes[idx] == b'"'
+            && bytes[idx + 1..]
+                .iter()
+                .take(hashes)
+                .filter(|byte| **byte == b'#')
+                .count()
+                == hashes
+        {
+            return Some(idx + 1 + hashes);
+        }
+        idx += 1;
+    }
+
+    Some(bytes.len())
+}
+
+/// Returns the index following the char literal or lifetime starting at `start`.
+fn char_end(source_code: &str, start: usize) -> usize {
+    let rest = &source_code[start + 1..];
+    if rest.starts_with('\\') {
+        return rest[2.min(rest.len())..]
+            .find('\'')
+            .map_or(source_code.len(), |len| start + 1 + 2 + len + 1);
+    }
+
+    match rest.chars().next() {
+        Some(char_) if rest[char_.len_utf8()..].starts_with('\'') => {
+            start + 1 + char_.len_utf8() + 1
+        }
+        // A lifetime
+        _ => start + 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(source_code: &str, mentions: &[Mention]) -> Vec<String> {
+        mentions
+            .iter()
+            .map(|mention| {
+                assert_eq!(source_code[mention.range.clone()], mention.name);
+                mention.name.clone()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn comments() {
+        let source_code = r##"//! Use [`Widget::get_name`] or `get_size`.
+/// Equivalent to calling get_position() twice.
+///
+/// ```
+/// let _ = widget.get_code();
+/// ```
+/// Not a mention: get_state or `get_`.
+fn get_position<'a>(a: &'a str) -> char {
+    // see get_state() for details
+    let _ = "// get_string()";
+    let _ = r#"/* get_raw() */"#;
+    let _ = '"'; // get_char()
+    /* get_block(), /* get_nested() */
+     * `get_block_end` */
+    'a'
+}
+"##;
+
+        assert_eq!(
+            names(source_code, &find(source_code)),
+            vec![
+                "get_name",
+                "get_size",
+                "get_position",
+                "get_state",
+                "get_char",
+                "get_block",
+                "get_nested",
+                "get_block_end",
+            ],
+        );
+    }
+
+    #[test]
+    fn markdown() {
+        let text = "# Title\n\nCall `get_name()` or get_size().\n\n```\nget_code()\n```\n";
+        assert_eq!(
+            names(text, &find_in_markdown(text)),
+            vec!["get_name", "get_size"]
+        );
+    }
+}
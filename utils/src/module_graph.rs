@@ -0,0 +1,494 @@
+//! Module graph based Rust files discovery.
+//!
+//! Instead of walking the directories, the module graph starts from the
+//! crate roots and follows the `mod` items, `#[path]` attributes and
+//! `include!` macros, so that only the files actually compiled are processed.
+//!
+//! `#[cfg]` attributes are ignored and the `path`s from `#[cfg_attr]`
+//! attributes are all followed, so that every configuration is covered.
+
+#[cfg(feature = "log")]
+use log::{debug, warn};
+use rules::dir_entry::{CheckOk, DirEntryFilter};
+use std::{
+    collections::{HashSet, VecDeque},
+    fs,
+    path::{Component, Path, PathBuf},
+};
+use syn::visit::{self, Visit};
+
+use crate::{
+    manifest::{Manifest, TargetKind},
+    Error,
+};
+
+/// Collects the Rust files of the crates found from `path`.
+///
+/// The directories are traversed according to `filter` in order to find
+/// the packages, i.e. the directories containing a `Cargo.toml` file
+/// with a `[package]` section. The Rust files are then collected from
+/// the crate roots following the module graph and only those accepted
+/// by [`DirEntryFilter::accepts_file`] are returned.
+///
+/// Files are returned in discovery order.
+pub fn rust_files(path: &Path, filter: &dyn DirEntryFilter) -> Result<Vec<PathBuf>, Error> {
+    let mut packages = Vec::new();
+    find_packages(path, filter, &mut packages)?;
+
+    let mut graph = ModuleGraph::default();
+    for package in packages.iter() {
+        #[cfg(feature = "log")]
+        debug!("following modules for package {:?}", package);
+
+        for root in crate_roots(package)? {
+            graph.push(root, None);
+        }
+    }
+
+    graph.follow()?;
+
+    Ok(graph
+        .files
+        .into_iter()
+        .filter(|path| filter.accepts_file(path))
+        .collect())
+}
+
+fn find_packages(
+    path: &Path,
+    filter: &dyn DirEntryFilter,
+    packages: &mut Vec<PathBuf>,
+) -> Result<(), Error> {
+    let manifest_path = path.join("Cargo.toml");
+    if manifest_path.is_file() {
+        let manifest = Manifest::read(&manifest_path)?;
+        if manifest.is_package {
+            packages.push(path.to_owned());
+        }
+    }
+
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(path).map_err(|err| Error::ReadDir(path.to_owned(), err))? {
+        entries.push(entry.map_err(|err| Error::ReadEntry(path.to_owned(), err))?);
+    }
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        if let CheckOk::Directory = filter.check(&entry)? {
+            find_packages(&entry.path(), filter, packages)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the crate roots for the package at `package_path`.
+///
+/// This includes the targets declared in the `Cargo.toml` file
+/// and the targets which `cargo` discovers automatically, unless
+/// the manifest overrides them.
+fn crate_roots(package_path: &Path) -> Result<Vec<PathBuf>, Error> {
+    let manifest = Manifest::read(&package_path.join("Cargo.toml"))?;
+
+    let mut roots: Vec<PathBuf> = manifest
+        .targets
+        .iter()
+        .filter_map(|target| target.path.as_ref())
+        .map(|path| package_path.join(path))
+        .collect();
+
+    if manifest.is_auto_discovered(TargetKind::BuildScript) {
+        roots.push(package_path.join("build.rs"));
+    }
+    if manifest.is_auto_discovered(TargetKind::Lib) {
+        roots.push(package_path.join("src").join("lib.rs"));
+    }
+    if manifest.is_auto_discovered(TargetKind::Bin) && !manifest.has_main_bin_path() {
+        roots.push(package_path.join("src").join("main.rs"));
+    }
+
+    for (kind, dir) in [
+        (TargetKind::Bin, package_path.join("src").join("bin")),
+        (TargetKind::Example, package_path.join("examples")),
+        (TargetKind::Test, package_path.join("tests")),
+        (TargetKind::Bench, package_path.join("benches")),
+    ]
+    .iter()
+    {
+        if !manifest.is_auto_discovered(*kind) {
+            continue;
+        }
+
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+
+        let mut paths = Vec::new();
+        for entry in entries {
+            let path = entry
+                .map_err(|err| Error::ReadEntry(dir.to_owned(), err))?
+                .path();
+            if path.is_dir() {
+                paths.push(path.join("main.rs"));
+            } else if path.extension().is_some_and(|ext| ext == "rs") {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+        roots.extend(paths);
+    }
+
+    Ok(roots.into_iter().filter(|path| path.is_file()).collect())
+}
+
+#[derive(Debug, Default)]
+struct ModuleGraph {
+    files: Vec<PathBuf>,
+    visited: HashSet<PathBuf>,
+    queue: VecDeque<(PathBuf, PathBuf)>,
+}
+
+impl ModuleGraph {
+    /// Pushes a file to the graph unless already known.
+    ///
+    /// `mod_dir` is the directory where the child modules are to be searched.
+    /// It defaults to the directory of the file, which is the rule for crate
+    /// roots, `mod.rs` files and files loaded with a `#[path]` attribute.
+    fn push(&mut self, path: PathBuf, mod_dir: Option<PathBuf>) {
+        let path = normalize(&path);
+        if !self.visited.insert(path.clone()) {
+            return;
+        }
+
+        let mod_dir = mod_dir.unwrap_or_else(|| parent_dir(&path));
+        self.files.push(path.clone());
+        self.queue.push_back((path, mod_dir));
+    }
+
+    fn follow(&mut self) -> Result<(), Error> {
+        while let Some((path, mod_dir)) = self.queue.pop_front() {
+            let source_code =
+                fs::read_to_string(&path).map_err(|err| Error::ReadFile(path.clone(), err))?;
+            let syntax_tree = match syn::parse_file(&source_code) {
+                Ok(syntax_tree) => syntax_tree,
+                Err(_) => {
+                    // The error will be reported when processing the file.
+                    continue;
+                }
+            };
+
+            let mut visitor = ModVisitor {
+                file_dir: parent_dir(&path),
+                mod_dir,
+                inline_depth: 0,
+                modules: Vec::new(),
+                included: Vec::new(),
+            };
+            visitor.visit_file(&syntax_tree);
+
+            for (mod_path, mod_dir) in visitor.modules {
+                self.push(mod_path, mod_dir);
+            }
+
+            for included in visitor.included {
+                let is_module =
+                    fs::read_to_string(&included).is_ok_and(|code| syn::parse_file(&code).is_ok());
+                if is_module {
+                    self.push(included, None);
+                } else {
+                    #[cfg(feature = "log")]
+                    warn!("skipping included file {:?}: not a module", included);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Visits a file collecting the out-of-line modules and the included files.
+struct ModVisitor {
+    file_dir: PathBuf,
+    mod_dir: PathBuf,
+    inline_depth: usize,
+    /// Path of the modules with the directory for their children if known.
+    modules: Vec<(PathBuf, Option<PathBuf>)>,
+    included: Vec<PathBuf>,
+}
+
+impl ModVisitor {
+    fn add_module(&mut self, node: &syn::ItemMod) {
+        let name = node.ident.to_string();
+        let mut found = false;
+
+        let (paths, is_cfg_dependent) = path_attributes(&node.attrs);
+        for path in paths.iter() {
+            // Paths are relative to the directory of the file
+            // unless the module is declared in an inline module.
+            let dir = if self.inline_depth == 0 {
+                &self.file_dir
+            } else {
+                &self.mod_dir
+            };
+            let path = dir.join(path);
+            if path.is_file() {
+                self.modules.push((path, None));
+                found = true;
+            } else {
+                #[cfg(feature = "log")]
+                warn!("module {} not found at {:?}", name, path);
+            }
+        }
+
+        if !paths.is_empty() && !is_cfg_dependent {
+            return;
+        }
+
+        let file_path = self.mod_dir.join(format!("{}.rs", name));
+        let mod_rs_path = self.mod_dir.join(&name).join("mod.rs");
+        if file_path.is_file() {
+            self.modules
+                .push((file_path, Some(self.mod_dir.join(&name))));
+        } else if mod_rs_path.is_file() {
+            self.modules.push((mod_rs_path, None));
+        } else if !found {
+            #[cfg(feature = "log")]
+            warn!("module {} not found in {:?}", name, self.mod_dir);
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for ModVisitor {
+    fn visit_item_mod(&mut self, node: &'ast syn::ItemMod) {
+        if node.content.is_none() {
+            self.add_module(node);
+            return;
+        }
+
+        let (paths, _) = path_attributes(&node.attrs);
+        let dir_name = match paths.first() {
+            Some(path) => path.clone(),
+            None => PathBuf::from(node.ident.to_string()),
+        };
+
+        let prev_mod_dir = self.mod_dir.clone();
+        self.mod_dir = self.mod_dir.join(dir_name);
+        self.inline_depth += 1;
+
+        visit::visit_item_mod(self, node);
+
+        self.inline_depth -= 1;
+        self.mod_dir = prev_mod_dir;
+    }
+
+    fn visit_macro(&mut self, node: &'ast syn::Macro) {
+        let is_include = node
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "include");
+        if is_include {
+            if let Ok(lit) = syn::parse2::<syn::LitStr>(node.tokens.clone()) {
+                self.included.push(self.file_dir.join(lit.value()));
+            }
+        }
+
+        visit::visit_macro(self, node);
+    }
+}
+
+/// Returns the paths defined by `path` and `cfg_attr(_, path)` attributes.
+///
+/// The `bool` indicates whether one of the paths depends on a `cfg_attr`.
+fn path_attributes(attrs: &[syn::Attribute]) -> (Vec<PathBuf>, bool) {
+    let mut paths = Vec::new();
+    let mut is_cfg_dependent = false;
+
+    for attr in attrs {
+        let meta = match attr.parse_meta() {
+            Ok(meta) => meta,
+            Err(_) => continue,
+        };
+
+        match meta {
+            syn::Meta::NameValue(name_value) if name_value.path.is_ident("path") => {
+                if let syn::Lit::Str(lit) = name_value.lit {
+                    paths.push(PathBuf::from(lit.value()));
+                }
+            }
+            syn::Meta::List(list) if list.path.is_ident("cfg_attr") => {
+                for nested in list.nested.iter().skip(1) {
+                    if let syn::NestedMeta::Meta(syn::Meta::NameValue(name_value)) = nested {
+                        if name_value.path.is_ident("path") {
+                            if let syn::Lit::Str(lit) = &name_value.lit {
+                                paths.push(PathBuf::from(lit.value()));
+                                is_cfg_dependent = true;
+                            }
+                        }
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    (paths, is_cfg_dependent)
+}
+
+fn parent_dir(path: &Path) -> PathBuf {
+    path.parent().map(Path::to_owned).unwrap_or_default()
+}
+
+/// Lexically normalizes the `path`, resolving the `.` & `..` components.
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push("..");
+                }
+            }
+            other => normalized.push(other.as_os_str()),
+        }
+    }
+
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{temp_dir, write};
+    use rules::dir_entry::{DefaultDirEntryFilter, GlobDirEntryFilter};
+
+    #[test]
+    fn follow_modules() {
+        let root = temp_dir(env!("CARGO_PKG_NAME"), "module_graph");
+
+        write(
+            &root.join("Cargo.toml"),
+            "[package]\nname = \"graph\"\n\n[[bin]]\nname = \"tool\"\npath = \"tool/main.rs\"\n",
+        );
+        write(
+            &root.join("src").join("lib.rs"),
+            r#"
+mod a;
+#[path = "other/b_impl.rs"]
+mod b;
+#[cfg_attr(unix, path = "sys/unix.rs")]
+#[cfg_attr(windows, path = "sys/windows.rs")]
+mod sys;
+mod inline {
+    mod c;
+}
+include!("included.rs");
+"#,
+        );
+        write(&root.join("src").join("a.rs"), "mod nested;");
+        write(&root.join("src").join("a").join("nested.rs"), "");
+        write(&root.join("src").join("other").join("b_impl.rs"), "mod d;");
+        write(&root.join("src").join("other").join("d.rs"), "");
+        write(&root.join("src").join("sys").join("unix.rs"), "");
+        write(&root.join("src").join("sys").join("windows.rs"), "");
+        write(&root.join("src").join("inline").join("c.rs"), "");
+        write(&root.join("src").join("included.rs"), "fn included() {}");
+        write(&root.join("src").join("orphan.rs"), "");
+        write(&root.join("tool").join("main.rs"), "fn main() {}");
+        write(&root.join("tests").join("it.rs"), "");
+
+        let files: Vec<PathBuf> = rust_files(&root, &DefaultDirEntryFilter)
+            .unwrap()
+            .into_iter()
+            .map(|path| path.strip_prefix(&root).unwrap().to_owned())
+            .collect();
+
+        let expected: Vec<PathBuf> = [
+            "tool/main.rs",
+            "src/lib.rs",
+            "tests/it.rs",
+            "src/a.rs",
+            "src/other/b_impl.rs",
+            "src/sys/unix.rs",
+            "src/sys/windows.rs",
+            "src/inline/c.rs",
+            "src/included.rs",
+            "src/a/nested.rs",
+            "src/other/d.rs",
+        ]
+        .iter()
+        .map(PathBuf::from)
+        .collect();
+
+        assert_eq!(files, expected);
+    }
+
+    #[test]
+    fn manifest_overrides() {
+        let root = temp_dir(env!("CARGO_PKG_NAME"), "module_graph_overrides");
+
+        write(
+            &root.join("Cargo.toml"),
+            "[package]\nname = \"overrides\"\nbuild = false\nautotests = false\n\n\
+             [lib]\npath = \"lib/lib.rs\"\n\n\
+             [[bin]]\nname = \"overrides\"\npath = \"tool/main.rs\"\n",
+        );
+        write(&root.join("build.rs"), "fn main() {}");
+        write(&root.join("lib").join("lib.rs"), "");
+        write(&root.join("src").join("lib.rs"), "");
+        write(&root.join("src").join("main.rs"), "fn main() {}");
+        write(
+            &root.join("src").join("bin").join("other.rs"),
+            "fn main() {}",
+        );
+        write(&root.join("tool").join("main.rs"), "fn main() {}");
+        write(&root.join("tests").join("it.rs"), "");
+
+        let files: Vec<PathBuf> = rust_files(&root, &DefaultDirEntryFilter)
+            .unwrap()
+            .into_iter()
+            .map(|path| path.strip_prefix(&root).unwrap().to_owned())
+            .collect();
+
+        let expected: Vec<PathBuf> = ["lib/lib.rs", "tool/main.rs", "src/bin/other.rs"]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+
+        assert_eq!(files, expected);
+    }
+
+    #[test]
+    fn glob_filter() {
+        let root = temp_dir(env!("CARGO_PKG_NAME"), "module_graph_glob");
+
+        write(&root.join("Cargo.toml"), "[package]\nname = \"glob\"\n");
+        write(
+            &root.join("src").join("lib.rs"),
+            "mod a;\n#[path = \"generated/b.rs\"]\nmod b;\ninclude!(\"generated/c.rs\");\n",
+        );
+        write(&root.join("src").join("a.rs"), "");
+        write(&root.join("src").join("generated").join("b.rs"), "mod d;");
+        write(&root.join("src").join("generated").join("d.rs"), "");
+        write(&root.join("src").join("generated").join("c.rs"), "");
+        write(&root.join("tests").join("it.rs"), "");
+
+        let mut filter = GlobDirEntryFilter::new(&root);
+        filter.include("src/**").exclude("**/generated/**");
+
+        let files: Vec<PathBuf> = rust_files(&root, &filter)
+            .unwrap()
+            .into_iter()
+            .map(|path| path.strip_prefix(&root).unwrap().to_owned())
+            .collect();
+
+        let expected: Vec<PathBuf> = ["src/lib.rs", "src/a.rs"]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+
+        assert_eq!(files, expected);
+    }
+}
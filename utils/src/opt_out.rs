@@ -0,0 +1,408 @@
+//! Markers opting items out of the getter renaming.
+//!
+//! The following markers are supported:
+//!
+//! - A `// fix-getters: skip` comment on the first line of an item or in the
+//!   comments right above it.
+//! - A `#[cfg_attr(any(), fix_getters::skip)]` attribute on an item. The
+//!   `cfg_attr` is never active, so the tool attribute doesn't need to be
+//!   registered.
+//! - A `//! fix-getters: skip-file` comment, which opts the whole file out.
+//!
+//! Markers on `impl` blocks, `trait`s & modules apply to the nested items.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ops::RangeInclusive,
+};
+use syn::visit::{self, Visit};
+
+use crate::mention::{self, CommentKind};
+
+/// Comment marker opting the next item out.
+pub const SKIP_MARKER: &str = "fix-getters: skip";
+/// Inner doc comment marker opting the whole file out.
+pub const SKIP_FILE_MARKER: &str = "fix-getters: skip-file";
+
+/// The opt-out markers found in a Rust source file.
+#[derive(Clone, Debug, Default)]
+pub struct OptOut {
+    is_file_skipped: bool,
+    /// Lines of the items targeted by a `// fix-getters: skip` comment,
+    /// along with the line of the comment.
+    lines: BTreeMap<usize, usize>,
+}
+
+impl OptOut {
+    /// Searches the opt-out comments in the `source_code`.
+    ///
+    /// String & char literals are skipped, so that a marker in a literal
+    /// doesn't opt anything out.
+    pub fn new(source_code: &str) -> Self {
+        let mut opt_out = OptOut::default();
+
+        let line_starts: Vec<usize> = std::iter::once(0)
+            .chain(source_code.match_indices('\n').map(|(pos, _)| pos + 1))
+            .collect();
+        let line_nb = |pos: usize| line_starts.partition_point(|start| *start <= pos);
+
+        let comments = mention::comments(source_code);
+        // Lines starting with a line comment
+        let comment_lines: BTreeSet<usize> = comments
+            .iter()
+            .filter(|comment| !comment.is_block)
+            .filter_map(|comment| {
+                let delimiter_start = comment.text.start - comment_prefix_len(comment.kind);
+                let line_nb = line_nb(delimiter_start);
+                source_code[line_starts[line_nb - 1]..delimiter_start]
+                    .trim()
+                    .is_empty()
+                    .then_some(line_nb)
+            })
+            .collect();
+
+        for comment in comments.iter().filter(|comment| !comment.is_block) {
+            let text = source_code[comment.text.clone()].trim();
+            match comment.kind {
+                CommentKind::InnerDoc if text.starts_with(SKIP_FILE_MARKER) => {
+                    opt_out.is_file_skipped = true;
+                }
+                CommentKind::Regular if text.starts_with(SKIP_MARKER) => {
+                    let marker_line = line_nb(comment.text.start);
+                    if !comment_lines.contains(&marker_line) {
+                        opt_out.lines.entry(marker_line).or_insert(marker_line);
+                        continue;
+                    }
+
+                    // Applies to the first line which is not a comment
+                    let target_line = source_code
+                        .lines()
+                        .enumerate()
+                        .skip(marker_line)
+                        .map(|(line_idx, line)| (line_idx + 1, line))
+                        .find(|(line_nb, _)| !comment_lines.contains(line_nb));
+                    if let Some((target_line, line)) = target_line {
+                        if !line.trim().is_empty() {
+                            opt_out.lines.entry(target_line).or_insert(marker_line);
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        opt_out
+    }
+
+    /// Returns `true` if the whole file is opted out.
+    pub fn is_file_skipped(&self) -> bool {
+        self.is_file_skipped
+    }
+
+    /// Returns `true` if one of the `lines` is targeted by a marker,
+    /// or if the whole file is opted out.
+    pub fn is_marked(&self, lines: RangeInclusive<usize>) -> bool {
+        self.is_file_skipped || self.lines.range(lines).next().is_some()
+    }
+
+    /// Returns the lines of the items opted out in the `syntax_tree`.
+    ///
+    /// Each range spans from the marker comment or the first attribute of the
+    /// item to its end, so that it covers the item's documentation. The nested
+    /// items of an opted out item are not listed separately.
+    pub fn opted_out_lines(&self, syntax_tree: &syn::File) -> Vec<RangeInclusive<usize>> {
+        if self.is_file_skipped {
+            return vec![1..=usize::MAX];
+        }
+
+        let mut collector = OptedOutLines {
+            opt_out: self,
+            lines: Vec::new(),
+        };
+        collector.visit_file(syntax_tree);
+
+        collector.lines
+    }
+
+    /// Returns the lines from the marker comment or the first of the `attrs`
+    /// to the `end_line`, if the item is opted out.
+    fn item_opted_out_lines(
+        &self,
+        attrs: &[syn::Attribute],
+        lines: RangeInclusive<usize>,
+        end_line: usize,
+    ) -> Option<RangeInclusive<usize>> {
+        let marker_line = self.lines.range(lines.clone()).map(|(_, line)| *line).min();
+        if marker_line.is_none() && !has_skip_attribute(attrs) {
+            return None;
+        }
+
+        let start = marker_line.map_or(*lines.start(), |line| line.min(*lines.start()));
+        Some(start..=end_line.max(*lines.end()))
+    }
+}
+
+/// Collects the lines of the opted out items.
+struct OptedOutLines<'a> {
+    opt_out: &'a OptOut,
+    lines: Vec<RangeInclusive<usize>>,
+}
+
+impl<'a, 'ast> Visit<'ast> for OptedOutLines<'a> {
+    fn visit_item(&mut self, node: &'ast syn::Item) {
+        if let (Some(lines), Some(end_line)) = (item_lines(node), item_end_line(node)) {
+            if let Some(lines) =
+                self.opt_out
+                    .item_opted_out_lines(item_attrs(node), lines, end_line)
+            {
+                self.lines.push(lines);
+                return;
+            }
+        }
+
+        visit::visit_item(self, node);
+    }
+
+    fn visit_impl_item_method(&mut self, node: &'ast syn::ImplItemMethod) {
+        let lines = attrs_lines(&node.attrs, node.sig.ident.span().start().line);
+        let end_line = node.block.brace_token.span.end().line;
+        match self
+            .opt_out
+            .item_opted_out_lines(&node.attrs, lines, end_line)
+        {
+            Some(lines) => self.lines.push(lines),
+            None => visit::visit_impl_item_method(self, node),
+        }
+    }
+
+    fn visit_trait_item_method(&mut self, node: &'ast syn::TraitItemMethod) {
+        let lines = attrs_lines(&node.attrs, node.sig.ident.span().start().line);
+        let end_line = match (&node.default, &node.semi_token) {
+            (Some(block), _) => block.brace_token.span.end().line,
+            (None, Some(semi_token)) => semi_token.spans[0].end().line,
+            (None, None) => *lines.end(),
+        };
+        match self
+            .opt_out
+            .item_opted_out_lines(&node.attrs, lines, end_line)
+        {
+            Some(lines) => self.lines.push(lines),
+            None => visit::visit_trait_item_method(self, node),
+        }
+    }
+}
+
+/// Returns the length of the delimiter of a line comment of this `kind`.
+fn comment_prefix_len(kind: CommentKind) -> usize {
+    match kind {
+        CommentKind::Regular => 2,
+        CommentKind::OuterDoc | CommentKind::InnerDoc => 3,
+    }
+}
+
+/// Returns `true` if the `attrs` contain a `fix_getters::skip` tool attribute,
+/// possibly wrapped in a `cfg_attr`.
+pub fn has_skip_attribute(attrs: &[syn::Attribute]) -> bool {
+    attrs.iter().any(|attr| {
+        if is_skip_path(&attr.path) {
+            return true;
+        }
+
+        if !attr.path.is_ident("cfg_attr") {
+            return false;
+        }
+
+        match attr.parse_meta() {
+            Ok(syn::Meta::List(list)) => list.nested.iter().skip(1).any(|nested| match nested {
+                syn::NestedMeta::Meta(meta) => is_skip_path(meta.path()),
+                _ => false,
+            }),
+            _ => false,
+        }
+    })
+}
+
+fn is_skip_path(path: &syn::Path) -> bool {
+    path.segments.len() == 2
+        && path.segments[0].ident == "fix_getters"
+        && path.segments[1].ident == "skip"
+}
+
+/// Returns the attributes of the `item`.
+pub fn item_attrs(item: &syn::Item) -> &[syn::Attribute] {
+    use syn::Item::*;
+
+    match item {
+        Const(item) => &item.attrs,
+        Enum(item) => &item.attrs,
+        ExternCrate(item) => &item.attrs,
+        Fn(item) => &item.attrs,
+        ForeignMod(item) => &item.attrs,
+        Impl(item) => &item.attrs,
+        Macro(item) => &item.attrs,
+        Macro2(item) => &item.attrs,
+        Mod(item) => &item.attrs,
+        Static(item) => &item.attrs,
+        Struct(item) => &item.attrs,
+        Trait(item) => &item.attrs,
+        TraitAlias(item) => &item.attrs,
+        Type(item) => &item.attrs,
+        Union(item) => &item.attrs,
+        Use(item) => &item.attrs,
+        _ => &[],
+    }
+}
+
+/// Returns the lines from the first attribute of an `item` to its name.
+///
+/// Returns `None` for the items which can't contain getters.
+pub fn item_lines(item: &syn::Item) -> Option<RangeInclusive<usize>> {
+    use syn::Item::*;
+
+    let name_line = match item {
+        Const(item) => item.ident.span().start().line,
+        Fn(item) => item.sig.ident.span().start().line,
+        Impl(item) => item.impl_token.span.start().line,
+        Macro(item) => item.mac.bang_token.span.start().line,
+        Mod(item) => item.ident.span().start().line,
+        Static(item) => item.ident.span().start().line,
+        Trait(item) => item.ident.span().start().line,
+        _ => return None,
+    };
+
+    Some(attrs_lines(item_attrs(item), name_line))
+}
+
+/// Returns the last line of an `item`.
+///
+/// Returns `None` for the items which can't contain getters.
+fn item_end_line(item: &syn::Item) -> Option<usize> {
+    use syn::Item::*;
+
+    let span = match item {
+        Const(item) => item.semi_token.spans[0],
+        Fn(item) => item.block.brace_token.span,
+        Impl(item) => item.brace_token.span,
+        Macro(item) => match (&item.semi_token, &item.mac.delimiter) {
+            (Some(semi_token), _) => semi_token.spans[0],
+            (None, syn::MacroDelimiter::Paren(paren)) => paren.span,
+            (None, syn::MacroDelimiter::Brace(brace)) => brace.span,
+            (None, syn::MacroDelimiter::Bracket(bracket)) => bracket.span,
+        },
+        Mod(item) => match (&item.content, &item.semi) {
+            (Some((brace, _)), _) => brace.span,
+            (None, Some(semi)) => semi.spans[0],
+            (None, None) => item.ident.span(),
+        },
+        Static(item) => item.semi_token.spans[0],
+        Trait(item) => item.brace_token.span,
+        _ => return None,
+    };
+
+    Some(span.end().line)
+}
+
+/// Returns the lines from the first of the `attrs` to the `name_line` of an item.
+pub fn attrs_lines(attrs: &[syn::Attribute], name_line: usize) -> RangeInclusive<usize> {
+    let start = attrs
+        .first()
+        .map_or(name_line, |attr| attr.pound_token.span.start().line);
+
+    start.min(name_line)..=name_line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn markers() {
+        let opt_out = OptOut::new(
+            r#"// fix-getters: skip
+// Mirrors the C API.
+#[inline]
+fn get_a() {}
+
+fn get_b() {} // fix-getters: skip
+
+/// fix-getters: skip
+fn get_c() {}
+
+fn get_d() {}
+"#,
+        );
+        assert!(!opt_out.is_file_skipped());
+        assert!(opt_out.is_marked(3..=4));
+        assert!(opt_out.is_marked(6..=6));
+        assert!(!opt_out.is_marked(9..=9));
+        assert!(!opt_out.is_marked(11..=11));
+
+        let opt_out = OptOut::new("//! fix-getters: skip-file\n\nfn get_a() {}\n");
+        assert!(opt_out.is_file_skipped());
+        assert!(opt_out.is_marked(3..=3));
+
+        let item: syn::ItemFn =
+            syn::parse_str("#[cfg_attr(any(), fix_getters::skip)] fn get_a() {}").unwrap();
+        assert!(has_skip_attribute(&item.attrs));
+        let item: syn::ItemFn = syn::parse_str("#[inline] fn get_a() {}").unwrap();
+        assert!(!has_skip_attribute(&item.attrs));
+    }
+
+    #[test]
+    fn literal_markers() {
+        let opt_out = OptOut::new(
+            r##"const MARKER: &str = "// fix-getters: skip";
+fn get_a() {}
+
+fn get_b() -> &'static str { "http://example.org // fix-getters: skip" }
+
+const RAW: &str = r#"
+// fix-getters: skip
+"#;
+fn get_c() {}
+
+const FILE_MARKER: &str = "//! fix-getters: skip-file";
+"##,
+        );
+        assert!(!opt_out.is_file_skipped());
+        assert!(!opt_out.is_marked(1..=usize::MAX));
+
+        let opt_out = OptOut::new("let _ = '\"'; // fix-getters: skip\n");
+        assert!(opt_out.is_marked(1..=1));
+    }
+
+    #[test]
+    fn opted_out_lines() {
+        let source_code = r#"// fix-getters: skip
+// Mirrors the C API.
+/// Doc for get_a().
+fn get_a() {
+}
+
+impl Foo {
+    /// Doc for get_b().
+    #[cfg_attr(any(), fix_getters::skip)]
+    fn get_b(&self) {
+    }
+
+    /// Doc for get_c().
+    fn get_c(&self) {}
+}
+
+trait Bar {
+    fn get_d(&self); // fix-getters: skip
+}
+"#;
+        let opt_out = OptOut::new(source_code);
+        let syntax_tree = syn::parse_file(source_code).unwrap();
+        assert_eq!(
+            opt_out.opted_out_lines(&syntax_tree),
+            vec![1..=5, 8..=11, 18..=18],
+        );
+
+        let source_code = "//! fix-getters: skip-file\n\nfn get_a() {}\n";
+        let opt_out = OptOut::new(source_code);
+        let syntax_tree = syn::parse_file(source_code).unwrap();
+        assert!(opt_out.opted_out_lines(&syntax_tree)[0].contains(&3));
+    }
+}
@@ -0,0 +1,206 @@
+//! Getters generated by `paste!`-style identifier concatenations.
+//!
+//! Crates such as `paste` generate identifiers from concatenations, e.g.
+//! `fn [<get_ $field>](&self)`. The generated names can't be known while
+//! parsing, but a concatenation which only prepends the `get_` literal prefix
+//! to a macro variable can be rewritten by dropping the prefix, e.g.
+//! `[<get_ $field>]` to `[<$field>]`, or by replacing it with `is_` for a
+//! getter returning a `bool`, e.g. `[<is_ $field>]`.
+
+use proc_macro2::{Delimiter, Group, Spacing, TokenTree};
+use rules::ReturnsBool;
+use std::ops::Range;
+
+const GET_PREFIX: &str = "get_";
+
+/// A concatenation generating a `get_*` identifier.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Concat {
+    /// The concatenated segments, e.g. `["get_", "$field"]`.
+    segments: Vec<String>,
+}
+
+impl Concat {
+    /// Parses the concatenation in the `group`, if it generates a `get_*` identifier.
+    ///
+    /// The `group` is expected to be delimited by brackets, e.g. `[<get_ $field>]`.
+    pub fn parse(group: &Group) -> Option<Self> {
+        if group.delimiter() != Delimiter::Bracket {
+            return None;
+        }
+
+        let tokens: Vec<TokenTree> = group.stream().into_iter().collect();
+        let inner = match tokens.as_slice() {
+            [TokenTree::Punct(open), inner @ .., TokenTree::Punct(close)]
+                if open.as_char() == '<' && close.as_char() == '>' =>
+            {
+                inner
+            }
+            _ => return None,
+        };
+
+        let mut segments: Vec<String> = Vec::new();
+        let mut idx = 0;
+        while idx < inner.len() {
+            match &inner[idx] {
+                TokenTree::Ident(ident) => segments.push(ident.to_string()),
+                TokenTree::Literal(literal) => segments.push(literal.to_string()),
+                TokenTree::Punct(punct) if punct.as_char() == '$' => match inner.get(idx + 1) {
+                    Some(TokenTree::Ident(var)) => {
+                        segments.push(format!("${}", var));
+                        idx += 1;
+                    }
+                    _ => return None,
+                },
+                // Case modifier, e.g. `$field:camel`
+                TokenTree::Punct(punct)
+                    if punct.as_char() == ':' && punct.spacing() == Spacing::Alone =>
+                {
+                    match (segments.last_mut(), inner.get(idx + 1)) {
+                        (Some(segment), Some(TokenTree::Ident(modifier))) => {
+                            *segment += &format!(":{}", modifier);
+                            idx += 1;
+                        }
+                        _ => return None,
+                    }
+                }
+                _ => return None,
+            }
+            idx += 1;
+        }
+
+        if !segments.first()?.starts_with(GET_PREFIX) {
+            return None;
+        }
+
+        Some(Concat { segments })
+    }
+
+    /// Builds the concatenation from its `name`, as returned by [`Concat::name`].
+    pub fn from_name(name: &str) -> Option<Self> {
+        let segments: Vec<String> = name
+            .strip_prefix("[<")?
+            .strip_suffix(">]")?
+            .split_whitespace()
+            .map(String::from)
+            .collect();
+        if !segments.first()?.starts_with(GET_PREFIX) {
+            return None;
+        }
+
+        Some(Concat { segments })
+    }
+
+    /// Returns the name of the concatenation as written, e.g. `[<get_ $field>]`.
+    pub fn name(&self) -> String {
+        format!("[<{}>]", self.segments.join(" "))
+    }
+
+    /// Returns the name of the concatenation without the `get_` prefix, e.g.
+    /// `[<$field>]`, or with the `is_` prefix if the getter `returns_bool`,
+    /// e.g. `[<is_ $field>]`.
+    ///
+    /// This is only possible when the `get_` prefix is followed by a single
+    /// macro variable without case modifier.
+    pub fn new_name(&self, returns_bool: impl Into<ReturnsBool>) -> Option<String> {
+        match self.segments.as_slice() {
+            [prefix, var] if prefix == GET_PREFIX && var.starts_with('$') && !var.contains(':') => {
+                if returns_bool.into().is_true() {
+                    Some(format!("[<is_ {}>]", var))
+                } else {
+                    Some(format!("[<{}>]", var))
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Returns the byte ranges of the occurrences of `name` in `line`.
+///
+/// A concatenation `name`, e.g. `[<get_ $field>]`, matches regardless of the
+/// whitespaces surrounding its segments, e.g. `[< get_$field >]`.
+pub fn match_ranges(line: &str, name: &str) -> Vec<Range<usize>> {
+    let segments = match name
+        .strip_prefix("[<")
+        .and_then(|name| name.strip_suffix(">]"))
+    {
+        Some(inner) => inner.split_whitespace().collect::<Vec<&str>>(),
+        None => {
+            return line
+                .match_indices(name)
+                .map(|(pos, name)| pos..pos + name.len())
+                .collect();
+        }
+    };
+
+    line.match_indices("[<")
+        .filter_map(|(start, open)| {
+            let mut rest = &line[start + open.len()..];
+            for segment in segments.iter() {
+                rest = rest.trim_start().strip_prefix(segment)?;
+            }
+            let rest = rest.trim_start().strip_prefix(">]")?;
+
+            Some(start..line.len() - rest.len())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(code: &str) -> Option<Concat> {
+        match syn::parse_str::<proc_macro2::TokenStream>(code)
+            .unwrap()
+            .into_iter()
+            .next()
+        {
+            Some(TokenTree::Group(group)) => Concat::parse(&group),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn concat() {
+        let concat = parse("[<get_ $field>]").unwrap();
+        assert_eq!(concat.name(), "[<get_ $field>]");
+        assert_eq!(concat.new_name(false).as_deref(), Some("[<$field>]"));
+        assert_eq!(concat.new_name(true).as_deref(), Some("[<is_ $field>]"));
+        assert_eq!(Concat::from_name(&concat.name()), Some(concat));
+
+        let concat = parse("[<get_$field>]").unwrap();
+        assert_eq!(concat.name(), "[<get_ $field>]");
+
+        let concat = parse("[<get_ $field _mut>]").unwrap();
+        assert_eq!(concat.name(), "[<get_ $field _mut>]");
+        assert!(concat.new_name(false).is_none());
+
+        let concat = parse("[<get_ $field:snake>]").unwrap();
+        assert_eq!(concat.name(), "[<get_ $field:snake>]");
+        assert!(concat.new_name(false).is_none());
+
+        let concat = parse("[<get_first_ $field>]").unwrap();
+        assert!(concat.new_name(false).is_none());
+
+        assert!(parse("[<set_ $field>]").is_none());
+        assert!(parse("[get_a, get_b]").is_none());
+        assert!(parse("(<get_ $field>)").is_none());
+    }
+
+    #[test]
+    fn ranges() {
+        let line = "self.[< get_$field >]() + self.[<get_ $field>]() + self.get_a()";
+        let ranges = match_ranges(line, "[<get_ $field>]");
+        assert_eq!(
+            ranges
+                .into_iter()
+                .map(|range| &line[range])
+                .collect::<Vec<&str>>(),
+            vec!["[< get_$field >]", "[<get_ $field>]"],
+        );
+        assert_eq!(match_ranges(line, "get_a"), vec![56..61]);
+        assert!(match_ranges(line, "[<get_ $other>]").is_empty());
+    }
+}
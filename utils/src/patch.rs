@@ -0,0 +1,234 @@
+//! Unified diff generation.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::{self, Write},
+    ops::Range,
+    path::{Component, Path, PathBuf},
+    sync::Mutex,
+};
+
+use crate::{edit, module_graph, Edit};
+
+/// Number of unchanged lines displayed around the changes.
+const CONTEXT: usize = 3;
+
+/// A patch in the unified diff format.
+///
+/// The diff of each Rust file is added as the file is processed. The diffs
+/// are rendered in the order of the paths, which are relative to the `root`
+/// so that the patch can be applied from there using `git apply`.
+#[derive(Debug)]
+pub struct Patch {
+    root: PathBuf,
+    files: Mutex<BTreeMap<PathBuf, String>>,
+}
+
+impl Patch {
+    /// Builds a [`Patch`] for the files in the tree starting at `root`.
+    pub fn new(root: impl AsRef<Path>) -> Self {
+        Patch {
+            root: module_graph::normalize(root.as_ref()),
+            files: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Adds the diff resulting from applying the `edits` to the `source_code`
+    /// of the file at `path`.
+    pub fn add(&self, path: &Path, source_code: &str, edits: &[Edit]) {
+        if edits.is_empty() {
+            return;
+        }
+
+        let path = self.relative_path(path);
+        let diff = unified_diff(&diff_path(&path), source_code, edits);
+        self.files
+            .lock()
+            .expect("patch poisoned")
+            .insert(path, diff);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.lock().expect("patch poisoned").is_empty()
+    }
+
+    /// Writes the patch to the given `writer`.
+    pub fn write_to(&self, writer: &mut dyn Write) -> io::Result<()> {
+        for diff in self.files.lock().expect("patch poisoned").values() {
+            writer.write_all(diff.as_bytes())?;
+        }
+
+        writer.flush()
+    }
+
+    fn relative_path(&self, path: &Path) -> PathBuf {
+        let path = module_graph::normalize(path);
+        match path.strip_prefix(&self.root) {
+            Ok(rel_path) if !rel_path.as_os_str().is_empty() => rel_path.to_owned(),
+            // Single file or file located out of the root
+            _ => path.file_name().map_or(path.clone(), PathBuf::from),
+        }
+    }
+}
+
+/// Returns `path` as used in diff headers, i.e. with `/` separators.
+fn diff_path(path: &Path) -> String {
+    path.components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(name.to_string_lossy()),
+            Component::ParentDir => Some("..".into()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Renders the diff resulting from applying the `edits` to the `source_code`.
+///
+/// `path` is the path to use in the diff headers. The diff uses the
+/// `a/` & `b/` prefixes expected by `git apply`.
+pub fn unified_diff(path: &str, source_code: &str, edits: &[Edit]) -> String {
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    for line in source_code.split_inclusive('\n') {
+        lines.push(offset..offset + line.len());
+        offset += line.len();
+    }
+
+    let line_idx = |offset: usize| {
+        lines
+            .partition_point(|line| line.end <= offset)
+            .min(lines.len().saturating_sub(1))
+    };
+
+    let mut changed = BTreeSet::new();
+    for edit in edits {
+        let last = line_idx(edit.range.end.saturating_sub(1).max(edit.range.start));
+        changed.extend(line_idx(edit.range.start)..=last);
+    }
+
+    let mut diff = format!("--- a/{}\n+++ b/{}\n", path, path);
+    let mut delta = 0isize;
+    for hunk in hunks(&changed, lines.len()) {
+        let mut body = String::new();
+        let (mut old_count, mut new_count) = (0, 0);
+
+        let mut idx = hunk.start;
+        while idx < hunk.end {
+            if !changed.contains(&idx) {
+                push_line(&mut body, ' ', &source_code[lines[idx].clone()]);
+                old_count += 1;
+                new_count += 1;
+                idx += 1;
+                continue;
+            }
+
+            // Render the whole run of changed lines
+            let first = idx;
+            while changed.contains(&idx) {
+                idx += 1;
+            }
+            let run = lines[first].start..lines[idx - 1].end;
+
+            for line in source_code[run.clone()].split_inclusive('\n') {
+                push_line(&mut body, '-', line);
+                old_count += 1;
+            }
+
+            let run_edits: Vec<Edit> = edits
+                .iter()
+                .filter(|edit| {
+                    run.start <= edit.range.start
+                        && edit.range.start < run.end
+                        && edit.range.end <= run.end
+                })
+                .map(|edit| {
+                    Edit::new(
+                        edit.range.start - run.start..edit.range.end - run.start,
+                        edit.text.as_str(),
+                    )
+                })
+                .collect();
+            for line in edit::apply(&source_code[run], &run_edits).split_inclusive('\n') {
+                push_line(&mut body, '+', line);
+                new_count += 1;
+            }
+        }
+
+        let old_start = hunk.start + 1;
+        let new_start = (old_start as isize + delta) as usize;
+        diff.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            old_start, old_count, new_start, new_count
+        ));
+        diff.push_str(&body);
+
+        delta += new_count as isize - old_count as isize;
+    }
+
+    diff
+}
+
+/// Groups the `changed` lines in hunks with their context.
+fn hunks(changed: &BTreeSet<usize>, line_count: usize) -> Vec<Range<usize>> {
+    let mut hunks: Vec<Range<usize>> = Vec::new();
+    for &idx in changed {
+        let start = idx.saturating_sub(CONTEXT);
+        let end = (idx + CONTEXT + 1).min(line_count);
+        match hunks.last_mut() {
+            Some(last) if start <= last.end => last.end = end,
+            _ => hunks.push(start..end),
+        }
+    }
+
+    hunks
+}
+
+fn push_line(body: &mut String, prefix: char, line: &str) {
+    body.push(prefix);
+    body.push_str(line);
+    if !line.ends_with('\n') {
+        body.push_str("\n\\ No newline at end of file\n");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_hunks() {
+        let source_code = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\nm";
+        let edits = [
+            Edit::new(2..3, "B"),
+            Edit::insert(6, "#"),
+            Edit::new(24..25, "M"),
+        ];
+
+        assert_eq!(
+            unified_diff("src/lib.rs", source_code, &edits),
+            concat!(
+                "--- a/src/lib.rs\n",
+                "+++ b/src/lib.rs\n",
+                "@@ -1,7 +1,7 @@\n",
+                " a\n",
+                "-b\n",
+                "+B\n",
+                " c\n",
+                "-d\n",
+                "+#d\n",
+                " e\n",
+                " f\n",
+                " g\n",
+                "@@ -10,4 +10,4 @@\n",
+                " j\n",
+                " k\n",
+                " l\n",
+                "-m\n",
+                "\\ No newline at end of file\n",
+                "+M\n",
+                "\\ No newline at end of file\n",
+            ),
+        );
+    }
+}
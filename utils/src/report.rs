@@ -0,0 +1,409 @@
+//! Report of the renaming candidates.
+
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display, Write as _},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use rules::{NewNameRule, RenameError, ReturnsBool};
+
+use crate::{paste, Getter, GetterError, NonGetterReason, Scope, Statistics};
+
+/// A function which was considered for renaming.
+///
+/// Candidates are recorded by the getter collections, whether the function
+/// is renamed or skipped.
+#[derive(Clone, Debug)]
+pub struct Candidate {
+    pub name: String,
+    pub line: usize,
+    pub scope: Scope,
+    /// The renaming, unless the function can't be renamed.
+    pub renaming: Option<Renaming>,
+    /// The reason for not renaming the function, if it is skipped.
+    pub skip: Option<SkipReason>,
+}
+
+/// The renaming of a [`Candidate`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Renaming {
+    pub new_name: String,
+    pub rule: NewNameRule,
+    pub returns_bool: ReturnsBool,
+}
+
+/// The reason for skipping a [`Candidate`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum SkipReason {
+    NonGetter(NonGetterReason),
+    RenameError(RenameError),
+}
+
+impl Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SkipReason::NonGetter(reason) => reason.fmt(f),
+            SkipReason::RenameError(err) => err.fmt(f),
+        }
+    }
+}
+
+impl Candidate {
+    /// Builds a [`Candidate`] for the `getter` to rename in `scope`.
+    pub fn renamed(getter: &Getter, scope: &Scope) -> Self {
+        Candidate {
+            name: getter.name.clone(),
+            line: getter.line,
+            scope: scope.clone(),
+            renaming: Some(Renaming {
+                new_name: getter.new_name.as_str().to_string(),
+                rule: getter.new_name.rule(),
+                returns_bool: getter.new_name.returns_bool(),
+            }),
+            skip: None,
+        }
+    }
+
+    /// Builds a [`Candidate`] for the `getter` skipped in `scope` for `reason`.
+    pub fn skipped(getter: &Getter, scope: &Scope, reason: NonGetterReason) -> Self {
+        Candidate {
+            skip: Some(SkipReason::NonGetter(reason)),
+            ..Candidate::renamed(getter, scope)
+        }
+    }
+
+    /// Builds a [`Candidate`] for the function which couldn't be renamed in `scope`.
+    pub fn rejected(err: &GetterError, scope: &Scope) -> Self {
+        Candidate {
+            name: err.name.clone(),
+            line: err.line,
+            scope: scope.clone(),
+            renaming: None,
+            skip: Some(SkipReason::RenameError(err.err)),
+        }
+    }
+
+    /// Builds a [`Candidate`] for the getter which name is generated by the
+    /// `concat`enation found at `line` in `scope`.
+    ///
+    /// The candidate is skipped for `reason` if provided, or if the
+    /// concatenation can't be rewritten.
+    pub fn generated(
+        concat: &paste::Concat,
+        line: usize,
+        scope: &Scope,
+        returns_bool: ReturnsBool,
+        reason: Option<NonGetterReason>,
+    ) -> Self {
+        let renaming = concat.new_name(returns_bool).map(|new_name| Renaming {
+            new_name,
+            rule: NewNameRule::Regular,
+            returns_bool,
+        });
+        let reason = match renaming {
+            Some(_) => reason,
+            None => Some(NonGetterReason::Generated),
+        };
+
+        Candidate {
+            name: concat.name(),
+            line,
+            scope: scope.clone(),
+            renaming,
+            skip: reason.map(SkipReason::NonGetter),
+        }
+    }
+
+    pub fn is_skipped(&self) -> bool {
+        self.skip.is_some()
+    }
+
+    /// Returns `true` if the candidate is opted out by a marker.
+    pub fn is_opted_out(&self) -> bool {
+        matches!(
+            self.skip,
+            Some(SkipReason::NonGetter(NonGetterReason::OptedOut))
+        )
+    }
+}
+
+/// A [`Candidate`] located in a Rust file.
+#[derive(Clone, Debug)]
+pub struct Record {
+    pub path: PathBuf,
+    pub column: usize,
+    pub candidate: Candidate,
+}
+
+/// Report of the renaming candidates.
+///
+/// The candidates of each Rust file are added as the file is processed.
+/// The records are rendered as a JSON array in the order of the paths,
+/// along with the [`Statistics`] of the run.
+#[derive(Debug, Default)]
+pub struct Report {
+    files: Mutex<BTreeMap<PathBuf, Vec<Record>>>,
+}
+
+impl Report {
+    /// Adds the `candidates` found in the `source_code` of the file at `path`.
+    pub fn add(&self, path: &Path, source_code: &str, mut candidates: Vec<Candidate>) {
+        if candidates.is_empty() {
+            return;
+        }
+
+        candidates.sort_by_key(|candidate| candidate.line);
+
+        let lines: Vec<&str> = source_code.lines().collect();
+        let mut records = Vec::<Record>::with_capacity(candidates.len());
+        for candidate in candidates {
+            // Candidates with the same name on the same line are located
+            // after the previous one.
+            let from = records
+                .iter()
+                .rev()
+                .take_while(|record| record.candidate.line == candidate.line)
+                .find(|record| record.candidate.name == candidate.name)
+                .map_or(0, |record| record.column);
+            let column = lines
+                .get(candidate.line.wrapping_sub(1))
+                .and_then(|line| {
+                    let start = line
+                        .char_indices()
+                        .nth(from)
+                        .map_or(line.len(), |(pos, _)| pos);
+                    paste::match_ranges(&line[start..], &candidate.name)
+                        .first()
+                        .map(|range| from + line[start..start + range.start].chars().count() + 1)
+                })
+                .unwrap_or(1);
+
+            records.push(Record {
+                path: path.to_owned(),
+                column,
+                candidate,
+            });
+        }
+
+        self.files
+            .lock()
+            .expect("report poisoned")
+            .insert(path.to_owned(), records);
+    }
+
+    /// Applies the review decision to the candidate `name` located
+    /// at `line` & `column` in the file at `path`.
+    ///
+    /// The candidate is renamed after `new_name` or skipped as
+    /// [`Declined`](NonGetterReason::Declined) if there is none.
+    pub fn review(
+        &self,
+        path: &Path,
+        (line, column): (usize, usize),
+        name: &str,
+        new_name: Option<&str>,
+    ) {
+        let mut files = self.files.lock().expect("report poisoned");
+        let candidate = match files.get_mut(path).and_then(|records| {
+            records.iter_mut().find(|record| {
+                record.candidate.line == line
+                    && record.column == column
+                    && record.candidate.name == name
+            })
+        }) {
+            Some(record) => &mut record.candidate,
+            None => return,
+        };
+
+        match (new_name, candidate.renaming.as_mut()) {
+            (Some(new_name), Some(renaming)) => renaming.new_name = new_name.to_string(),
+            _ => candidate.skip = Some(SkipReason::NonGetter(NonGetterReason::Declined)),
+        }
+    }
+
+    /// Applies the review decision to the candidate `name` generated by
+    /// a derive attribute of the struct found in `scope` in the file at `path`.
+    ///
+    /// Derived candidates are located at their fields, so they are matched
+    /// by `scope` & `name`. See [`review`](Self::review) for `new_name`.
+    pub fn review_derived(&self, path: &Path, scope: &Scope, name: &str, new_name: Option<&str>) {
+        let mut files = self.files.lock().expect("report poisoned");
+        let candidate = match files.get_mut(path).and_then(|records| {
+            records.iter_mut().find(|record| {
+                !record.candidate.is_skipped()
+                    && &record.candidate.scope == scope
+                    && record.candidate.name == name
+            })
+        }) {
+            Some(record) => &mut record.candidate,
+            None => return,
+        };
+
+        match (new_name, candidate.renaming.as_mut()) {
+            (Some(new_name), Some(renaming)) => renaming.new_name = new_name.to_string(),
+            _ => candidate.skip = Some(SkipReason::NonGetter(NonGetterReason::Declined)),
+        }
+    }
+
+    /// Returns the records in the order of the paths.
+    pub fn records(&self) -> Vec<Record> {
+        self.files
+            .lock()
+            .expect("report poisoned")
+            .values()
+            .flatten()
+            .cloned()
+            .collect()
+    }
+
+    /// Computes the [`Statistics`] of the records.
+    pub fn statistics(&self) -> Statistics {
+        Statistics::new(&self.records())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.lock().expect("report poisoned").is_empty()
+    }
+
+    /// Writes the report to the given `writer` as a JSON object.
+    ///
+    /// The object holds the array of `candidates` and the `statistics`.
+    pub fn write_to(&self, writer: &mut dyn Write) -> io::Result<()> {
+        let records = self.records();
+        writer.write_all(b"{\n  \"candidates\": [")?;
+        for (idx, record) in records.iter().enumerate() {
+            if idx > 0 {
+                writer.write_all(b",")?;
+            }
+            write!(writer, "\n    {}", to_json(record))?;
+        }
+        if !records.is_empty() {
+            writer.write_all(b"\n  ")?;
+        }
+        writeln!(
+            writer,
+            "],\n  \"statistics\": {}\n}}",
+            Statistics::new(&records).to_json(),
+        )?;
+
+        writer.flush()
+    }
+}
+
+fn to_json(record: &Record) -> String {
+    let candidate = &record.candidate;
+    let mut json = format!(
+        "{{\"path\": {}, \"line\": {}, \"column\": {}, \"scope\": {}, \"name\": {}, ",
+        json_string(&record.path.to_string_lossy()),
+        candidate.line,
+        record.column,
+        json_string(&candidate.scope.to_string()),
+        json_string(&candidate.name),
+    );
+
+    match &candidate.renaming {
+        Some(renaming) => {
+            let _ = write!(
+                json,
+                "\"new_name\": {}, \"rule\": \"{:?}\", \"returns_bool\": \"{:?}\"",
+                json_string(&renaming.new_name),
+                renaming.rule,
+                renaming.returns_bool,
+            );
+        }
+        None => json.push_str("\"new_name\": null, \"rule\": null, \"returns_bool\": null"),
+    }
+
+    match &candidate.skip {
+        Some(SkipReason::NonGetter(reason)) => {
+            let _ = write!(json, ", \"non_getter_reason\": \"{:?}\"", reason);
+        }
+        Some(SkipReason::RenameError(err)) => {
+            let _ = write!(json, ", \"rename_error\": \"{:?}\"", err);
+        }
+        None => (),
+    }
+    json.push('}');
+
+    json
+}
+
+pub(crate) fn json_string(value: &str) -> String {
+    let mut json = String::with_capacity(value.len() + 2);
+    json.push('"');
+    for char_ in value.chars() {
+        match char_ {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            char_ if char_.is_control() => {
+                let _ = write!(json, "\\u{:04x}", char_ as u32);
+            }
+            char_ => json.push(char_),
+        }
+    }
+    json.push('"');
+
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ScopeItem;
+
+    #[test]
+    fn json_records() {
+        let source_code = "fn get_a() {}\n\nfn f() {\n    é.get_b(get_b());\n}\n";
+        let scope = Scope::from(ScopeItem::Fn("f".to_string()));
+        let get_b = Getter::try_new("get_b".to_string(), ReturnsBool::Maybe, 4).unwrap();
+        let get_a = Getter::try_new("get_a".to_string(), false, 1).unwrap();
+        let get_mut = GetterError {
+            name: "get_mut".to_string(),
+            err: RenameError::Reserved,
+            line: 4,
+        };
+        let candidates = vec![
+            Candidate::renamed(&get_b, &scope),
+            Candidate::skipped(&get_b, &scope, NonGetterReason::NotAMethod),
+            Candidate::renamed(&get_a, &Scope::from(ScopeItem::Fn("get_a".to_string()))),
+            Candidate::rejected(&get_mut, &scope),
+        ];
+
+        let report = Report::default();
+        report.add(Path::new("src/\"lib\".rs"), source_code, candidates);
+
+        let columns: Vec<usize> = report.records().iter().map(|rec| rec.column).collect();
+        assert_eq!(columns, [4, 7, 13, 1]);
+
+        let mut output = Vec::new();
+        report.write_to(&mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[0], "{");
+        assert_eq!(lines[1], "  \"candidates\": [");
+        assert_eq!(
+            lines[2],
+            "    {\"path\": \"src/\\\"lib\\\".rs\", \"line\": 1, \"column\": 4, \"scope\": \"fn get_a\", \
+             \"name\": \"get_a\", \"new_name\": \"a\", \"rule\": \"Regular\", \"returns_bool\": \"False\"},",
+        );
+        assert!(lines[4].ends_with(
+            "\"new_name\": \"b\", \"rule\": \"Regular\", \"returns_bool\": \"Maybe\", \
+             \"non_getter_reason\": \"NotAMethod\"},"
+        ));
+        assert!(lines[5].ends_with(
+            "\"name\": \"get_mut\", \"new_name\": null, \"rule\": null, \
+             \"returns_bool\": null, \"rename_error\": \"Reserved\"}"
+        ));
+        assert_eq!(lines[6], "  ],");
+        assert_eq!(lines[7], "  \"statistics\": {");
+        assert_eq!(lines[8], "    \"renamed\": 2,");
+        assert_eq!(lines[9], "    \"skipped\": 2,");
+    }
+}
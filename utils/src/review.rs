@@ -0,0 +1,615 @@
+//! Review of the renamings.
+//!
+//! A [`Review`] wraps a [`CrateTraverser`] and applies the [`Decisions`]
+//! to the renamings it collects. In interactive mode, the user is prompted
+//! for each renaming which is not already decided.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::Write as _,
+    fs::{self, DirEntry},
+    io::{BufRead, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use crate::{atomic_write, edit, CrateTraverser, Edit, EditKind, Error, Report, Scope};
+
+/// Number of lines displayed around a renaming in interactive mode.
+const CONTEXT: usize = 2;
+
+const HELP: &str = "\
+y - rename this function
+n - keep this function unchanged
+e - edit the new name
+a - rename this function and all the functions with the same name
+k - keep this function and all the functions with the same name
+q - quit, keeping the remaining undecided functions unchanged
+? - print help
+";
+
+/// Decisions on the renamings by function name.
+///
+/// The decision for a name is the new name to use. Keeping a function
+/// unchanged is recorded as mapping its name to itself.
+///
+/// Decisions can be saved to a file, e.g. so that the decisions taken when
+/// renaming the getter definitions apply to the getter calls.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Decisions(BTreeMap<String, String>);
+
+impl Decisions {
+    /// Loads the decisions from the file at `path`, if it exists.
+    ///
+    /// The file contains one `name = new_name` decision per line.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let mut decisions = Decisions::default();
+        if !path.exists() {
+            return Ok(decisions);
+        }
+
+        let content =
+            fs::read_to_string(path).map_err(|err| Error::ReadFile(path.to_owned(), err))?;
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some((name, new_name)) = line.split_once('=') {
+                decisions.insert(name.trim(), new_name.trim());
+            }
+        }
+
+        Ok(decisions)
+    }
+
+    /// Saves the decisions to the file at `path`.
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let mut content = String::new();
+        for (name, new_name) in self.0.iter() {
+            let _ = writeln!(content, "{} = {}", name, new_name);
+        }
+
+        atomic_write::write(path, content.as_bytes(), None)
+    }
+
+    /// Returns the new name decided for `name`, if any.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0.get(name).map(String::as_str)
+    }
+
+    pub fn insert(&mut self, name: impl Into<String>, new_name: impl Into<String>) {
+        self.0.insert(name.into(), new_name.into());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// A [`CrateTraverser`] wrapper which reviews the renamings.
+///
+/// Renamings for which a decision is known are applied accordingly. Other
+/// renamings are applied as is, unless the [`Review`] is interactive.
+///
+/// If a [`Report`] is provided, its candidates are updated after the decisions.
+pub struct Review<T> {
+    inner: T,
+    state: Mutex<State>,
+    report: Option<Arc<Report>>,
+}
+
+impl<T: CrateTraverser> Review<T> {
+    /// Builds a [`Review`] applying the `decisions` to the renamings of `inner`.
+    pub fn new(inner: T, decisions: Decisions) -> Self {
+        Review {
+            inner,
+            state: Mutex::new(State {
+                applied: decisions.clone(),
+                recorded: decisions,
+                prompter: None,
+                must_quit: false,
+                is_by_name: false,
+            }),
+            report: None,
+        }
+    }
+
+    /// Applies each decision to all the functions with the same name.
+    ///
+    /// This is needed when the definitions & the calls are renamed in the same
+    /// pass: the decision taken for a getter definition must apply to its calls.
+    pub fn by_name(self) -> Self {
+        self.state().is_by_name = true;
+        self
+    }
+
+    /// Updates the candidates of the `report` after the decisions.
+    pub fn with_report(mut self, report: Arc<Report>) -> Self {
+        self.report = Some(report);
+        self
+    }
+
+    /// Builds an interactive [`Review`].
+    ///
+    /// The user is prompted via `output` for each renaming which is not
+    /// already decided. Answers are read from `input`.
+    pub fn interactive(
+        inner: T,
+        decisions: Decisions,
+        input: impl BufRead + Send + 'static,
+        output: impl Write + Send + 'static,
+    ) -> Self {
+        let review = Review::new(inner, decisions);
+        review.state().prompter = Some(Prompter {
+            input: Box::new(input),
+            output: Box::new(output),
+        });
+
+        review
+    }
+
+    /// Returns the decisions taken so far, including the initial decisions.
+    pub fn decisions(&self) -> Decisions {
+        self.state().recorded.clone()
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().expect("review poisoned")
+    }
+}
+
+impl<T: CrateTraverser> CrateTraverser for Review<T> {
+    fn collect_edits(&self, path: &Path, source_code: &str) -> Result<Vec<Edit>, Error> {
+        let edits = self.inner.collect_edits(path, source_code)?;
+        self.state()
+            .review(path, source_code, edits, self.report.as_deref())
+    }
+
+    fn handle_skipped_dir_entry(
+        &self,
+        entry: &DirEntry,
+        output_path: &Option<PathBuf>,
+    ) -> Result<(), Error> {
+        self.inner.handle_skipped_dir_entry(entry, output_path)
+    }
+}
+
+struct State {
+    /// Decisions applied without prompting.
+    applied: Decisions,
+    /// All the decisions, including those only applying to one function.
+    recorded: Decisions,
+    prompter: Option<Prompter>,
+    /// Keeps the undecided functions unchanged.
+    must_quit: bool,
+    /// Applies the decisions to all the functions with the same name.
+    is_by_name: bool,
+}
+
+impl State {
+    fn review(
+        &mut self,
+        path: &Path,
+        source_code: &str,
+        edits: Vec<Edit>,
+        report: Option<&Report>,
+    ) -> Result<Vec<Edit>, Error> {
+        let mut kept_lines = HashSet::new();
+        let mut reviewed = Vec::with_capacity(edits.len());
+
+        for mut edit in edits {
+            if let EditKind::DeriveAttribute { scope, getters } = &edit.kind {
+                if self.review_derived(path, source_code, &edit, scope, getters, report)? {
+                    reviewed.push(edit);
+                }
+                continue;
+            }
+
+            if !edit.kind.is_rename() {
+                reviewed.push(edit);
+                continue;
+            }
+
+            let name = &source_code[edit.range.clone()];
+            let new_name = if let Some(new_name) = self.applied.get(name) {
+                Some(new_name.to_string())
+            } else if self.must_quit {
+                None
+            } else if self.prompter.is_some() {
+                self.prompt(path, source_code, &edit, name, &edit.text)?
+            } else {
+                Some(edit.text.clone())
+            };
+
+            let new_name = new_name.filter(|new_name| new_name != name);
+            let position = edit::position(source_code, edit.range.start);
+            if let Some(report) = report {
+                report.review(path, position, name, new_name.as_deref());
+            }
+
+            match new_name {
+                Some(new_name) => {
+                    edit.text = new_name;
+                    reviewed.push(edit);
+                }
+                None => {
+                    kept_lines.insert(position.0);
+                }
+            }
+        }
+
+        // Don't document the functions which are kept unchanged
+        reviewed.retain(|edit| {
+            !edit.kind.is_doc_alias()
+                || !kept_lines.contains(&edit::position(source_code, edit.range.start).0)
+        });
+
+        Ok(reviewed)
+    }
+
+    /// Reviews the renaming of the getters generated by the derive attribute
+    /// `edit` of the struct found in `scope`.
+    ///
+    /// An attribute can't rename its getters separately: if one of them is
+    /// kept unchanged, they are all kept and the attribute is left unchanged.
+    ///
+    /// Returns `true` if the `edit` is applied.
+    fn review_derived(
+        &mut self,
+        path: &Path,
+        source_code: &str,
+        edit: &Edit,
+        scope: &Scope,
+        getters: &[(String, String)],
+        report: Option<&Report>,
+    ) -> Result<bool, Error> {
+        let mut is_applied = true;
+        for (name, new_name) in getters {
+            let decision = if let Some(decision) = self.applied.get(name) {
+                Some(decision.to_string())
+            } else if self.must_quit {
+                None
+            } else if self.prompter.is_some() {
+                self.prompt(path, source_code, edit, name, new_name)?
+            } else {
+                Some(new_name.clone())
+            };
+
+            if decision.as_deref() != Some(new_name) {
+                is_applied = false;
+                break;
+            }
+        }
+
+        for (name, new_name) in getters {
+            let new_name = if is_applied {
+                new_name.as_str()
+            } else {
+                // Also keep the calls unchanged
+                if !self.must_quit {
+                    if self.is_by_name {
+                        self.applied.insert(name, name.as_str());
+                    }
+                    self.recorded.insert(name, name.as_str());
+                }
+                name.as_str()
+            };
+
+            if let Some(report) = report {
+                report.review_derived(path, scope, name, Some(new_name).filter(|n| n != name));
+            }
+        }
+
+        Ok(is_applied)
+    }
+
+    /// Prompts the user for a decision about renaming the function `name`
+    /// as `new_name` with the `edit`.
+    ///
+    /// Returns the new name or `None` if the function is kept unchanged.
+    fn prompt(
+        &mut self,
+        path: &Path,
+        source_code: &str,
+        edit: &Edit,
+        name: &str,
+        new_name: &str,
+    ) -> Result<Option<String>, Error> {
+        let prompter = self.prompter.as_mut().expect("interactive review");
+        prompter.display(path, source_code, edit)?;
+
+        loop {
+            let answer = prompter.ask(&format!(
+                "Rename {}() -> {}()? [y,n,e,a,k,q,?] ",
+                name, new_name
+            ))?;
+
+            let new_name = match answer.as_deref() {
+                Some("y") => new_name.to_string(),
+                Some("n") => name.to_string(),
+                // The derive attributes generate the names of their getters
+                Some("e") if edit.kind.is_derive_attribute() => {
+                    prompter.print("The name of a derived getter can't be edited\n")?;
+                    continue;
+                }
+                Some("e") => match prompter.ask(&format!("New name for {}(): ", name))? {
+                    Some(new_name) if is_ident(&new_name) => new_name,
+                    _ => {
+                        prompter.print("Invalid name\n")?;
+                        continue;
+                    }
+                },
+                Some("a") => {
+                    self.applied.insert(name, new_name);
+                    new_name.to_string()
+                }
+                Some("k") => {
+                    self.applied.insert(name, name);
+                    name.to_string()
+                }
+                Some("q") | None => {
+                    self.must_quit = true;
+                    return Ok(None);
+                }
+                _ => {
+                    prompter.print(HELP)?;
+                    continue;
+                }
+            };
+
+            if self.is_by_name {
+                self.applied.insert(name, new_name.as_str());
+            }
+            self.recorded.insert(name, new_name.as_str());
+            return Ok(Some(new_name));
+        }
+    }
+}
+
+struct Prompter {
+    input: Box<dyn BufRead + Send>,
+    output: Box<dyn Write + Send>,
+}
+
+impl Prompter {
+    /// Displays the renaming `edit` with the surrounding lines.
+    fn display(&mut self, path: &Path, source_code: &str, edit: &Edit) -> Result<(), Error> {
+        let (line, column) = edit::position(source_code, edit.range.start);
+        let mut text = format!("\n{}:{}:{}", path.display(), line, column);
+        match &edit.kind {
+            EditKind::Rename { scope } => {
+                let _ = write!(text, " in {}", scope);
+            }
+            EditKind::DeriveAttribute { scope, .. } => {
+                let _ = write!(text, " derive attribute in {}", scope);
+            }
+            _ => (),
+        }
+        text.push('\n');
+
+        let first = line.saturating_sub(CONTEXT).max(1);
+        for (idx, content) in source_code
+            .lines()
+            .enumerate()
+            .skip(first - 1)
+            .take(line + CONTEXT + 1 - first)
+        {
+            let marker = if idx + 1 == line { '>' } else { ' ' };
+            let _ = writeln!(text, "{} {:>5} | {}", marker, idx + 1, content);
+        }
+
+        self.print(&text)
+    }
+
+    /// Asks the user the `question`.
+    ///
+    /// Returns `None` if the input is closed.
+    fn ask(&mut self, question: &str) -> Result<Option<String>, Error> {
+        self.print(question)?;
+
+        let mut answer = String::new();
+        let len = self.input.read_line(&mut answer).map_err(Error::Prompt)?;
+        if len == 0 {
+            return Ok(None);
+        }
+
+        Ok(Some(answer.trim().to_string()))
+    }
+
+    fn print(&mut self, text: &str) -> Result<(), Error> {
+        self.output
+            .write_all(text.as_bytes())
+            .and_then(|()| self.output.flush())
+            .map_err(Error::Prompt)
+    }
+}
+
+fn is_ident(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|first| first == '_' || first.is_alphabetic())
+        && chars.all(|char_| char_ == '_' || char_.is_alphanumeric())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{report::SkipReason, Candidate, Getter, NonGetterReason, Scope, ScopeItem};
+    use std::io;
+
+    struct Fixer;
+
+    impl CrateTraverser for Fixer {
+        fn collect_edits(&self, _path: &Path, source_code: &str) -> Result<Vec<Edit>, Error> {
+            let scope = Scope::from(ScopeItem::struct_impl("Foo"));
+            let mut edits = Vec::new();
+            let mut offset = 0;
+            for line in source_code.split_inclusive('\n') {
+                if let Some(pos) = line.find(", with_prefix") {
+                    let getters = vec![
+                        ("get_a".to_string(), "a".to_string()),
+                        ("get_b".to_string(), "is_b".to_string()),
+                    ];
+                    let range = offset + pos..offset + pos + ", with_prefix".len();
+                    edits.push(Edit::derive_attribute(range, "", &scope, getters));
+                } else if let Some(pos) = line.find("get_") {
+                    let end = offset + pos + line[pos..].find('(').unwrap();
+                    let name = &source_code[offset + pos..end];
+                    if line.starts_with("fn ") {
+                        edits.push(Edit::doc_alias(offset, "#[doc(alias)] "));
+                    }
+                    edits.push(Edit::rename(offset + pos..end, &name[4..], &scope));
+                }
+                offset += line.len();
+            }
+
+            Ok(edits)
+        }
+    }
+
+    #[test]
+    fn interactive_review() {
+        let source_code =
+            "fn get_a()\nfn get_b()\nfn get_c()\nfn get_a()\nfn get_b()\nfn get_d()\n";
+        let mut decisions = Decisions::default();
+        decisions.insert("get_d", "get_d");
+
+        let review = Review::interactive(
+            Fixer,
+            decisions,
+            io::Cursor::new("y\nk\nx\ne\n0\ne\nc_\n"),
+            io::sink(),
+        );
+        let output = review.fix(Path::new("lib.rs"), source_code).unwrap();
+
+        assert_eq!(
+            output.unwrap(),
+            "#[doc(alias)] fn a()\nfn get_b()\n#[doc(alias)] fn c_()\nfn get_a()\nfn get_b()\nfn get_d()\n",
+        );
+
+        let decisions = review.decisions();
+        assert_eq!(decisions.get("get_a"), Some("a"));
+        assert_eq!(decisions.get("get_b"), Some("get_b"));
+        assert_eq!(decisions.get("get_c"), Some("c_"));
+        assert_eq!(decisions.get("get_d"), Some("get_d"));
+    }
+
+    #[test]
+    fn by_name_review() {
+        let review = Review::interactive(
+            Fixer,
+            Decisions::default(),
+            io::Cursor::new("n\ne\nb_\ny\nq\n"),
+            io::sink(),
+        )
+        .by_name();
+
+        let output = review
+            .fix(Path::new("a.rs"), "fn get_a()\nfn get_b()\nfn get_c()\n")
+            .unwrap();
+        assert_eq!(
+            output.unwrap(),
+            "fn get_a()\n#[doc(alias)] fn b_()\n#[doc(alias)] fn c()\n",
+        );
+
+        // The calls use the decisions taken for the definitions, even after quitting
+        let output = review
+            .fix(
+                Path::new("b.rs"),
+                "fn get_d()\nx.get_a()\nx.get_b()\nx.get_c()\nx.get_d()\n",
+            )
+            .unwrap();
+        assert_eq!(
+            output.unwrap(),
+            "fn get_d()\nx.get_a()\nx.b_()\nx.c()\nx.get_d()\n",
+        );
+
+        let decisions = review.decisions();
+        assert_eq!(decisions.get("get_a"), Some("get_a"));
+        assert_eq!(decisions.get("get_b"), Some("b_"));
+        assert_eq!(decisions.get("get_c"), Some("c"));
+        assert_eq!(decisions.get("get_d"), None);
+    }
+
+    #[test]
+    fn derive_attribute_review() {
+        let source_code = "#[getset(get, with_prefix)]\nx.get_a()\nx.get_b()\n";
+
+        // The names of the derived getters can't be edited
+        let review = Review::interactive(
+            Fixer,
+            Decisions::default(),
+            io::Cursor::new("e\ny\nn\n"),
+            io::sink(),
+        )
+        .by_name();
+        let output = review.fix(Path::new("a.rs"), source_code).unwrap();
+        assert_eq!(output, None);
+
+        // All the getters are kept when one of them is declined
+        let decisions = review.decisions();
+        assert_eq!(decisions.get("get_a"), Some("get_a"));
+        assert_eq!(decisions.get("get_b"), Some("get_b"));
+
+        let review = Review::interactive(
+            Fixer,
+            Decisions::default(),
+            io::Cursor::new("y\ny\n"),
+            io::sink(),
+        )
+        .by_name();
+        let output = review.fix(Path::new("a.rs"), source_code).unwrap();
+        assert_eq!(output.unwrap(), "#[getset(get)]\nx.a()\nx.is_b()\n");
+    }
+
+    #[test]
+    fn review_report() {
+        let source_code = "fn get_a()\nfn get_b()\nfn get_c()\n";
+        let path = Path::new("lib.rs");
+        let scope = Scope::from(ScopeItem::struct_impl("Foo"));
+
+        let report = Arc::new(Report::default());
+        report.add(
+            path,
+            source_code,
+            ["get_a", "get_b", "get_c"]
+                .iter()
+                .enumerate()
+                .map(|(idx, name)| {
+                    let getter = Getter::try_new(name.to_string(), false, idx + 1).unwrap();
+                    Candidate::renamed(&getter, &scope)
+                })
+                .collect(),
+        );
+
+        let review = Review::interactive(
+            Fixer,
+            Decisions::default(),
+            io::Cursor::new("y\nn\ne\nc_\n"),
+            io::sink(),
+        )
+        .with_report(Arc::clone(&report));
+        review.fix(path, source_code).unwrap();
+
+        let candidates: Vec<Candidate> = report
+            .records()
+            .into_iter()
+            .map(|record| record.candidate)
+            .collect();
+        assert_eq!(candidates[0].renaming.as_ref().unwrap().new_name, "a");
+        assert!(!candidates[0].is_skipped());
+        assert_eq!(
+            candidates[1].skip,
+            Some(SkipReason::NonGetter(NonGetterReason::Declined)),
+        );
+        assert_eq!(candidates[2].renaming.as_ref().unwrap().new_name, "c_");
+        assert!(!candidates[2].is_skipped());
+
+        let stats = report.statistics();
+        assert_eq!(stats.skip_reasons.get("Declined"), Some(&1));
+        assert_eq!(stats.renames.get(&("get_c".into(), "c_".into())), Some(&1));
+    }
+}
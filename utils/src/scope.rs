@@ -1,99 +1,435 @@
 //! Rust code scope identification.
 
-use std::{cell::RefCell, fmt, rc::Rc, string::ToString};
+use std::{
+    fmt,
+    path::{Component, Path},
+    string::ToString,
+};
+
+/// Name of the root module in a [`Scope`] path.
+pub const CRATE_ROOT: &str = "crate";
 
 /// Rust code scope identification.
-#[derive(Debug)]
-pub enum Scope {
+///
+/// A [`Scope`] is the chain of the items enclosing the code, starting with
+/// the modules, e.g. `crate::gui::widget::impl Button`. The innermost
+/// [`ScopeItem`] determines the kind of the scope.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Scope {
+    /// The items from the crate root to the innermost item, never empty.
+    items: Vec<ScopeItem>,
+}
+
+impl Scope {
+    /// Builds the [`Scope`] of the crate root module.
+    pub fn crate_root() -> Self {
+        Scope::from(ScopeItem::Mod(CRATE_ROOT.to_string()))
+    }
+
+    /// Builds the [`Scope`] of the module defined in the Rust file at `path`.
+    ///
+    /// The module path is derived from the location of the file in the `src`
+    /// directory, e.g. `src/gui/widget.rs` & `src/gui/widget/mod.rs` define
+    /// `crate::gui::widget`. Files located out of a `src` directory, such as
+    /// tests or examples, and binaries in `src/bin` are crate roots.
+    pub fn from_file(path: &Path) -> Self {
+        let mut scope = Scope::crate_root();
+
+        let components: Vec<&str> = path
+            .components()
+            .filter_map(|component| match component {
+                Component::Normal(name) => name.to_str(),
+                _ => None,
+            })
+            .collect();
+        let rel_path = match components.iter().rposition(|name| *name == "src") {
+            Some(src_pos) => &components[src_pos + 1..],
+            None => return scope,
+        };
+        let (file_name, dirs) = match rel_path.split_last() {
+            Some(split) => split,
+            None => return scope,
+        };
+        let dirs = match dirs.split_first() {
+            // `src/bin/name.rs` or `src/bin/name/main.rs`
+            Some((&"bin", dirs)) => match dirs.split_first() {
+                Some((_, dirs)) => dirs,
+                None => return scope,
+            },
+            _ => dirs,
+        };
+
+        for dir in dirs {
+            scope.push(ScopeItem::Mod(dir.to_string()));
+        }
+        match file_name.strip_suffix(".rs") {
+            Some("lib") | Some("main") if dirs.is_empty() => (),
+            Some("mod") | None => (),
+            Some(module) => scope.push(ScopeItem::Mod(module.to_string())),
+        }
+
+        scope
+    }
+
+    /// Returns the innermost item of this [`Scope`].
+    pub fn item(&self) -> &ScopeItem {
+        self.items.last().expect("empty scope")
+    }
+
+    /// Returns the items of this [`Scope`], starting with the outermost.
+    pub fn items(&self) -> &[ScopeItem] {
+        &self.items
+    }
+
+    /// Checks whether the innermost item is nested in the body of a function
+    /// or in the initializer of a `const` or `static` item.
+    pub fn is_in_body(&self) -> bool {
+        self.items[..self.items.len() - 1].iter().any(|item| {
+            matches!(
+                item,
+                ScopeItem::Fn(_) | ScopeItem::Const(_) | ScopeItem::Static(_)
+            )
+        })
+    }
+
+    /// Returns the kind of the innermost item, regardless of its name.
+    pub fn kind(&self) -> &'static str {
+        self.item().kind()
+    }
+
+    /// Enters the `item`, which becomes the innermost item.
+    pub fn push(&mut self, item: impl Into<ScopeItem>) {
+        self.items.push(item.into());
+    }
+
+    /// Leaves the innermost item.
+    ///
+    /// The outermost item is never removed.
+    pub fn pop(&mut self) {
+        if self.items.len() > 1 {
+            self.items.pop();
+        }
+    }
+
+    /// Returns a copy of this [`Scope`] in which the `item` is entered.
+    pub fn join(&self, item: impl Into<ScopeItem>) -> Self {
+        let mut scope = self.clone();
+        scope.push(item);
+        scope
+    }
+}
+
+impl Default for Scope {
+    fn default() -> Self {
+        Scope::from(ScopeItem::Unexpected)
+    }
+}
+
+impl From<ScopeItem> for Scope {
+    fn from(item: ScopeItem) -> Self {
+        Scope { items: vec![item] }
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        for (idx, item) in self.items.iter().enumerate() {
+            if idx > 0 {
+                f.write_str("::")?;
+            }
+            item.fmt(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks whether the displayed `scope` matches the `pattern`.
+///
+/// The `pattern` matches the end of the scope path, e.g. `widget::impl Button`
+/// matches `crate::gui::widget::impl Button`. An item can also be designated
+/// without its generic parameters & where clause, e.g. `impl Button` matches
+/// `impl<T> Button<T> where T: Clone`, or by its name only, e.g. `Button`.
+pub fn matches(scope: &str, pattern: &str) -> bool {
+    let items = split_path(scope);
+    let pattern = split_path(pattern);
+    if pattern.len() > items.len() {
+        return false;
+    }
+
+    items[items.len() - pattern.len()..]
+        .iter()
+        .zip(pattern)
+        .all(|(item, pattern)| {
+            if *item == pattern {
+                return true;
+            }
+
+            let item = strip_generics(item);
+            item == pattern || item.rsplit(' ').next() == Some(pattern)
+        })
+}
+
+/// Splits the displayed `scope` into its items.
+///
+/// The `::` separators nested in generic arguments are not considered.
+fn split_path(scope: &str) -> Vec<&str> {
+    let bytes = scope.as_bytes();
+    let mut items = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    let mut idx = 0;
+    while idx < bytes.len() {
+        match bytes[idx] {
+            b'<' => depth += 1,
+            b'>' if idx == 0 || bytes[idx - 1] != b'-' => depth = depth.saturating_sub(1),
+            b':' if depth == 0 && bytes.get(idx + 1) == Some(&b':') => {
+                items.push(&scope[start..idx]);
+                start = idx + 2;
+                idx += 1;
+            }
+            _ => (),
+        }
+        idx += 1;
+    }
+    items.push(&scope[start..]);
+
+    items
+}
+
+/// Removes the generic parameters & arguments and the where clause from the
+/// displayed `item`.
+fn strip_generics(item: &str) -> String {
+    let item = item.split(" where ").next().unwrap_or(item);
+
+    let mut stripped = String::with_capacity(item.len());
+    let mut depth = 0usize;
+    let mut prev = None;
+    for char_ in item.chars() {
+        match char_ {
+            '<' => depth += 1,
+            '>' if prev != Some('-') && depth > 0 => depth -= 1,
+            _ if depth == 0 => stripped.push(char_),
+            _ => (),
+        }
+        prev = Some(char_);
+    }
+
+    stripped
+}
+
+/// An item enclosing some Rust code.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum ScopeItem {
     Attribute(String),
+    /// A comment or documentation prose.
+    Comment,
     Const(String),
     Documentation,
+    Enum(String),
+    /// An `extern` block, with its ABI if specified, e.g. `"C"`.
+    ExternBlock(Option<String>),
+    ExternCrate(String),
     Fn(String),
     Macro(String),
+    Mod(String),
     Static(String),
-    StructImpl(String),
+    Struct(String),
+    StructImpl {
+        generics: ImplGenerics,
+        type_: String,
+    },
     Trait(String),
-    TraitImpl { trait_: String, type_: String },
+    TraitImpl {
+        generics: ImplGenerics,
+        trait_: String,
+        type_: String,
+    },
+    Type(String),
+    Union(String),
+    Use,
+    #[default]
     Unexpected,
 }
 
-impl Default for Scope {
-    fn default() -> Self {
-        Scope::Unexpected
+impl ScopeItem {
+    /// Returns the kind of this [`ScopeItem`], regardless of its name.
+    pub fn kind(&self) -> &'static str {
+        use ScopeItem::*;
+
+        match self {
+            Attribute(_) => "attr",
+            Comment => "comment",
+            Const(_) => "const",
+            Documentation => "doc code",
+            Enum(_) => "enum",
+            ExternBlock(_) => "extern block",
+            ExternCrate(_) => "extern crate",
+            Fn(_) => "fn",
+            Macro(_) => "macro!",
+            Mod(_) => "mod",
+            Static(_) => "static",
+            Struct(_) => "struct",
+            StructImpl { .. } => "impl",
+            Trait(_) => "trait",
+            TraitImpl { .. } => "trait impl",
+            Type(_) => "type",
+            Union(_) => "union",
+            Use => "use",
+            Unexpected => "unexpected",
+        }
+    }
+
+    /// Builds the [`ScopeItem`] for the `impl` block of `type_` without generics.
+    pub fn struct_impl(type_: impl ToString) -> Self {
+        ScopeItem::StructImpl {
+            generics: ImplGenerics::default(),
+            type_: type_.to_string(),
+        }
     }
 }
 
-impl From<&syn::Item> for Scope {
+impl From<&syn::Item> for ScopeItem {
     fn from(node: &syn::Item) -> Self {
         match node {
-            syn::Item::Const(item) => Scope::Const(item.ident.to_string()),
-            syn::Item::Fn(fn_) => Scope::Fn(fn_.sig.ident.to_string()),
+            syn::Item::Const(item) => ScopeItem::Const(item.ident.to_string()),
+            syn::Item::Enum(enum_) => ScopeItem::Enum(enum_.ident.to_string()),
+            syn::Item::ExternCrate(crate_) => ScopeItem::ExternCrate(crate_.ident.to_string()),
+            syn::Item::Fn(fn_) => ScopeItem::Fn(fn_.sig.ident.to_string()),
+            syn::Item::ForeignMod(foreign_mod) => ScopeItem::ExternBlock(
+                foreign_mod
+                    .abi
+                    .name
+                    .as_ref()
+                    .map(|name| format!("{:?}", name.value())),
+            ),
             syn::Item::Impl(impl_) => {
-                let type_ident = format_type_name(&impl_.self_ty);
+                let generics = ImplGenerics::from(&impl_.generics);
+                let type_ = format_type_name(&impl_.self_ty);
 
-                if let Some((_, trait_path, _)) = &impl_.trait_ {
-                    let trait_ident = path_ident(&trait_path);
+                if let Some((bang, trait_path, _)) = &impl_.trait_ {
+                    let bang = if bang.is_some() { "!" } else { "" };
 
-                    Scope::TraitImpl {
-                        trait_: trait_ident,
-                        type_: type_ident,
+                    ScopeItem::TraitImpl {
+                        generics,
+                        trait_: format!("{}{}", bang, format_path(trait_path)),
+                        type_,
                     }
                 } else {
-                    Scope::StructImpl(type_ident)
+                    ScopeItem::StructImpl { generics, type_ }
                 }
             }
-            syn::Item::Macro(macro_) => Scope::Macro(
+            syn::Item::Macro(macro_) => ScopeItem::Macro(
                 macro_
                     .ident
                     .as_ref()
                     .map(|ident| ident.to_string())
                     .unwrap_or_else(|| "unnamed".to_string()),
             ),
-            syn::Item::Macro2(macro2) => Scope::Macro(macro2.ident.to_string()),
-            syn::Item::Static(static_) => Scope::Static(static_.ident.to_string()),
-            syn::Item::Trait(trait_) => Scope::Trait(trait_.ident.to_string()),
-            _ => Scope::Unexpected,
+            syn::Item::Macro2(macro2) => ScopeItem::Macro(macro2.ident.to_string()),
+            syn::Item::Mod(mod_) => ScopeItem::Mod(mod_.ident.to_string()),
+            syn::Item::Static(static_) => ScopeItem::Static(static_.ident.to_string()),
+            syn::Item::Struct(struct_) => ScopeItem::Struct(struct_.ident.to_string()),
+            syn::Item::Trait(trait_) => ScopeItem::Trait(trait_.ident.to_string()),
+            syn::Item::TraitAlias(alias) => ScopeItem::Trait(alias.ident.to_string()),
+            syn::Item::Type(type_) => ScopeItem::Type(type_.ident.to_string()),
+            syn::Item::Union(union_) => ScopeItem::Union(union_.ident.to_string()),
+            syn::Item::Use(_) => ScopeItem::Use,
+            _ => ScopeItem::Unexpected,
         }
     }
 }
 
-impl From<&syn::Attribute> for Scope {
+impl From<&syn::Attribute> for ScopeItem {
     fn from(node: &syn::Attribute) -> Self {
-        Scope::Attribute(path_ident(&node.path))
+        ScopeItem::Attribute(path_ident(&node.path))
     }
 }
 
-impl From<&syn::Macro> for Scope {
+impl From<&syn::Macro> for ScopeItem {
     fn from(node: &syn::Macro) -> Self {
-        Scope::Macro(path_ident(&node.path))
+        ScopeItem::Macro(path_ident(&node.path))
     }
 }
 
-impl From<Scope> for Rc<RefCell<Scope>> {
-    fn from(scope: Scope) -> Self {
-        Rc::new(RefCell::new(scope))
-    }
-}
-
-impl fmt::Display for Scope {
+impl fmt::Display for ScopeItem {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        use Scope::*;
+        use ScopeItem::*;
 
         match self {
             Attribute(name) => write!(f, "attr {}", name),
+            Comment => f.write_str("comment"),
             Const(name) => write!(f, "const {}", name),
             Documentation => f.write_str("doc code"),
+            Enum(name) => write!(f, "enum {}", name),
+            ExternBlock(Some(abi)) => write!(f, "extern {}", abi),
+            ExternBlock(None) => f.write_str("extern"),
+            ExternCrate(name) => write!(f, "extern crate {}", name),
             Fn(name) => write!(f, "fn {}", name),
             Macro(name) => write!(f, "macro! {}", name),
+            Mod(name) => f.write_str(name),
             Static(name) => write!(f, "static {}", name),
-            StructImpl(struct_) => f.write_str(struct_),
-            Trait(trait_) => f.write_str(trait_),
-            TraitImpl { trait_, type_ } => write!(f, "impl {} for {}", trait_, type_),
+            Struct(name) => write!(f, "struct {}", name),
+            StructImpl { generics, type_ } => write!(
+                f,
+                "impl{} {}{}",
+                generics.params, type_, generics.where_clause
+            ),
+            Trait(trait_) => write!(f, "trait {}", trait_),
+            TraitImpl {
+                generics,
+                trait_,
+                type_,
+            } => write!(
+                f,
+                "impl{} {} for {}{}",
+                generics.params, trait_, type_, generics.where_clause
+            ),
+            Type(name) => write!(f, "type {}", name),
+            Union(name) => write!(f, "union {}", name),
+            Use => f.write_str("use"),
             Unexpected => f.write_str("**Unexpected scope**"),
         }
     }
 }
 
+/// The generic parameters & where clause of an `impl` block, as displayed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ImplGenerics {
+    /// The generic parameters, e.g. `<'a, T: Clone>`, or an empty string.
+    pub params: String,
+    /// The where clause, e.g. ` where T: Debug`, or an empty string.
+    pub where_clause: String,
+}
+
+impl From<&syn::Generics> for ImplGenerics {
+    fn from(generics: &syn::Generics) -> Self {
+        let params = if generics.params.is_empty() {
+            String::new()
+        } else {
+            let params: Vec<String> = generics.params.iter().map(format_generic_param).collect();
+            format!("<{}>", params.join(", "))
+        };
+
+        let where_clause = match &generics.where_clause {
+            Some(where_clause) if !where_clause.predicates.is_empty() => {
+                let predicates: Vec<String> = where_clause
+                    .predicates
+                    .iter()
+                    .map(format_where_predicate)
+                    .collect();
+                format!(" where {}", predicates.join(", "))
+            }
+            _ => String::new(),
+        };
+
+        ImplGenerics {
+            params,
+            where_clause,
+        }
+    }
+}
+
 fn path_ident(path: &syn::Path) -> String {
     if path.segments.is_empty() {
         return String::default();
@@ -101,9 +437,174 @@ fn path_ident(path: &syn::Path) -> String {
     path.segments.last().unwrap().ident.to_string()
 }
 
+/// Formats the last segment of the `path`, along with its generic arguments.
+fn format_path(path: &syn::Path) -> String {
+    match path.segments.last() {
+        Some(segment) => format_path_segment(segment),
+        None => String::default(),
+    }
+}
+
+fn format_path_segment(segment: &syn::PathSegment) -> String {
+    let mut formatted = segment.ident.to_string();
+
+    match &segment.arguments {
+        syn::PathArguments::None => (),
+        syn::PathArguments::AngleBracketed(args) => {
+            let args: Vec<String> = args.args.iter().map(format_generic_arg).collect();
+            formatted += &format!("<{}>", args.join(", "));
+        }
+        syn::PathArguments::Parenthesized(args) => {
+            let inputs: Vec<String> = args.inputs.iter().map(format_type_name).collect();
+            formatted += &format!("({})", inputs.join(", "));
+            if let syn::ReturnType::Type(_, output) = &args.output {
+                formatted += &format!(" -> {}", format_type_name(output));
+            }
+        }
+    }
+
+    formatted
+}
+
+fn format_generic_arg(arg: &syn::GenericArgument) -> String {
+    match arg {
+        syn::GenericArgument::Lifetime(lifetime) => lifetime.to_string(),
+        syn::GenericArgument::Type(type_) => format_type_name(type_),
+        syn::GenericArgument::Binding(binding) => {
+            format!("{} = {}", binding.ident, format_type_name(&binding.ty))
+        }
+        syn::GenericArgument::Constraint(constraint) => {
+            format!(
+                "{}: {}",
+                constraint.ident,
+                format_bounds(constraint.bounds.iter())
+            )
+        }
+        syn::GenericArgument::Const(expr) => format_const_expr(expr),
+    }
+}
+
+fn format_const_expr(expr: &syn::Expr) -> String {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Int(int),
+            ..
+        }) => int.base10_digits().to_string(),
+        syn::Expr::Path(path) => format_path(&path.path),
+        _ => "{ .. }".to_string(),
+    }
+}
+
+fn format_generic_param(param: &syn::GenericParam) -> String {
+    match param {
+        syn::GenericParam::Type(type_param) => {
+            if type_param.bounds.is_empty() {
+                type_param.ident.to_string()
+            } else {
+                format!(
+                    "{}: {}",
+                    type_param.ident,
+                    format_bounds(type_param.bounds.iter())
+                )
+            }
+        }
+        syn::GenericParam::Lifetime(lifetime_def) => {
+            format_lifetime_bounds(&lifetime_def.lifetime, lifetime_def.bounds.iter())
+        }
+        syn::GenericParam::Const(const_param) => format!(
+            "const {}: {}",
+            const_param.ident,
+            format_type_name(&const_param.ty)
+        ),
+    }
+}
+
+fn format_where_predicate(predicate: &syn::WherePredicate) -> String {
+    match predicate {
+        syn::WherePredicate::Type(predicate) => format!(
+            "{}{}: {}",
+            format_bound_lifetimes(predicate.lifetimes.as_ref()),
+            format_type_name(&predicate.bounded_ty),
+            format_bounds(predicate.bounds.iter())
+        ),
+        syn::WherePredicate::Lifetime(predicate) => {
+            format_lifetime_bounds(&predicate.lifetime, predicate.bounds.iter())
+        }
+        syn::WherePredicate::Eq(predicate) => format!(
+            "{} = {}",
+            format_type_name(&predicate.lhs_ty),
+            format_type_name(&predicate.rhs_ty)
+        ),
+    }
+}
+
+fn format_lifetime_bounds<'a>(
+    lifetime: &syn::Lifetime,
+    bounds: impl Iterator<Item = &'a syn::Lifetime>,
+) -> String {
+    let bounds: Vec<String> = bounds.map(ToString::to_string).collect();
+    if bounds.is_empty() {
+        lifetime.to_string()
+    } else {
+        format!("{}: {}", lifetime, bounds.join(" + "))
+    }
+}
+
+fn format_bound_lifetimes(lifetimes: Option<&syn::BoundLifetimes>) -> String {
+    match lifetimes {
+        Some(lifetimes) => {
+            let lifetimes: Vec<String> = lifetimes
+                .lifetimes
+                .iter()
+                .map(|lifetime_def| lifetime_def.lifetime.to_string())
+                .collect();
+            format!("for<{}> ", lifetimes.join(", "))
+        }
+        None => String::new(),
+    }
+}
+
+fn format_bounds<'a>(bounds: impl Iterator<Item = &'a syn::TypeParamBound>) -> String {
+    let bounds: Vec<String> = bounds
+        .map(|bound| match bound {
+            syn::TypeParamBound::Trait(trait_) => {
+                let modifier = match trait_.modifier {
+                    syn::TraitBoundModifier::Maybe(_) => "?",
+                    syn::TraitBoundModifier::None => "",
+                };
+                format!(
+                    "{}{}{}",
+                    format_bound_lifetimes(trait_.lifetimes.as_ref()),
+                    modifier,
+                    format_path(&trait_.path)
+                )
+            }
+            syn::TypeParamBound::Lifetime(lifetime) => lifetime.to_string(),
+        })
+        .collect();
+
+    bounds.join(" + ")
+}
+
 fn format_type_name(self_ty: &syn::Type) -> String {
     match self_ty {
-        syn::Type::Path(path) => path_ident(&path.path),
+        syn::Type::Path(path) => match &path.qself {
+            Some(qself) => {
+                let segments: Vec<String> =
+                    path.path.segments.iter().map(format_path_segment).collect();
+                let (trait_, assoc) = segments.split_at(qself.position);
+                match trait_.last() {
+                    Some(trait_) => format!(
+                        "<{} as {}>::{}",
+                        format_type_name(&qself.ty),
+                        trait_,
+                        assoc.join("::")
+                    ),
+                    None => format!("<{}>::{}", format_type_name(&qself.ty), assoc.join("::")),
+                }
+            }
+            None => format_path(&path.path),
+        },
         syn::Type::Reference(ref_) => {
             let prefix = match &ref_.lifetime {
                 None => if ref_.mutability.is_some() {
@@ -114,9 +615,9 @@ fn format_type_name(self_ty: &syn::Type) -> String {
                 .to_string(),
                 Some(lifetime) => {
                     if ref_.mutability.is_some() {
-                        format!("&{} mut ", lifetime.to_string())
+                        format!("&{} mut ", lifetime)
                     } else {
-                        format!("&{} ", lifetime.to_string())
+                        format!("&{} ", lifetime)
                     }
                 }
             };
@@ -124,18 +625,16 @@ fn format_type_name(self_ty: &syn::Type) -> String {
             format!("{}{}", prefix, format_type_name(&ref_.elem))
         }
         syn::Type::Slice(slice) => format!("[{}]", format_type_name(&slice.elem)),
+        syn::Type::Array(array) => format!(
+            "[{}; {}]",
+            format_type_name(&array.elem),
+            format_const_expr(&array.len)
+        ),
         syn::Type::TraitObject(trait_obj) => {
-            let mut trait_bound = "dyn ".to_string();
-            for (idx, bound) in trait_obj.bounds.iter().enumerate() {
-                if idx > 0 {
-                    trait_bound += " + ";
-                }
-
-                if let syn::TypeParamBound::Trait(trait_) = bound {
-                    trait_bound += &path_ident(&trait_.path);
-                }
-            }
-            trait_bound
+            format!("dyn {}", format_bounds(trait_obj.bounds.iter()))
+        }
+        syn::Type::ImplTrait(impl_trait) => {
+            format!("impl {}", format_bounds(impl_trait.bounds.iter()))
         }
         syn::Type::Tuple(tuple) => {
             let mut tuple_str = "(".to_string();
@@ -151,9 +650,131 @@ fn format_type_name(self_ty: &syn::Type) -> String {
         syn::Type::Paren(paren) => {
             format!("({})", format_type_name(&paren.elem))
         }
+        syn::Type::Group(group) => format_type_name(&group.elem),
         syn::Type::Ptr(ptr) => {
-            format!("*{}", format_type_name(&ptr.elem))
+            let mutability = if ptr.mutability.is_some() {
+                "mut"
+            } else {
+                "const"
+            };
+            format!("*{} {}", mutability, format_type_name(&ptr.elem))
+        }
+        syn::Type::BareFn(bare_fn) => {
+            let inputs: Vec<String> = bare_fn
+                .inputs
+                .iter()
+                .map(|arg| format_type_name(&arg.ty))
+                .collect();
+            let mut formatted = format!(
+                "{}fn({})",
+                format_bound_lifetimes(bare_fn.lifetimes.as_ref()),
+                inputs.join(", ")
+            );
+            if let syn::ReturnType::Type(_, output) = &bare_fn.output {
+                formatted += &format!(" -> {}", format_type_name(output));
+            }
+            formatted
         }
-        _ => unimplemented!("format type formatting for {:#?}", self_ty),
+        syn::Type::Never(_) => "!".to_string(),
+        syn::Type::Infer(_) => "_".to_string(),
+        syn::Type::Macro(macro_) => format!("{}!(..)", path_ident(&macro_.mac.path)),
+        _ => "..".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_file() {
+        let assert_scope = |path: &str, expected: &str| {
+            assert_eq!(Scope::from_file(Path::new(path)).to_string(), expected);
+        };
+
+        assert_scope("src/lib.rs", "crate");
+        assert_scope("src/main.rs", "crate");
+        assert_scope("gui/src/widget.rs", "crate::widget");
+        assert_scope("src/gui/widget/mod.rs", "crate::gui::widget");
+        assert_scope("src/gui/widget/button.rs", "crate::gui::widget::button");
+        assert_scope("src/bin/tool.rs", "crate");
+        assert_scope("src/bin/tool/main.rs", "crate");
+        assert_scope("src/bin/tool/args.rs", "crate::args");
+        assert_scope("tests/widget.rs", "crate");
+    }
+
+    #[test]
+    fn path() {
+        let mut scope = Scope::from_file(Path::new("src/gui/widget.rs"));
+        scope.push(ScopeItem::struct_impl("Button"));
+        assert_eq!(scope.to_string(), "crate::gui::widget::impl Button");
+        assert_eq!(scope.kind(), "impl");
+
+        scope.pop();
+        assert_eq!(scope.to_string(), "crate::gui::widget");
+        scope.pop();
+        scope.pop();
+        scope.pop();
+        assert_eq!(scope, Scope::crate_root());
+
+        let scope = "crate::gui::widget::impl Button";
+        assert!(matches(scope, "Button"));
+        assert!(matches(scope, "impl Button"));
+        assert!(matches(scope, "widget::Button"));
+        assert!(matches(scope, "crate::gui::widget::impl Button"));
+        assert!(!matches(scope, "net::Button"));
+        assert!(!matches(scope, "widget"));
+        assert!(!matches("crate::net::impl Button", "widget::Button"));
+    }
+
+    #[test]
+    fn items() {
+        let file = syn::parse_file(
+            r#"mod gui {}
+enum Kind { A }
+union Bits { a: u8 }
+type Alias = u8;
+use std::fmt;
+extern "C" {}
+impl<'a, T: Clone + 'a, const N: usize> Buffer<'a, T, N> where T: fmt::Debug {}
+impl<T> From<[T; 2]> for Pair<T> where for<'a> &'a T: Into<u8> {}
+impl<T: ?Sized> !Send for Ptr<*const T> {}
+impl Iterator for Box<dyn Fn(u8) -> bool> {}
+"#,
+        )
+        .unwrap();
+
+        let items: Vec<String> = file
+            .items
+            .iter()
+            .map(|item| ScopeItem::from(item).to_string())
+            .collect();
+        assert_eq!(
+            items,
+            vec![
+                "gui",
+                "enum Kind",
+                "union Bits",
+                "type Alias",
+                "use",
+                "extern \"C\"",
+                "impl<'a, T: Clone + 'a, const N: usize> Buffer<'a, T, N> where T: Debug",
+                "impl<T> From<[T; 2]> for Pair<T> where for<'a> &'a T: Into<u8>",
+                "impl<T: ?Sized> !Send for Ptr<*const T>",
+                "impl Iterator for Box<dyn Fn(u8) -> bool>",
+            ],
+        );
+
+        let scope = Scope::crate_root().join(ScopeItem::from(&file.items[6]));
+        assert!(matches(&scope.to_string(), "Buffer"));
+        assert!(matches(&scope.to_string(), "crate::impl Buffer"));
+
+        let mut scope = Scope::crate_root().join(ScopeItem::from(&file.items[7]));
+        assert!(matches(&scope.to_string(), "impl From for Pair"));
+        assert!(!scope.is_in_body());
+        scope.push(ScopeItem::Fn("from".to_string()));
+        scope.push(ScopeItem::Fn("nested".to_string()));
+        assert!(scope.is_in_body());
+        assert!(matches(&scope.to_string(), "Pair::fn from::nested"));
     }
 }
@@ -0,0 +1,335 @@
+//! Statistics about the renaming candidates.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    io::{self, Write},
+    path::PathBuf,
+};
+
+use crate::report::{json_string, Record, SkipReason};
+
+/// Number of renames listed in the most frequent renames.
+pub const TOP_RENAMES: usize = 10;
+
+/// Numbers of renamed and skipped candidates.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Counts {
+    pub renamed: usize,
+    pub skipped: usize,
+}
+
+impl Counts {
+    fn add(&mut self, is_skipped: bool) {
+        if is_skipped {
+            self.skipped += 1;
+        } else {
+            self.renamed += 1;
+        }
+    }
+}
+
+/// Statistics about the renaming candidates.
+///
+/// Renamed candidates are counted per renaming rule, skipped candidates per
+/// reason. All candidates are counted per scope kind and per file.
+#[derive(Debug, Default)]
+pub struct Statistics {
+    pub total: Counts,
+    pub rules: BTreeMap<String, usize>,
+    pub skip_reasons: BTreeMap<String, usize>,
+    pub scope_kinds: BTreeMap<&'static str, Counts>,
+    pub files: BTreeMap<PathBuf, Counts>,
+    /// Number of occurrences of each `(name, new_name)` rename.
+    pub renames: BTreeMap<(String, String), usize>,
+}
+
+impl Statistics {
+    /// Computes the [`Statistics`] for the given `records`.
+    pub fn new<'a>(records: impl IntoIterator<Item = &'a Record>) -> Self {
+        let mut stats = Statistics::default();
+        for record in records {
+            let candidate = &record.candidate;
+            let is_skipped = candidate.is_skipped();
+
+            stats.total.add(is_skipped);
+            stats
+                .scope_kinds
+                .entry(candidate.scope.kind())
+                .or_default()
+                .add(is_skipped);
+            stats
+                .files
+                .entry(record.path.clone())
+                .or_default()
+                .add(is_skipped);
+
+            match (&candidate.skip, &candidate.renaming) {
+                (Some(skip), _) => {
+                    let reason = match skip {
+                        SkipReason::NonGetter(reason) => format!("{:?}", reason),
+                        SkipReason::RenameError(err) => format!("{:?}", err),
+                    };
+                    *stats.skip_reasons.entry(reason).or_default() += 1;
+                }
+                (None, Some(renaming)) => {
+                    *stats
+                        .rules
+                        .entry(format!("{:?}", renaming.rule))
+                        .or_default() += 1;
+                    *stats
+                        .renames
+                        .entry((candidate.name.clone(), renaming.new_name.clone()))
+                        .or_default() += 1;
+                }
+                (None, None) => (),
+            }
+        }
+
+        stats
+    }
+
+    /// Returns the `count` most frequent renames as `(name, new_name, occurrences)`.
+    pub fn top_renames(&self, count: usize) -> Vec<(&str, &str, usize)> {
+        let mut renames: Vec<(&str, &str, usize)> = self
+            .renames
+            .iter()
+            .map(|((name, new_name), occurrences)| (name.as_str(), new_name.as_str(), *occurrences))
+            .collect();
+        // Most frequent first, then by name
+        renames.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| a.0.cmp(b.0)));
+        renames.truncate(count);
+
+        renames
+    }
+
+    /// Writes the statistics to the given `writer` as a summary table.
+    pub fn write_to(&self, writer: &mut dyn Write) -> io::Result<()> {
+        writeln!(
+            writer,
+            "\nCandidates: {} renamed, {} skipped",
+            self.total.renamed, self.total.skipped,
+        )?;
+
+        write_table(
+            writer,
+            "Renaming rule",
+            self.rules
+                .iter()
+                .map(|(rule, count)| (rule.clone(), count.to_string())),
+        )?;
+        write_table(
+            writer,
+            "Skip reason",
+            self.skip_reasons
+                .iter()
+                .map(|(reason, count)| (reason.clone(), count.to_string())),
+        )?;
+        write_table(
+            writer,
+            "Scope kind",
+            self.scope_kinds
+                .iter()
+                .map(|(kind, counts)| (kind.to_string(), counts_cell(counts))),
+        )?;
+        write_table(
+            writer,
+            "File",
+            self.files
+                .iter()
+                .map(|(path, counts)| (path.display().to_string(), counts_cell(counts))),
+        )?;
+        write_table(
+            writer,
+            "Most frequent renames",
+            self.top_renames(TOP_RENAMES)
+                .into_iter()
+                .map(|(name, new_name, count)| {
+                    (format!("{}() -> {}()", name, new_name), count.to_string())
+                }),
+        )?;
+
+        writer.flush()
+    }
+
+    /// Returns the statistics as a JSON object.
+    pub(crate) fn to_json(&self) -> String {
+        let mut json = format!(
+            "{{\n    \"renamed\": {},\n    \"skipped\": {},\n    \"rules\": {},\n    \
+             \"skip_reasons\": {},\n",
+            self.total.renamed,
+            self.total.skipped,
+            json_counts(
+                self.rules
+                    .iter()
+                    .map(|(rule, count)| (rule.as_str(), *count))
+            ),
+            json_counts(
+                self.skip_reasons
+                    .iter()
+                    .map(|(reason, count)| (reason.as_str(), *count))
+            ),
+        );
+
+        let scope_kinds: Vec<String> = self
+            .scope_kinds
+            .iter()
+            .map(|(kind, counts)| {
+                format!("{}: {}", json_string(kind), json_renamed_skipped(counts))
+            })
+            .collect();
+        let _ = writeln!(json, "    \"scope_kinds\": {{{}}},", scope_kinds.join(", "));
+
+        let files: Vec<String> = self
+            .files
+            .iter()
+            .map(|(path, counts)| {
+                format!(
+                    "{}: {}",
+                    json_string(&path.to_string_lossy()),
+                    json_renamed_skipped(counts)
+                )
+            })
+            .collect();
+        let _ = writeln!(json, "    \"files\": {{{}}},", files.join(", "));
+
+        let renames: Vec<String> = self
+            .top_renames(TOP_RENAMES)
+            .into_iter()
+            .map(|(name, new_name, count)| {
+                format!(
+                    "{{\"name\": {}, \"new_name\": {}, \"count\": {}}}",
+                    json_string(name),
+                    json_string(new_name),
+                    count
+                )
+            })
+            .collect();
+        let _ = write!(json, "    \"top_renames\": [{}]\n  }}", renames.join(", "));
+
+        json
+    }
+}
+
+fn counts_cell(counts: &Counts) -> String {
+    format!("{} / {}", counts.renamed, counts.skipped)
+}
+
+fn write_table(
+    writer: &mut dyn Write,
+    title: &str,
+    rows: impl Iterator<Item = (String, String)>,
+) -> io::Result<()> {
+    let rows: Vec<(String, String)> = rows.collect();
+    if rows.is_empty() {
+        return Ok(());
+    }
+
+    let width = rows
+        .iter()
+        .map(|(label, _)| label.chars().count())
+        .chain(std::iter::once(title.chars().count()))
+        .max()
+        .unwrap_or_default();
+
+    writeln!(writer, "\n{}", title)?;
+    for (label, value) in rows {
+        writeln!(writer, "  {:<width$}  {:>9}", label, value, width = width)?;
+    }
+
+    Ok(())
+}
+
+fn json_counts<'a>(counts: impl Iterator<Item = (&'a str, usize)>) -> String {
+    let counts: Vec<String> = counts
+        .map(|(key, count)| format!("{}: {}", json_string(key), count))
+        .collect();
+    format!("{{{}}}", counts.join(", "))
+}
+
+fn json_renamed_skipped(counts: &Counts) -> String {
+    format!(
+        "{{\"renamed\": {}, \"skipped\": {}}}",
+        counts.renamed, counts.skipped
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        report::Candidate, Getter, GetterError, NonGetterReason, Report, Scope, ScopeItem,
+    };
+    use rules::RenameError;
+    use std::path::Path;
+
+    #[test]
+    fn counts() {
+        let scope = Scope::from(ScopeItem::struct_impl("Foo"));
+        let get_a = Getter::try_new("get_a".to_string(), false, 1).unwrap();
+        let get_type = Getter::try_new("get_type".to_string(), false, 2).unwrap();
+        let get_mut = GetterError {
+            name: "get_mut".to_string(),
+            err: RenameError::Reserved,
+            line: 3,
+        };
+
+        let report = Report::default();
+        report.add(
+            Path::new("src/lib.rs"),
+            "",
+            vec![
+                Candidate::renamed(&get_a, &scope),
+                Candidate::renamed(&get_type, &scope),
+                Candidate::rejected(&get_mut, &scope),
+            ],
+        );
+        report.add(
+            Path::new("src/foo.rs"),
+            "",
+            vec![
+                Candidate::renamed(&get_a, &Scope::from(ScopeItem::Fn("f".to_string()))),
+                Candidate::skipped(&get_a, &scope, NonGetterReason::MultipleArgs),
+            ],
+        );
+
+        let stats = report.statistics();
+        assert_eq!(
+            stats.total,
+            Counts {
+                renamed: 3,
+                skipped: 2
+            }
+        );
+        assert_eq!(stats.rules.get("Regular"), Some(&2));
+        assert_eq!(stats.rules.get("Substituted"), Some(&1));
+        assert_eq!(stats.skip_reasons.get("Reserved"), Some(&1));
+        assert_eq!(stats.skip_reasons.get("MultipleArgs"), Some(&1));
+        assert_eq!(
+            stats.scope_kinds.get("impl"),
+            Some(&Counts {
+                renamed: 2,
+                skipped: 2
+            })
+        );
+        assert_eq!(
+            stats.files.get(Path::new("src/foo.rs")),
+            Some(&Counts {
+                renamed: 1,
+                skipped: 1
+            })
+        );
+        assert_eq!(
+            stats.top_renames(2),
+            [("get_a", "a", 2), ("get_type", "type_", 1)],
+        );
+
+        let mut output = Vec::new();
+        stats.write_to(&mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("Candidates: 3 renamed, 2 skipped"));
+        assert!(output
+            .lines()
+            .any(|line| line.split_whitespace().eq(["get_a()", "->", "a()", "2"])));
+    }
+}
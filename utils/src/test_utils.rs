@@ -0,0 +1,25 @@
+//! Helpers for the tests of the `fix-getters` crates.
+//!
+//! Requires `feature` **`test-utils`**, except for the tests of this crate.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+/// Returns an empty temporary directory for the `test` of the crate `crate_name`.
+///
+/// The directory is cleared if it already exists, e.g. from a previous run.
+pub fn temp_dir(crate_name: &str, test: &str) -> PathBuf {
+    let root = env::temp_dir().join(crate_name).join(test);
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(&root).unwrap();
+
+    root
+}
+
+/// Writes the `content` to the file at `path`, creating the parent directories.
+pub fn write(path: &Path, content: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}